@@ -37,13 +37,20 @@ impl MethodError {
     }
 }
 
+impl From<Box<dyn std::error::Error + Send + Sync>> for MethodError {
+    fn from(err: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        Self(err)
+    }
+}
+
 #[derive(Debug, Error)]
-#[error(transparent)]
+#[error("{0}")]
 /// An error happened while executing the task within the workflow.
 ///
 /// These errors only happen at runtime, never at the planning stage
-/// of the worker.
-pub struct IOError(Box<dyn std::error::Error + Send + Sync>);
+/// of the worker. The original error is kept as the [`source`](`std::error::Error::source`)
+/// of the `IOError` and can be recovered by downcasting.
+pub struct IOError(#[source] Box<dyn std::error::Error + Send + Sync>);
 
 impl IOError {
     pub fn new<E: std::error::Error + Send + Sync + 'static>(err: E) -> Self {
@@ -51,6 +58,12 @@ impl IOError {
     }
 }
 
+impl From<Box<dyn std::error::Error + Send + Sync>> for IOError {
+    fn from(err: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        Self(err)
+    }
+}
+
 impl Deref for IOError {
     type Target = Box<dyn std::error::Error + Send + Sync>;
 
@@ -30,7 +30,9 @@
 //! Some commonly used extractors are
 //!
 //! ```rust
-//! use mahler::extract::{View, Pointer, Args, Target, System, Res};
+//! use mahler::extract::{
+//!     View, ViewRef, Pointer, PointerState, Previous, Args, Target, RawTarget, System, Res,
+//! };
 //!
 //! struct MyConnection;
 //! struct MySystemState;
@@ -39,10 +41,22 @@
 //! // state for the handler.
 //! fn view(state: View<u32>) {}
 //!
+//! // `ViewRef` is a read-only `View`, it avoids copying the
+//! // state but it cannot be used to make changes
+//! fn view_ref(state: ViewRef<u32>) {}
+//!
 //! // `Pointer` is like `View`, except the pointed value can be null
 //! // for instance, in the case of `create` operations
 //! fn pointer(state: Pointer<u32>) {}
 //!
+//! // `PointerState` tells whether the value or its parent are
+//! // missing from the state
+//! fn pointer_state(state: PointerState<u32>) {}
+//!
+//! // `Previous` gives you the value at the path before the
+//! // workflow started
+//! fn previous(Previous(initial): Previous<u32>) {}
+//!
 //! // `Args` gives you the path arguments and deserializes them
 //! fn args(Args(counter_name): Args<String>) {}
 //!
@@ -50,6 +64,10 @@
 //! // note that `delete` operations do not have a target.
 //! fn target(Target(tgt): Target<u32>) {}
 //!
+//! // `RawTarget` gives you the target value without
+//! // deserializing it
+//! fn raw_target(RawTarget(tgt): RawTarget) {}
+//!
 //! // `System` provides a view into the top level system state.
 //! // A Job using the System extractor is not parallelizable
 //! fn system(System(state): System<MySystemState>) {}
@@ -93,6 +111,7 @@
 
 mod args;
 mod path;
+mod previous;
 mod res;
 mod system;
 mod target;
@@ -100,6 +119,7 @@ mod view;
 
 pub use args::*;
 pub use path::*;
+pub use previous::*;
 pub use res::*;
 pub use system::*;
 pub use target::*;
@@ -0,0 +1,75 @@
+use anyhow::Context as AnyhowCxt;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::ops::Deref;
+
+use crate::errors::ExtractionError;
+use crate::system::System;
+use crate::task::{Context, FromSystem};
+
+#[derive(Debug)]
+/// Extracts the value at the task path before the workflow execution
+///
+/// When a workflow applies multiple changes to the same path, [View](`super::View`) provides
+/// the value at the time the task is called, while `Previous` provides the value as it was
+/// before planning started. If the path did not exist, the value is deserialized from `null`,
+/// use `Previous<Option<T>>` for paths that may not exist.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use mahler::{
+///     extract::{Previous, View},
+///     task::{Handler, update},
+///     worker::{Worker, Ready}
+/// };
+///
+/// fn plus_one(mut counter: View<i32>, Previous(initial): Previous<i32>) -> View<i32> {
+///     // only allow to increase the counter by 2 in a single workflow
+///     if *counter - initial < 2 {
+///         *counter += 1;
+///     }
+///     counter
+/// }
+///
+/// let worker: Worker<i32, Ready> = Worker::new()
+///     .job("", update(plus_one))
+///     .initial_state(0)
+///     .unwrap();
+/// ```
+///
+/// # Errors
+///
+/// Initializing the extractor will fail if the previous value cannot be deserialized
+/// into the given type `<T>`.
+pub struct Previous<T>(pub T);
+
+impl<T: DeserializeOwned> FromSystem for Previous<T> {
+    type Error = ExtractionError;
+
+    fn from_system(system: &System, context: &Context) -> Result<Self, Self::Error> {
+        let value = context
+            .path
+            .as_ref()
+            .resolve(system.baseline())
+            .unwrap_or(&Value::Null);
+
+        let previous = serde_json::from_value::<T>(value.clone()).with_context(|| {
+            format!(
+                "Failed to deserialize previous value {value} at path '{}' into {}",
+                context.path,
+                std::any::type_name::<T>()
+            )
+        })?;
+
+        Ok(Previous(previous))
+    }
+}
+
+impl<T> Deref for Previous<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
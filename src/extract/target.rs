@@ -1,5 +1,6 @@
 use anyhow::Context as AnyhowCxt;
 use serde::de::DeserializeOwned;
+use serde_json::Value;
 use std::ops::Deref;
 
 use crate::errors::ExtractionError;
@@ -99,3 +100,76 @@ impl<T> Deref for Target<T> {
         &self.0
     }
 }
+
+#[derive(Debug)]
+/// Extracts the target state for tasks as a raw JSON value
+///
+/// Unlike [`Target`], the target at the task path is not deserialized, which allows generic
+/// handlers to operate on values of arbitrary shape. The value is `null` if no target exists,
+/// for instance, for `delete` type jobs.
+///
+/// ```rust,no_run
+/// use mahler::{
+///     extract::{RawTarget, View},
+///     task::{Handler, update},
+///     worker::{Worker, Ready}
+/// };
+/// use serde_json::Value;
+///
+/// // copy the target at the path as is
+/// fn set_value(mut value: View<Value>, RawTarget(tgt): RawTarget) -> View<Value> {
+///     *value = tgt;
+///     value
+/// }
+///
+/// let worker: Worker<Value, Ready> = Worker::new()
+///     .job("/{foo}", update(set_value))
+///     .initial_state(serde_json::json!({}))
+///     .unwrap();
+/// ```
+pub struct RawTarget(pub Value);
+
+impl FromContext for RawTarget {
+    type Error = ExtractionError;
+
+    fn from_context(context: &Context) -> Result<Self, Self::Error> {
+        Ok(RawTarget(context.target.clone()))
+    }
+}
+
+impl FromSystem for RawTarget {
+    type Error = ExtractionError;
+
+    fn from_system(_: &System, context: &Context) -> Result<Self, Self::Error> {
+        Self::from_context(context)
+    }
+}
+
+impl Deref for RawTarget {
+    type Target = Value;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_extracts_the_target_as_a_raw_value() {
+        let target = json!({"config": {"name": "foo", "ports": [80, 443]}});
+        let context = Context::new()
+            .with_path("/services/one")
+            .with_target(target.clone());
+
+        let RawTarget(raw) = RawTarget::from_context(&context).unwrap();
+        assert_eq!(raw, target);
+
+        // No target exists for the context
+        let RawTarget(raw) = RawTarget::from_context(&Context::new()).unwrap();
+        assert_eq!(raw, Value::Null);
+    }
+}
@@ -1,4 +1,4 @@
-use anyhow::{anyhow, Context as AnyhowCtx};
+use anyhow::anyhow;
 use json_patch::{
     diff, AddOperation, CopyOperation, MoveOperation, Patch, PatchOperation, RemoveOperation,
     ReplaceOperation, TestOperation,
@@ -6,14 +6,15 @@ use json_patch::{
 use jsonptr::resolve::ResolveError;
 use jsonptr::PointerBuf;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
 use crate::errors::ExtractionError;
 use crate::path::Path;
 use crate::system::System;
-use crate::task::{Context, Effect, Error, FromSystem, IntoResult};
+use crate::task::{Context, Effect, Error, FromSystem, FromSystemRef, IntoResult};
 
 /// Extracts a pointer to a sub-element of the global state indicated
 /// by the path.
@@ -59,7 +60,7 @@ use crate::task::{Context, Effect, Error, FromSystem, IntoResult};
 ///
 /// Initializing the extractor will fail if the path assigned to the job cannot be resolved or the
 /// value pointed by the path cannot be deserialized into type `<T>`
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Pointer<T> {
     initial: Value,
     state: Option<T>,
@@ -67,9 +68,9 @@ pub struct Pointer<T> {
 }
 
 impl<T> Pointer<T> {
-    // The only way to create a pointer is via the
-    // from_system method
-    fn new(initial: Value, state: Option<T>, path: Path) -> Self {
+    // Pointers are only created by the from_system method
+    // or when converting an effect created with `Effect::from_value`
+    pub(crate) fn new(initial: Value, state: Option<T>, path: Path) -> Self {
         Pointer {
             initial,
             state,
@@ -82,6 +83,44 @@ impl<T> Pointer<T> {
         self.insert(value.into())
     }
 
+    /// Take the value out of the pointer, leaving it unassigned
+    ///
+    /// After this call, the value at the location indicated by the path will be removed, this
+    /// is useful to move a value from one location to another.
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    ///
+    /// use mahler::extract::Pointer;
+    /// use mahler::task::prelude::*;
+    /// use mahler::worker::Worker;
+    ///
+    /// type Counters = HashMap<String, i32>;
+    ///
+    /// fn take_counter(mut counter: Pointer<i32>) -> Pointer<i32> {
+    ///     // the value is returned to the caller and the pointer is left unassigned
+    ///     assert_eq!(counter.take(), Some(1));
+    ///     counter
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker = Worker::new()
+    ///     .job("/{counter}", update(take_counter))
+    ///     .initial_state::<Counters>(Counters::from([("one".into(), 1), ("two".into(), 2)]))
+    ///     .unwrap();
+    ///
+    /// // the task results in a `remove` operation on `/one`
+    /// let state = worker
+    ///     .run_task(take_counter.with_arg("counter", "one"))
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(state, Counters::from([("two".into(), 2)]));
+    /// # })
+    /// ```
+    pub fn take(&mut self) -> Option<T> {
+        self.state.take()
+    }
+
     /// Clear the value at the location indicated by the path
     pub fn unassign(mut self) -> Self {
         self.state.take();
@@ -97,45 +136,76 @@ impl<T> Pointer<T> {
     }
 }
 
+/// Resolve the location indicated by the context path without deserializing it
+fn resolve<'a>(
+    root: &'a Value,
+    context: &Context,
+) -> Result<PointerState<&'a Value>, ExtractionError> {
+    let json_ptr = context.path.as_ref();
+
+    // Use the parent of the pointer unless we are at the root
+    let parent = json_ptr.parent().unwrap_or(json_ptr);
+
+    match parent.resolve(root) {
+        Ok(_) => {}
+        Err(ResolveError::NotFound { .. } | ResolveError::OutOfBounds { .. }) => {
+            return Ok(PointerState::ParentMissing)
+        }
+        Err(e) => {
+            // XXX: how can this happen?
+            Err(anyhow!(e).context(format!("Failed to resolve path {}", context.path)))?
+        }
+    }
+
+    // At this point we assume that if the pointer cannot be
+    // resolved is because the value does not exist yet unless
+    // the parent is a scalar
+    match json_ptr.resolve(root) {
+        Ok(value) => Ok(PointerState::Present(value)),
+        Err(ResolveError::NotFound { .. } | ResolveError::OutOfBounds { .. }) => {
+            Ok(PointerState::Absent)
+        }
+        Err(e) => {
+            // XXX: how can this happen?
+            Err(anyhow!(e).context(format!("Failed to resolve path {}", context.path)))?
+        }
+    }
+}
+
+/// Deserialize the value at the context path into type `<T>`
+fn deserialize<'a, T: Deserialize<'a>>(
+    value: &'a Value,
+    context: &Context,
+) -> Result<T, ExtractionError> {
+    // Track the path of the failing field to make nested
+    // deserialization errors easier to debug. Deserializing from the
+    // reference avoids cloning the sub-tree first
+    let state = serde_path_to_error::deserialize::<_, T>(value).map_err(|e| {
+        let field = e.path().to_string();
+        anyhow!(e.into_inner()).context(format!(
+            "Failed to deserialize {value} at path '{}' into {}, field '{field}'",
+            context.path,
+            std::any::type_name::<T>()
+        ))
+    })?;
+
+    Ok(state)
+}
+
 impl<T: DeserializeOwned> FromSystem for Pointer<T> {
     type Error = ExtractionError;
 
     fn from_system(system: &System, context: &Context) -> Result<Self, Self::Error> {
-        let json_ptr = context.path.as_ref();
-        let root = system.root();
-
-        // Use the parent of the pointer unless we are at the root
-        let parent = json_ptr.parent().unwrap_or(json_ptr);
-
-        // Try to resolve the parent or fail
-        // XXX: how can this happen?
-        parent
-            .resolve(root)
-            .with_context(|| format!("Failed to resolve path {}", context.path))?;
-
-        // At this point we assume that if the pointer cannot be
-        // resolved is because the value does not exist yet unless
-        // the parent is a scalar
-        let (state, initial): (Option<T>, Value) = match json_ptr.resolve(root) {
-            Ok(value) => (
-                Some(serde_json::from_value::<T>(value.clone()).with_context(|| {
-                    format!(
-                        "Failed to deserialize {value} into {}",
-                        std::any::type_name::<T>()
-                    )
-                })?),
-                value.clone(),
-            ),
-            Err(e) => match e {
-                ResolveError::NotFound { .. } => (None, Value::Null),
-                ResolveError::OutOfBounds { .. } => (None, Value::Null),
-                _ => {
-                    // XXX: how can this happen?
-                    return Err(
-                        anyhow!(e).context(format!("Failed to resolve path {}", context.path))
-                    )?;
-                }
-            },
+        let (state, initial): (Option<T>, Value) = match resolve(system.root(), context)? {
+            // The root always exists, a null root means there is no value
+            PointerState::Present(Value::Null) if context.path.as_ref().is_root() => {
+                (None, Value::Null)
+            }
+            PointerState::Present(value) => (Some(deserialize(value, context)?), value.clone()),
+            PointerState::Absent => (None, Value::Null),
+            PointerState::ParentMissing => {
+                return Err(anyhow!("Failed to resolve path {}", context.path))?;
+            }
         };
 
         Ok(Pointer::new(initial, state, context.path.clone()))
@@ -201,6 +271,74 @@ fn prepend_path(pointer: PointerBuf, patch: Patch) -> Patch {
     Patch(changes)
 }
 
+/// Describes whether the location indicated by the path exists in the system state
+///
+/// While [`Pointer`] is `None` whenever the pointed value does not exist, `PointerState` allows
+/// to tell apart a missing value from a missing parent, so handlers can decide whether to create
+/// the value or report an error.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_json::json;
+/// use mahler::System;
+/// use mahler::extract::PointerState;
+/// use mahler::task::{Context, FromSystem};
+///
+/// let system = System::try_from(json!({"counters": {"one": 1}})).unwrap();
+///
+/// let one = PointerState::<i32>::from_system(&system, &Context::new().with_path("/counters/one"));
+/// assert_eq!(one.unwrap(), PointerState::Present(1));
+///
+/// let two = PointerState::<i32>::from_system(&system, &Context::new().with_path("/counters/two"));
+/// assert_eq!(two.unwrap(), PointerState::Absent);
+///
+/// let other = PointerState::<i32>::from_system(&system, &Context::new().with_path("/other/one"));
+/// assert_eq!(other.unwrap(), PointerState::ParentMissing);
+/// ```
+///
+/// # Errors
+///
+/// Initializing the extractor will fail if the path cannot be resolved because a parent is a
+/// scalar value, or the value pointed by the path cannot be deserialized into type `<T>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PointerState<T> {
+    /// The value exists at the path
+    Present(T),
+    /// The parent of the path exists but the value does not
+    Absent,
+    /// The parent of the path does not exist
+    ParentMissing,
+}
+
+impl<T> PointerState<T> {
+    /// Return the value if present
+    pub fn into_option(self) -> Option<T> {
+        match self {
+            PointerState::Present(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> FromSystem for PointerState<T> {
+    type Error = ExtractionError;
+
+    fn from_system(system: &System, context: &Context) -> Result<Self, Self::Error> {
+        let state = match resolve(system.root(), context)? {
+            // The root always exists, a null root means there is no value
+            PointerState::Present(Value::Null) if context.path.as_ref().is_root() => {
+                PointerState::Absent
+            }
+            PointerState::Present(value) => PointerState::Present(deserialize(value, context)?),
+            PointerState::Absent => PointerState::Absent,
+            PointerState::ParentMissing => PointerState::ParentMissing,
+        };
+
+        Ok(state)
+    }
+}
+
 impl<T: Serialize> IntoResult<Patch> for Pointer<T> {
     fn into_result(self) -> Result<Patch, Error> {
         let before = self.initial;
@@ -219,6 +357,13 @@ impl<T: Serialize> IntoResult<Patch> for Pointer<T> {
                 path: self.path.into(),
                 value: after,
             })]),
+            // The root cannot be removed, so it is replaced with null instead
+            (_, Value::Null) if self.path.as_ref().is_root() => {
+                Patch(vec![PatchOperation::Replace(ReplaceOperation {
+                    path: self.path.into(),
+                    value: Value::Null,
+                })])
+            }
             (_, Value::Null) => Patch(vec![PatchOperation::Remove(RemoveOperation {
                 path: self.path.into(),
             })]),
@@ -280,7 +425,7 @@ impl<T, E> From<Pointer<T>> for Effect<Pointer<T>, E> {
 ///
 /// Initializing the extractor will fail if the path assigned to the job cannot be resolved (or it
 /// resolves to `Null`) or the value pointed by the path cannot be deserialized into type `<T>`
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct View<T>(Pointer<T>);
 
 impl<T: DeserializeOwned> FromSystem for View<T> {
@@ -327,6 +472,86 @@ impl<T, E> From<View<T>> for Effect<View<T>, E> {
     }
 }
 
+/// Extracts a read-only view of a sub-element of the global state indicated
+/// by the path.
+///
+/// Like [`View`], the `ViewRef` extractor expects that the location pointed by the Job path
+/// exists. The view borrows the system state for the lifetime `'a` and the value is deserialized
+/// directly from it, without cloning the sub-tree or keeping a copy of the original value to
+/// calculate changes. This makes `ViewRef` cheaper than `View` for reading large sub-trees.
+///
+/// Because it borrows the system, a `ViewRef` cannot be modified nor returned from a handler.
+/// Use [`View`] if the handler needs to make changes to the system.
+///
+/// ```rust
+/// use mahler::extract::{ViewRef, View, Target};
+///
+/// fn plus_one(
+///     mut counter: View<i32>,
+///     limit: ViewRef<i32>,
+///     Target(tgt): Target<i32>,
+/// ) -> View<i32> {
+///     if *counter < tgt && *counter < *limit {
+///         *counter += 1;
+///     }
+///     counter
+/// }
+/// ```
+///
+/// When used as a handler argument, `T` needs to implement [`DeserializeOwned`]. Outside of a
+/// handler, [`ViewRef::new`] accepts any type that can be deserialized from the borrowed state,
+/// allowing to read string leaves as `&'a str` with zero copies.
+///
+/// # Errors
+///
+/// Initializing the extractor will fail if the path assigned to the job cannot be resolved
+/// or the value pointed by the path cannot be deserialized into type `<T>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewRef<'a, T> {
+    value: T,
+    _system: PhantomData<&'a System>,
+}
+
+impl<'a, T: Deserialize<'a>> ViewRef<'a, T> {
+    /// Create a view of the value pointed by the context path, borrowing from the system state
+    pub fn new(system: &'a System, context: &Context) -> Result<Self, ExtractionError> {
+        match resolve(system.root(), context)? {
+            PointerState::Present(value) => Ok(ViewRef {
+                value: deserialize(value, context)?,
+                _system: PhantomData,
+            }),
+            _ => Err(anyhow!("Path {} does not exist", context.path).into()),
+        }
+    }
+}
+
+impl<T> ViewRef<'_, T> {
+    /// Return the inner value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: DeserializeOwned> FromSystemRef for ViewRef<'_, T> {
+    type Error = ExtractionError;
+    type Output<'a> = ViewRef<'a, T>;
+
+    fn from_system_ref<'a>(
+        system: &'a System,
+        context: &Context,
+    ) -> Result<ViewRef<'a, T>, Self::Error> {
+        ViewRef::new(system, context)
+    }
+}
+
+impl<T> Deref for ViewRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -377,6 +602,89 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_reports_the_path_of_the_field_that_failed_to_deserialize() {
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Config {
+            name: String,
+            limits: Limits,
+        }
+
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Limits {
+            cpu: Vec<u32>,
+        }
+
+        let system = System::try_from(json!({
+            "services": {
+                "one": {"name": "one", "limits": {"cpu": [1, "two"]}}
+            }
+        }))
+        .unwrap();
+
+        let err = View::<Config>::from_system(&system, &Context::new().with_path("/services/one"))
+            .unwrap_err();
+
+        let msg = err.to_string();
+        assert!(msg.contains("at path '/services/one'"), "{msg}");
+        assert!(msg.contains("field 'limits.cpu[1]'"), "{msg}");
+        assert!(msg.contains("invalid type: string \"two\""), "{msg}");
+    }
+
+    #[test]
+    fn it_removes_the_value_taken_from_a_pointer() {
+        let state = State {
+            numbers: HashMap::from([("one".to_string(), 1)]),
+        };
+        let system = System::try_from(state).unwrap();
+
+        let mut ptr: Pointer<i32> =
+            Pointer::from_system(&system, &Context::new().with_path("/numbers/one")).unwrap();
+
+        assert_eq!(ptr.take(), Some(1));
+        assert!(ptr.is_none());
+
+        let changes = ptr.into_result().unwrap();
+        assert_eq!(
+            changes,
+            serde_json::from_value::<Patch>(json!([
+              { "op": "remove", "path": "/numbers/one" },
+            ]))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn it_combines_changes_from_a_tuple_of_views() {
+        let mut numbers = HashMap::new();
+        numbers.insert("one".to_string(), 1);
+        numbers.insert("two".to_string(), 2);
+
+        let state = State { numbers };
+
+        let system = System::try_from(state).unwrap();
+
+        let mut one: View<i32> =
+            View::from_system(&system, &Context::new().with_path("/numbers/one")).unwrap();
+        let mut two: View<i32> =
+            View::from_system(&system, &Context::new().with_path("/numbers/two")).unwrap();
+
+        *one += 1;
+        *two += 1;
+
+        let changes = (one, two).into_result().unwrap();
+        assert_eq!(
+            changes,
+            serde_json::from_value::<Patch>(json!([
+              { "op": "replace", "path": "/numbers/one", "value": 2 },
+              { "op": "replace", "path": "/numbers/two", "value": 3 },
+            ]))
+            .unwrap()
+        );
+    }
+
     #[test]
     fn it_fails_if_pointed_path_is_invalid() {
         let mut numbers = HashMap::new();
@@ -397,6 +705,106 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_reads_a_leaf_with_a_view_ref_without_copying_it() {
+        use serde::de::{Deserializer, Visitor};
+        use std::fmt;
+
+        // Records whether the deserializer lent the string from the
+        // system state or had to provide an owned copy
+        #[derive(Debug, PartialEq)]
+        enum Source {
+            Borrowed,
+            Copied,
+        }
+
+        impl<'de> Deserialize<'de> for Source {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct SourceVisitor;
+
+                impl<'de> Visitor<'de> for SourceVisitor {
+                    type Value = Source;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        write!(f, "a string")
+                    }
+
+                    fn visit_borrowed_str<E>(self, _: &'de str) -> Result<Source, E> {
+                        Ok(Source::Borrowed)
+                    }
+
+                    fn visit_str<E>(self, _: &str) -> Result<Source, E> {
+                        Ok(Source::Copied)
+                    }
+
+                    fn visit_string<E>(self, _: String) -> Result<Source, E> {
+                        Ok(Source::Copied)
+                    }
+                }
+
+                deserializer.deserialize_str(SourceVisitor)
+            }
+        }
+
+        let system = System::try_from(json!({
+            "large": (0..1000).map(|i| i.to_string()).collect::<Vec<_>>(),
+            "name": "one"
+        }))
+        .unwrap();
+        let context = Context::new().with_path("/name");
+
+        let view = ViewRef::<Source>::from_system_ref(&system, &context).unwrap();
+        assert_eq!(*view, Source::Borrowed);
+
+        let view = ViewRef::<String>::from_system_ref(&system, &context).unwrap();
+        assert_eq!(view.into_inner(), "one");
+
+        // A borrowed leaf points to the string in the system state
+        let view = ViewRef::<&str>::new(&system, &context).unwrap();
+        assert_eq!(*view, "one");
+        assert!(std::ptr::eq(
+            view.into_inner(),
+            system.root()["name"].as_str().unwrap()
+        ));
+
+        // The value must exist
+        assert!(
+            ViewRef::<String>::from_system_ref(&system, &Context::new().with_path("/other"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn it_distinguishes_an_absent_value_from_a_missing_parent() {
+        let system = System::try_from(json!({"numbers": {"one": 1}, "list": ["a"]})).unwrap();
+
+        let state = |path: &str| {
+            PointerState::<serde_json::Value>::from_system(&system, &Context::new().with_path(path))
+                .unwrap()
+        };
+
+        assert_eq!(state("/numbers/one"), PointerState::Present(json!(1)));
+        assert_eq!(state("/numbers/two"), PointerState::Absent);
+        assert_eq!(state("/list/1"), PointerState::Absent);
+        assert_eq!(state("/none/two"), PointerState::ParentMissing);
+        assert_eq!(state("/list/3/one"), PointerState::ParentMissing);
+
+        // A scalar parent is still an error
+        assert!(PointerState::<i32>::from_system(
+            &system,
+            &Context::new().with_path("/numbers/one/two")
+        )
+        .is_err());
+
+        // Pointer is none for absent values but fails for missing parents
+        let ptr = Pointer::<i32>::from_system(&system, &Context::new().with_path("/numbers/two"))
+            .unwrap();
+        assert_eq!(*ptr, None);
+        assert!(
+            Pointer::<i32>::from_system(&system, &Context::new().with_path("/none/two")).is_err()
+        );
+    }
+
     #[test]
     fn it_assigns_a_value_to_pointed_path() {
         let mut numbers = HashMap::new();
@@ -524,6 +932,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_creates_and_deletes_a_value_at_the_root() {
+        let system = System::try_from(json!(null)).unwrap();
+
+        let mut ptr: Pointer<i32> =
+            Pointer::from_system(&system, &Context::new().with_path("")).unwrap();
+        assert_eq!(*ptr, None);
+
+        ptr.assign(1);
+        let changes = ptr.into_result().unwrap();
+        assert_eq!(
+            changes,
+            serde_json::from_value::<Patch>(json!([
+              { "op": "add", "path": "", "value": 1 },
+            ]))
+            .unwrap()
+        );
+
+        let system = System::try_from(json!(1)).unwrap();
+        let ptr: Pointer<i32> =
+            Pointer::from_system(&system, &Context::new().with_path("")).unwrap();
+        assert_eq!(*ptr, Some(1));
+
+        // The root cannot be removed, so it is replaced with null
+        let changes = ptr.unassign().into_result().unwrap();
+        assert_eq!(
+            changes,
+            serde_json::from_value::<Patch>(json!([
+              { "op": "replace", "path": "", "value": null },
+            ]))
+            .unwrap()
+        );
+    }
+
     #[test]
     fn it_extracts_an_existing_value_on_a_vec() {
         let state = StateVec {
@@ -101,6 +101,10 @@
 //! }
 //! ```
 //!
+//! By default, numbers in the state are limited to the range of 64-bit integers and floats.
+//! Install mahler with the `arbitrary_precision` feature to enable the `serde_json` feature of
+//! the same name, so big integers and exact decimals are preserved through the system state.
+//!
 //! Mahler provides another mechanism for accessing read-only, non-serializable resources from
 //! jobs.
 //!
@@ -471,6 +475,9 @@ pub mod task;
 pub mod worker;
 pub mod workflow;
 
+pub use planner::{Distance, NumberPolicy};
+pub use system::System;
+
 // TODO: this should not be exported from this crate.
 // It would more sense to re-export it, including the seq
 // and dag macros, from a "mahler-test" crate
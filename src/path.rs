@@ -100,6 +100,68 @@ impl PathArgs {
     }
 }
 
+impl PathArgs {
+    /// Replace the `{param}` and `{*param}` placeholders in the route with the argument values
+    ///
+    /// Placeholders are replaced in a single pass over the route, so argument values are used
+    /// verbatim even if they contain braces or text that looks like another placeholder. Escaped
+    /// `{{` and `}}` are converted to literal braces.
+    ///
+    /// Returns the expanded route and the keys of the arguments used, or the list of
+    /// placeholders with no matching argument.
+    pub fn expand(&self, route: &str) -> Result<(String, Vec<Arc<str>>), Vec<String>> {
+        let mut final_route = String::new();
+        let mut used_keys = Vec::new();
+        let mut missing_args = Vec::new();
+        let mut chars = route.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c == '{' && chars.peek() == Some(&'{') {
+                chars.next(); // Skip second '{'
+                final_route.push('{');
+            } else if c == '}' && chars.peek() == Some(&'}') {
+                chars.next(); // Skip second '}'
+                final_route.push('}');
+            } else if c == '{' {
+                let mut placeholder = String::from("{");
+                while let Some(&next) = chars.peek() {
+                    placeholder.push(next);
+                    chars.next();
+                    if next == '}' {
+                        break;
+                    }
+                }
+
+                let key = placeholder
+                    .strip_prefix('{')
+                    .and_then(|p| p.strip_suffix('}'))
+                    .map(|p| p.strip_prefix('*').unwrap_or(p));
+
+                match key.and_then(|k| self.iter().find(|(key, _)| key.as_ref() == k)) {
+                    Some((k, v)) => {
+                        used_keys.push(k.clone());
+                        final_route.push_str(v);
+                    }
+                    None => {
+                        if placeholder.ends_with('}') {
+                            missing_args.push(placeholder.clone());
+                        }
+                        final_route.push_str(&placeholder);
+                    }
+                }
+            } else {
+                final_route.push(c);
+            }
+        }
+
+        if !missing_args.is_empty() {
+            return Err(missing_args);
+        }
+
+        Ok((final_route, used_keys))
+    }
+}
+
 impl Display for PathArgs {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let entries = self
@@ -127,17 +189,6 @@ impl DerefMut for PathArgs {
     }
 }
 
-impl From<matchit::Params<'_, '_>> for PathArgs {
-    fn from(params: matchit::Params) -> PathArgs {
-        let params: Vec<(Arc<str>, String)> = params
-            .iter()
-            .map(|(k, v)| (Arc::from(k), String::from(v)))
-            .collect();
-
-        PathArgs(params)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,8 +1,9 @@
-use json_patch::{diff, Patch, PatchOperation, RemoveOperation, ReplaceOperation};
-use jsonptr::Pointer;
+use json_patch::{diff, AddOperation, Patch, PatchOperation, RemoveOperation, ReplaceOperation};
+use jsonptr::{Pointer, PointerBuf};
 use serde_json::Value;
 use std::collections::btree_set::Iter;
-use std::fmt::{self, Display};
+use std::fmt::{self, Debug, Display};
+use std::sync::Arc;
 use std::{
     cmp::Ordering,
     collections::{BTreeSet, LinkedList},
@@ -11,12 +12,31 @@ use std::{
 use crate::task::Operation as JobOperation;
 
 #[derive(Debug)]
-pub struct Distance(BTreeSet<Operation>);
+/// The set of changes between a state and a target
+///
+/// This is used by the planner to look for jobs that are applicable to the differences between
+/// the current state of the system and the target state. It can also be used to write custom
+/// heuristics or to report progress.
+///
+/// ```rust
+/// use mahler::Distance;
+/// use serde_json::json;
+///
+/// let distance = Distance::new(&json!({"a": 1, "b": 1}), &json!({"a": 2, "b": 2}));
+/// assert_eq!(distance.count(), 2);
+/// assert_eq!(distance.paths().collect::<Vec<&str>>(), vec!["/a", "/b"]);
+/// ```
+pub struct Distance {
+    // All operations that could be used to reach the target
+    operations: BTreeSet<Operation>,
+    // Paths for the differing values between the state and target
+    changes: Vec<PointerBuf>,
+}
 
 impl Display for Distance {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "[")?;
-        for (i, op) in self.0.iter().enumerate() {
+        for (i, op) in self.operations.iter().enumerate() {
             if i > 0 {
                 write!(f, ", ")?;
             }
@@ -42,12 +62,15 @@ impl Distance {
     /// The distance encodes all the possible operations that can be used to move
     /// between two states
     pub fn new(src: &Value, tgt: &Value) -> Distance {
-        let mut distance = Distance(BTreeSet::new());
+        let mut distance = Distance {
+            operations: BTreeSet::new(),
+            changes: Vec::new(),
+        };
 
         // calculate differences between the system root and
         // the target
         let Patch(changes) = diff(src, tgt);
-        for op in changes {
+        for op in changes.into_iter().map(|op| root_operation(src, op)) {
             // For every operation on the list of changes
             let path = op.path();
 
@@ -91,22 +114,84 @@ impl Distance {
             }
 
             // Finally insert the actual operation
+            distance.changes.push(path.to_buf());
             distance.insert(Operation::from(op));
         }
 
         distance
     }
 
+    /// Calculate the distance between some state and target, treating numbers that
+    /// differ by at most `epsilon` as equal
+    ///
+    /// This is useful for floating point values that may not compare exactly after
+    /// arithmetic operations or serialization round-trips.
+    ///
+    /// ```rust
+    /// use mahler::Distance;
+    /// use serde_json::json;
+    ///
+    /// let distance = Distance::with_epsilon(&json!({"a": 0.1 + 0.2}), &json!({"a": 0.3}), 1e-9);
+    /// assert!(distance.is_empty());
+    /// ```
+    pub fn with_epsilon(src: &Value, tgt: &Value, epsilon: f64) -> Distance {
+        Distance::with_policy(src, tgt, NumberPolicy::Epsilon(epsilon))
+    }
+
+    /// Calculate the distance between some state and target, comparing numbers
+    /// according to the given [`NumberPolicy`]
+    ///
+    /// ```rust
+    /// use mahler::{Distance, NumberPolicy};
+    /// use serde_json::json;
+    ///
+    /// let distance = Distance::with_policy(&json!({"a": 2}), &json!({"a": 2.4}), NumberPolicy::RoundToInt);
+    /// assert!(distance.is_empty());
+    /// ```
+    pub fn with_policy(src: &Value, tgt: &Value, policy: NumberPolicy) -> Distance {
+        Distance::with_comparators(src, tgt, policy, &PathComparators::default())
+    }
+
+    /// Calculate the distance between some state and target, comparing numbers according
+    /// to the given policy and values at matching paths with the given comparators
+    pub(crate) fn with_comparators(
+        src: &Value,
+        tgt: &Value,
+        policy: NumberPolicy,
+        comparators: &PathComparators,
+    ) -> Distance {
+        if policy == NumberPolicy::Exact && comparators.is_empty() {
+            return Distance::new(src, tgt);
+        }
+
+        // Replace target values considered equal by the policy or comparators
+        // with the state value so they are not reported as changes
+        let mut tgt = tgt.clone();
+        tolerate(src, &mut tgt, policy, comparators, &mut Vec::new());
+        Distance::new(src, &tgt)
+    }
+
     fn insert(&mut self, o: Operation) {
-        self.0.insert(o);
+        self.operations.insert(o);
     }
 
+    /// Return `true` if the state and target are equal
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.operations.is_empty()
+    }
+
+    /// Return the number of differing values between the state and target
+    pub fn count(&self) -> usize {
+        self.changes.len()
     }
 
-    pub fn iter(&self) -> Iter<Operation> {
-        self.0.iter()
+    /// Return the paths of the differing values between the state and target
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().map(|path| path.as_str())
+    }
+
+    pub(crate) fn iter(&self) -> Iter<'_, Operation> {
+        self.operations.iter()
     }
 
     fn insert_remove_ops(&mut self, path: &Pointer, value: &Value) {
@@ -154,7 +239,18 @@ impl Operation {
         self.0.path()
     }
 
+    /// Return true if a job with the given operation can be used to
+    /// reduce this distance operation
+    ///
+    /// Jobs defined with [`JobOperation::Any`] match every operation, while jobs
+    /// defined with [`JobOperation::None`] never match.
     pub fn matches(&self, op: &JobOperation) -> bool {
+        match op {
+            JobOperation::Any => return true,
+            JobOperation::None => return false,
+            _ => {}
+        }
+
         match self.0 {
             PatchOperation::Add(..) => op == &JobOperation::Create,
             PatchOperation::Replace(..) => op == &JobOperation::Update,
@@ -189,6 +285,225 @@ impl From<PatchOperation> for Operation {
     }
 }
 
+// A diff always replaces the root. As a missing value is represented as null,
+// replacing a null root is reported as an add, and replacing the root with null as
+// a remove, so create and delete jobs can be used at the root
+fn root_operation(src: &Value, op: PatchOperation) -> PatchOperation {
+    match op {
+        PatchOperation::Replace(ReplaceOperation { path, value }) if path.is_root() => {
+            if src.is_null() {
+                PatchOperation::Add(AddOperation { path, value })
+            } else if value.is_null() {
+                PatchOperation::Remove(RemoveOperation { path })
+            } else {
+                PatchOperation::Replace(ReplaceOperation { path, value })
+            }
+        }
+        op => op,
+    }
+}
+
+// Get the value of an integer number without loss of precision
+fn as_i128(n: &serde_json::Number) -> Option<i128> {
+    n.as_i64()
+        .map(i128::from)
+        .or_else(|| n.as_u64().map(i128::from))
+        // With `arbitrary_precision`, integers outside of the 64-bit range
+        // keep their exact representation
+        .or_else(|| n.to_string().parse().ok())
+}
+
+/// Policy used to compare numbers in the state with the target
+///
+/// By default numbers are compared exactly. Integers are always compared exactly,
+/// independently of the policy, to avoid losing precision for values above 2^53.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NumberPolicy {
+    /// Numbers are equal only if they have the same value
+    #[default]
+    Exact,
+    /// Numbers that differ by at most the given value are equal
+    Epsilon(f64),
+    /// Numbers are equal if they round to the same integer
+    RoundToInt,
+}
+
+impl NumberPolicy {
+    /// Return `true` if the numbers are equal according to the policy
+    ///
+    /// ```rust
+    /// use mahler::NumberPolicy;
+    /// use serde_json::Number;
+    ///
+    /// let a = Number::from_f64(0.1 + 0.2).unwrap();
+    /// let b = Number::from_f64(0.3).unwrap();
+    /// assert!(!NumberPolicy::Exact.equals(&a, &b));
+    /// assert!(NumberPolicy::Epsilon(1e-9).equals(&a, &b));
+    /// assert!(NumberPolicy::RoundToInt.equals(&a, &b));
+    /// ```
+    pub fn equals(&self, a: &serde_json::Number, b: &serde_json::Number) -> bool {
+        // Integers are always compared exactly
+        if let (Some(x), Some(y)) = (as_i128(a), as_i128(b)) {
+            return x == y;
+        }
+
+        let (Some(x), Some(y)) = (a.as_f64(), b.as_f64()) else {
+            return a == b;
+        };
+
+        match self {
+            NumberPolicy::Exact => x == y,
+            NumberPolicy::Epsilon(epsilon) => (x - y).abs() <= *epsilon,
+            NumberPolicy::RoundToInt => x.round() == y.round(),
+        }
+    }
+}
+
+/// Function used to compare a value in the state with the value at the same path in the target
+pub(crate) type Comparator = Arc<dyn Fn(&Value, &Value) -> bool + Send + Sync>;
+
+/// Custom comparators for values at paths matching a pattern
+///
+/// Patterns are JSON pointers where a `*` token matches any single token of the path, e.g.
+/// `/services/*/tags`. If multiple patterns match a path, the first one is used.
+#[derive(Clone, Default)]
+pub struct PathComparators(Vec<(Vec<String>, Comparator)>);
+
+impl PathComparators {
+    /// Add a comparator for the paths matching the pattern
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the pattern is not a valid JSON pointer
+    pub(crate) fn insert(&mut self, pattern: &str, comparator: Comparator) {
+        let pointer = Pointer::parse(pattern)
+            .unwrap_or_else(|e| panic!("invalid path pattern '{pattern}': {e}"));
+        let tokens = pointer.tokens().map(|t| t.decoded().into_owned()).collect();
+        self.0.push((tokens, comparator));
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // Find the comparator for the path given as a list of tokens
+    fn find(&self, path: &[String]) -> Option<&Comparator> {
+        self.0.iter().find_map(|(pattern, comparator)| {
+            let matches = pattern.len() == path.len()
+                && pattern
+                    .iter()
+                    .zip(path)
+                    .all(|(p, token)| p == "*" || p == token);
+            matches.then_some(comparator)
+        })
+    }
+}
+
+impl Debug for PathComparators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.0.iter().map(|(pattern, _)| {
+                    pattern.iter().map(|t| format!("/{t}")).collect::<String>()
+                }),
+            )
+            .finish()
+    }
+}
+
+// Overwrite values in the target with the value in the state if both are
+// equal according to the comparator for the path or the number policy
+fn tolerate(
+    src: &Value,
+    tgt: &mut Value,
+    policy: NumberPolicy,
+    comparators: &PathComparators,
+    path: &mut Vec<String>,
+) {
+    if comparators.find(path).is_some_and(|cmp| cmp(src, tgt)) {
+        *tgt = src.clone();
+        return;
+    }
+
+    match (src, tgt) {
+        (Value::Number(a), Value::Number(b)) if policy.equals(a, b) => {
+            *b = a.clone();
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            for (k, vb) in b.iter_mut() {
+                if let Some(va) = a.get(k) {
+                    path.push(k.clone());
+                    tolerate(va, vb, policy, comparators, path);
+                    path.pop();
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for (i, (va, vb)) in a.iter().zip(b.iter_mut()).enumerate() {
+                path.push(i.to_string());
+                tolerate(va, vb, policy, comparators, path);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Compute a numeric measure of how far the state `src` is from the target `tgt`
+///
+/// Numbers contribute the absolute value of their difference, objects and arrays
+/// contribute the sum of the measure of their elements, and any other differing value
+/// (including values missing from either side) contributes 1.
+pub fn magnitude(src: &Value, tgt: &Value) -> f64 {
+    match (src, tgt) {
+        // Compute the difference of integers before converting to f64 to avoid
+        // losing precision for values above 2^53
+        (Value::Number(a), Value::Number(b)) => match (as_i128(a), as_i128(b)) {
+            (Some(a), Some(b)) => a.abs_diff(b) as f64,
+            _ => match (a.as_f64(), b.as_f64()) {
+                (Some(a), Some(b)) => (a - b).abs(),
+                _ => 1.0,
+            },
+        },
+        (Value::Object(a), Value::Object(b)) => {
+            let mut total = 0.0;
+            for (k, va) in a.iter() {
+                total += b.get(k).map_or(1.0, |vb| magnitude(va, vb));
+            }
+            total + b.keys().filter(|k| !a.contains_key(*k)).count() as f64
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            let common: f64 = a
+                .iter()
+                .zip(b.iter())
+                .map(|(va, vb)| magnitude(va, vb))
+                .sum();
+            common + a.len().abs_diff(b.len()) as f64
+        }
+        (a, b) if a == b => 0.0,
+        _ => 1.0,
+    }
+}
+
+/// Compute the [magnitude] of the difference between the state and target, comparing numbers
+/// according to the given policy and values at matching paths with the given comparators
+///
+/// Values considered equal by the policy or comparators do not contribute to the measure.
+pub(crate) fn magnitude_with_comparators(
+    src: &Value,
+    tgt: &Value,
+    policy: NumberPolicy,
+    comparators: &PathComparators,
+) -> f64 {
+    if policy == NumberPolicy::Exact && comparators.is_empty() {
+        return magnitude(src, tgt);
+    }
+
+    let mut tgt = tgt.clone();
+    tolerate(src, &mut tgt, policy, comparators, &mut Vec::new());
+    magnitude(src, &tgt)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -207,6 +522,118 @@ mod tests {
         assert_eq!(ops, result)
     }
 
+    #[test]
+    fn it_matches_any_job_operation() {
+        let distance = Distance::new(&json!({"a": 1, "b": 1}), &json!({"a": 2, "c": 1}));
+        for op in distance.iter() {
+            assert!(op.matches(&JobOperation::Any));
+            assert!(!op.matches(&JobOperation::None));
+        }
+    }
+
+    #[test]
+    fn it_ignores_number_differences_within_epsilon() {
+        let src = json!({"a": 0.1 + 0.2, "b": [1.0, 2.0], "c": "x"});
+        let tgt = json!({"a": 0.3, "b": [1.0, 2.0000001], "c": "x"});
+
+        // exact comparison reports both changes
+        assert_eq!(Distance::new(&src, &tgt).count(), 2);
+
+        let distance = Distance::with_epsilon(&src, &tgt, 1e-6);
+        assert!(distance.is_empty());
+
+        // differences above the tolerance are still reported
+        let distance = Distance::with_epsilon(&src, &json!({"a": 0.4, "b": [1.0, 2.0]}), 1e-6);
+        assert_eq!(distance.paths().collect::<Vec<&str>>(), vec!["/a", "/c"]);
+    }
+
+    #[test]
+    fn it_compares_numbers_using_the_configured_policy() {
+        let src = json!({"a": 2.0});
+        let tgt = json!({"a": 2.0000001});
+
+        assert_eq!(
+            Distance::with_policy(&src, &tgt, NumberPolicy::Exact)
+                .paths()
+                .collect::<Vec<&str>>(),
+            vec!["/a"]
+        );
+        assert!(Distance::with_policy(&src, &tgt, NumberPolicy::Epsilon(1e-6)).is_empty());
+        assert!(!Distance::with_policy(&src, &tgt, NumberPolicy::Epsilon(1e-9)).is_empty());
+        assert!(Distance::with_policy(&src, &tgt, NumberPolicy::RoundToInt).is_empty());
+
+        // numbers rounding to different integers are still reported
+        let tgt = json!({"a": 2.5});
+        assert!(!Distance::with_policy(&src, &tgt, NumberPolicy::RoundToInt).is_empty());
+
+        // integers are compared exactly with every policy
+        let src = json!({"a": 2});
+        let tgt = json!({"a": 3});
+        assert!(!Distance::with_policy(&src, &tgt, NumberPolicy::Epsilon(1.0)).is_empty());
+        assert!(!Distance::with_policy(&src, &tgt, NumberPolicy::RoundToInt).is_empty());
+    }
+
+    #[test]
+    fn it_uses_custom_comparators_at_matching_paths() {
+        let mut comparators = PathComparators::default();
+        comparators.insert(
+            "/services/*/tags",
+            Arc::new(|a: &Value, b: &Value| {
+                let (Some(a), Some(b)) = (a.as_array(), b.as_array()) else {
+                    return a == b;
+                };
+                a.len() == b.len() && a.iter().all(|v| b.contains(v))
+            }),
+        );
+
+        let src = json!({"services": {"one": {"tags": ["a", "b"]}, "two": {"tags": ["c"]}}, "tags": ["a", "b"]});
+        let tgt = json!({"services": {"one": {"tags": ["b", "a"]}, "two": {"tags": ["d"]}}, "tags": ["b", "a"]});
+
+        let distance = Distance::with_comparators(&src, &tgt, NumberPolicy::Exact, &comparators);
+        assert_eq!(
+            distance.paths().collect::<Vec<&str>>(),
+            vec!["/services/two/tags/0", "/tags/0", "/tags/1"]
+        );
+    }
+
+    #[test]
+    fn it_compares_large_integers_exactly() {
+        let src = json!({"a": 9007199254740992u64, "b": -9007199254740992i64});
+        let tgt = json!({"a": 9007199254740993u64, "b": -9007199254740993i64});
+
+        // 2^53 and 2^53 + 1 are equal when converted to f64
+        let distance = Distance::with_epsilon(&src, &tgt, 1e-9);
+        assert_eq!(distance.paths().collect::<Vec<&str>>(), vec!["/a", "/b"]);
+        assert_eq!(magnitude(&src, &tgt), 2.0);
+    }
+
+    #[test]
+    fn it_counts_the_differing_values() {
+        let distance = Distance::new(
+            &json!({"a": 1, "b": "one", "c": {"k": "v"}}),
+            &json!({"a": 2, "b": "two", "c": {"k": "v"}}),
+        );
+        assert_eq!(distance.count(), 2);
+        assert_eq!(distance.paths().collect::<Vec<&str>>(), vec!["/a", "/b"]);
+
+        let distance = Distance::new(&json!({"a": 1}), &json!({"a": 1}));
+        assert_eq!(distance.count(), 0);
+        assert!(distance.is_empty());
+    }
+
+    #[test]
+    fn it_measures_the_distance_magnitude() {
+        assert_eq!(magnitude(&json!(0), &json!(2)), 2.0);
+        assert_eq!(magnitude(&json!(3), &json!(2)), 1.0);
+        assert_eq!(magnitude(&json!("a"), &json!("b")), 1.0);
+        assert_eq!(
+            magnitude(&json!({"a": 1, "b": "x"}), &json!({"a": 3, "c": "x"})),
+            4.0
+        );
+        assert_eq!(magnitude(&json!([1, 2]), &json!([1, 2, 3])), 1.0);
+        assert_eq!(magnitude(&json!({"a": [1]}), &json!({"a": [1]})), 0.0);
+    }
+
     #[test]
     fn it_calculates_possible_changes_to_target() {
         distance_eq(
@@ -1,34 +1,305 @@
 use anyhow::anyhow;
 use matchit::Router;
-use std::collections::btree_set::Iter;
-use std::collections::{BTreeSet, HashMap};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{self, Debug};
+use std::sync::Arc;
 use thiserror::Error;
 
+use crate::errors::SerializationError;
 use crate::path::PathArgs;
-use crate::task::Job;
+use crate::task::{self, Job, Operation};
+use crate::workflow::{Dag, RehydrateError, SerializedWorkUnit, Step, Workflow};
 
 #[derive(Debug, Error)]
 #[error(transparent)]
 pub struct PathSearchError(#[from] anyhow::Error);
 
-#[derive(Default, Debug, Clone)]
+/// Backend used to match system paths to the routes that jobs are registered on
+///
+/// By default, routes are matched using the [matchit](https://docs.rs/matchit) syntax, where
+/// `{name}` matches a single path segment and `{*name}` matches the remainder of the path. A
+/// custom matcher can be provided to support different routing patterns (e.g. regular expression
+/// segments). Routes are never removed from a domain, so matchers only need to support
+/// registering and looking up routes.
+///
+/// Note that the route syntax is also used to build the path of the tasks returned by methods,
+/// where `{name}` and `{*name}` placeholders are replaced by the task arguments.
+pub trait RouteMatcher: Send + Sync + 'static {
+    /// Register a route with the matcher
+    ///
+    /// Returns an error if the route is not valid for the matcher
+    fn insert(&mut self, route: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Find the registered route matching the given path
+    ///
+    /// Returns the route along with the values for the route parameters
+    fn at<'r>(&'r self, path: &str) -> Option<(&'r str, Vec<(String, String)>)>;
+}
+
+// The default matcher using matchit
+#[derive(Default, Clone)]
+struct DefaultMatcher(Router<String>);
+
+impl RouteMatcher for DefaultMatcher {
+    fn insert(&mut self, route: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.0.insert(route, String::from(route))?;
+        Ok(())
+    }
+
+    fn at<'r>(&'r self, path: &str) -> Option<(&'r str, Vec<(String, String)>)> {
+        self.0.at(path).ok().map(|matched| {
+            (
+                matched.value.as_str(),
+                matched
+                    .params
+                    .iter()
+                    .map(|(k, v)| (String::from(k), String::from(v)))
+                    .collect(),
+            )
+        })
+    }
+}
+
+// Allows to clone the boxed matcher
+trait CloneableMatcher: RouteMatcher {
+    fn clone_box(&self) -> Box<dyn CloneableMatcher>;
+}
+
+impl<M: RouteMatcher + Clone> CloneableMatcher for M {
+    fn clone_box(&self) -> Box<dyn CloneableMatcher> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn CloneableMatcher> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl Debug for dyn CloneableMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RouteMatcher")
+    }
+}
+
+/// Summary of the operations handled by the jobs on each route
+///
+/// This allows to check, before looking for a workflow, that the domain is able to handle the
+/// changes required by a target. For instance, if no route has a job for the `delete` operation,
+/// the planner will not be able to find a workflow for targets that remove values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage(BTreeMap<String, BTreeSet<Operation>>);
+
+impl Coverage {
+    /// Return the routes with registered jobs
+    pub fn routes(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(|route| route.as_str())
+    }
+
+    /// Return the operations of the jobs registered on the given route
+    pub fn operations(&self, route: &str) -> impl Iterator<Item = &Operation> {
+        self.0.get(route).into_iter().flatten()
+    }
+
+    /// Return true if a job on the route may be selected by the planner for the operation
+    ///
+    /// Jobs for [`Operation::Any`] cover every operation, while jobs for [`Operation::None`]
+    /// are never selected by the planner on their own.
+    pub fn covers(&self, route: &str, operation: &Operation) -> bool {
+        if operation == &Operation::None {
+            return false;
+        }
+        self.operations(route)
+            .any(|op| op == operation || op == &Operation::Any)
+    }
+}
+
+// Remove trailing slashes from a route or path. A route made only of slashes is kept as
+// is, as `/` points to the empty key of the root object rather than to the root itself
+fn trim_trailing_slashes(route: &str) -> &str {
+    match route.trim_end_matches('/') {
+        "" => route,
+        trimmed => trimmed,
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The set of jobs available to the planner, indexed by route
+///
+/// See [`Worker::builder`](`crate::worker::Worker::builder`)
 pub struct Domain {
-    // The router stores a list of jobs matching a route
-    router: Router<BTreeSet<Job>>,
-    // The index stores the reverse relation of job id to a route
+    // The matcher finds the route for a given path
+    matcher: Box<dyn CloneableMatcher>,
+    // The list of jobs assigned to each (normalized) route
+    routes: HashMap<String, BTreeSet<Job>>,
+    // The index stores the reverse relation of job id to the route
+    // as registered by the user
     index: HashMap<Box<str>, String>,
+    // Default values for path arguments, indexed by registered route
+    defaults: HashMap<String, PathArgs>,
+    // Optional normalizer applied to routes and lookup paths before matching
+    normalizer: Option<fn(&str) -> String>,
+    // Reject jobs that cannot be ordered unambiguously
+    strict: bool,
+}
+
+impl Default for Domain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Build a domain from a list of `(route, job)` pairs
+///
+/// # Panics
+///
+/// Collecting will panic under the same conditions as [`Domain::job`]
+impl FromIterator<(&'static str, Job)> for Domain {
+    fn from_iter<T: IntoIterator<Item = (&'static str, Job)>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(Domain::new(), |domain, (route, job)| domain.job(route, job))
+    }
 }
 
 impl Domain {
+    /// Create an empty domain
     pub fn new() -> Self {
         Self {
-            router: Router::new(),
+            matcher: Box::new(DefaultMatcher::default()),
+            routes: HashMap::new(),
             index: HashMap::new(),
+            defaults: HashMap::new(),
+            normalizer: None,
+            strict: false,
+        }
+    }
+
+    /// Use a custom matcher to find the jobs for a given path
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if jobs have already been registered in the domain
+    pub fn with_matcher<M: RouteMatcher + Clone>(mut self, matcher: M) -> Self {
+        assert!(
+            self.index.is_empty(),
+            "a route matcher must be set before registering any jobs"
+        );
+        self.matcher = Box::new(matcher);
+        self
+    }
+
+    /// Reject jobs that would be tried by the planner in an ambiguous order
+    ///
+    /// When enabled, registering a job panics if another job on the same route has
+    /// the same operation, priority, kind (action or method) and fallback flag. Such jobs are
+    /// otherwise tried in order of their id.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if jobs have already been registered in the domain
+    pub fn with_strict_priorities(mut self, enable: bool) -> Self {
+        assert!(
+            self.index.is_empty(),
+            "strict priorities must be set before registering any jobs"
+        );
+        self.strict = enable;
+        self
+    }
+
+    /// Set a normalizer to apply to routes on registration and to paths on lookup
+    ///
+    /// Only static route segments are normalized, segments with
+    /// placeholders are left untouched. Paths are normalized for matching only,
+    /// path arguments are read from the original path and task paths are built
+    /// from the route as registered.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if jobs have already been registered in the domain
+    pub fn with_normalizer(mut self, normalizer: fn(&str) -> String) -> Self {
+        assert!(
+            self.index.is_empty(),
+            "a normalizer must be set before registering any jobs"
+        );
+        self.normalizer = Some(normalizer);
+        self
+    }
+
+    // Apply the normalizer to the static segments of a route. Trailing
+    // slashes are always removed so routes match with or without them
+    fn normalize_route(&self, route: &str) -> String {
+        let route = trim_trailing_slashes(route);
+        if let Some(normalize) = self.normalizer {
+            route
+                .split('/')
+                .map(|segment| {
+                    if segment.contains('{') {
+                        Cow::Borrowed(segment)
+                    } else {
+                        Cow::Owned(normalize(segment))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("/")
+        } else {
+            String::from(route)
+        }
+    }
+
+    // Find the route matching a lookup path, removing any trailing slashes. If a normalizer
+    // is set, the route is matched against a normalized copy of the path, but the values
+    // of the route parameters are read from the original path
+    fn match_path(&self, path: &str) -> Option<(&str, Vec<(String, String)>)> {
+        let path = trim_trailing_slashes(path);
+        let Some(normalize) = self.normalizer else {
+            return self.matcher.at(path);
+        };
+
+        let segments: Vec<&str> = path.split('/').collect();
+        let normalized = segments
+            .iter()
+            .map(|segment| normalize(segment))
+            .collect::<Vec<_>>()
+            .join("/");
+        let (route, mut params) = self.matcher.at(&normalized)?;
+
+        for (i, segment) in route.split('/').enumerate() {
+            // Parameters sharing a segment with static text keep the matched value
+            let Some(name) = segment
+                .strip_prefix('{')
+                .and_then(|name| name.strip_suffix('}'))
+            else {
+                continue;
+            };
+            let value = match name.strip_prefix('*') {
+                Some(_) => segments.get(i..).map(|rest| rest.join("/")),
+                None => segments.get(i).map(|segment| segment.to_string()),
+            };
+            let name = name.trim_start_matches('*');
+            if let (Some(value), Some((_, param))) =
+                (value, params.iter_mut().find(|(key, _)| key == name))
+            {
+                *param = value;
+            }
         }
+
+        Some((route, params))
     }
 
     /// Add a job to a domain
     ///
+    /// Trailing slashes in the route are ignored, and also ignored in paths when
+    /// looking up jobs.
+    ///
+    /// A route may have jobs for multiple operations, e.g. `create`, `update` and `delete`
+    /// handlers for the same path, as long as the job ids are different. Jobs on the same route
+    /// are tried in order of priority and then id, and only jobs for the operation required by
+    /// the target are selected by the planner.
+    ///
     /// # Panics
     ///
     /// This function will panic if the route is not a valid path
@@ -36,17 +307,26 @@ impl Domain {
     pub fn job(self, route: &'static str, job: Job) -> Self {
         // TODO: it would be great to figure out a way to validate
         // that the pointer is valid for the parent state at compile time
+        let registered = trim_trailing_slashes(route);
+        let route = self.normalize_route(route);
         let Self {
-            mut router,
+            mut matcher,
+            mut routes,
             mut index,
+            defaults,
+            normalizer,
+            strict,
         } = self;
 
         let job_id = String::from(job.id());
         let operation = job.operation();
 
-        // Remove the route from the router if it exists or create
-        // a new set if it doesn't
-        let mut queue = router.remove(route).unwrap_or_default();
+        // Register the route with the matcher the first time
+        // it is used
+        if !routes.contains_key(&route) {
+            matcher.insert(&route).expect("route should be valid");
+        }
+        let queue = routes.entry(route.clone()).or_default();
 
         // Do not allow the same job to be assigned to
         // multiple operations. This could cause problems at
@@ -58,17 +338,41 @@ impl Domain {
             )
         }
 
+        // Jobs with the same operation, priority, kind and fallback flag would be tried
+        // in order of id
+        if strict && operation != &Operation::None {
+            if let Some(other) = queue.iter().find(|j| {
+                j.operation() == operation
+                    && j.priority() == job.priority()
+                    && j.is_method() == job.is_method()
+                    && j.is_fallback() == job.is_fallback()
+            }) {
+                let describe = |j: &Job| {
+                    format!(
+                        "'{}' ({}, fallback: {})",
+                        j.id(),
+                        if j.is_method() { "method" } else { "action" },
+                        j.is_fallback()
+                    )
+                };
+                panic!(
+                    "cannot assign job {} to route '{}', job {} has the same operation '{:?}' and priority {}",
+                    describe(&job),
+                    route,
+                    describe(other),
+                    operation,
+                    job.priority()
+                )
+            }
+        }
+
         // Insert the route to the queue
         let updated = queue.insert(job);
 
-        // (re)insert the queue to the router, we should not have
-        // conflicts here
-        router.insert(route, queue).expect("route should be valid");
-
         // Only allow one assignment of a job to a route
         if updated {
             if let Some(oldroute) =
-                index.insert(job_id.clone().into_boxed_str(), String::from(route))
+                index.insert(job_id.clone().into_boxed_str(), String::from(registered))
             {
                 panic!(
                     "cannot assign job '{}' to route '{}', a previous assignment exists to '{}'",
@@ -77,7 +381,14 @@ impl Domain {
             }
         }
 
-        Self { router, index }
+        Self {
+            matcher,
+            routes,
+            index,
+            defaults,
+            normalizer,
+            strict,
+        }
     }
 
     pub fn jobs<const N: usize>(self, route: &'static str, list: [Job; N]) -> Self {
@@ -85,6 +396,69 @@ impl Domain {
             .fold(self, |domain, job| domain.job(route, job))
     }
 
+    /// Add a job to a domain, providing default values for the route path arguments
+    ///
+    /// Defaults are used when searching for the path of a job (e.g. when
+    /// expanding a method) if the argument was not provided by the caller.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic under the same conditions as [`Domain::job`]
+    pub fn job_with_defaults<const N: usize>(
+        self,
+        route: &'static str,
+        job: Job,
+        defaults: [(&str, &str); N],
+    ) -> Self {
+        let mut domain = self.job(route, job);
+
+        let route = String::from(trim_trailing_slashes(route));
+        let route_defaults = domain.defaults.entry(route).or_default();
+        for (key, value) in defaults {
+            route_defaults.insert(key, value);
+        }
+
+        domain
+    }
+
+    /// Get the operations handled by the jobs on each route
+    pub fn coverage(&self) -> Coverage {
+        Coverage(
+            self.routes
+                .iter()
+                .map(|(route, jobs)| {
+                    (
+                        route.clone(),
+                        jobs.iter().map(|job| job.operation().clone()).collect(),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Create a workflow from its serialized representation
+    ///
+    /// Every task in the serialized workflow is bound to the handler of the job with the same
+    /// id registered for the task path.
+    pub fn rehydrate(&self, serialized: Value) -> Result<Workflow, RehydrateError> {
+        let steps: Vec<Step<SerializedWorkUnit>> =
+            serde_json::from_value(serialized).map_err(SerializationError::from)?;
+
+        let steps = steps
+            .into_iter()
+            .map(|step| {
+                step.try_map(&mut |unit: SerializedWorkUnit| {
+                    unit.rehydrate(|id, context| match self.find_job(context.path(), id) {
+                        Some(job) => Some(job.new_task(context)),
+                        None => task::builtin(id).map(|task| task.with_context(context)),
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Workflow(Dag::from_steps(steps)))
+    }
+
     // This allows to find the path that a task relates to from the
     // job it belongs to and the arguments given by the user as part
     // of the context. It will also remove any unused args from the
@@ -96,78 +470,20 @@ impl Domain {
         args: &mut PathArgs,
     ) -> Result<String, PathSearchError> {
         if let Some(route) = self.index.get(job_id) {
-            let mut route = route.clone();
-            let mut replacements = Vec::new();
-            let mut used_keys = Vec::new();
-
-            // Step 1: Replace `{param}` and `{*param}` placeholders
-            for (k, v) in args.iter() {
-                let param = format!("{{{}}}", k);
-                let wildcard_param = format!("{{*{}}}", k);
-                let escaped_param = format!("{{{{{}}}}}", k);
-
-                // Replace escaped parameters with a temp placeholder, but do not mark as used
-                let placeholder = format!("__ESCAPED_{}__", k);
-                route = route.replace(&escaped_param, &placeholder);
-
-                // Only mark as used if actual replacement occurs
-                if route.contains(&param) || route.contains(&wildcard_param) {
-                    used_keys.push(k.clone());
-                    replacements.push((param, v.clone()));
-                    replacements.push((wildcard_param, v.clone()));
-                }
-            }
-
-            // Apply replacements
-            for (param, value) in replacements {
-                route = route.replace(&param, &value);
-            }
-
-            // Convert `{{param}}` → `{param}`
-            let mut final_route = String::new();
-            let mut chars = route.chars().peekable();
-            let mut missing_args = Vec::new();
-
-            while let Some(c) = chars.next() {
-                if c == '{' && chars.peek() == Some(&'{') {
-                    chars.next(); // Skip second '{'
-                    final_route.push('{');
-                } else if c == '}' && chars.peek() == Some(&'}') {
-                    chars.next(); // Skip second '}'
-                    final_route.push('}');
-                } else if c == '{' {
-                    let mut placeholder = String::from("{");
-                    while let Some(&next) = chars.peek() {
-                        placeholder.push(next);
-                        chars.next();
-                        if next == '}' {
-                            break;
-                        }
+            // Fall back to the route defaults for arguments not
+            // provided by the caller
+            if let Some(defaults) = self.defaults.get(route) {
+                for (k, v) in defaults.iter() {
+                    if !args.iter().any(|(key, _)| key == k) {
+                        args.insert(k, v.clone());
                     }
-
-                    if placeholder.ends_with('}') {
-                        missing_args.push(placeholder.clone());
-                    }
-
-                    final_route.push_str(&placeholder);
-                } else {
-                    final_route.push(c);
                 }
             }
 
-            if !missing_args.is_empty() {
-                return Err(anyhow!(
-                    "missing arguments for task {job_id}: {:?}",
-                    missing_args
-                ))?;
-            }
-
-            // Restore escaped parameters
-            for (k, _) in args.iter() {
-                let placeholder = format!("__ESCAPED_{}__", k);
-                let param = format!("{{{}}}", k);
-                final_route = final_route.replace(&placeholder, &param);
-            }
+            // Replace the route placeholders with the argument values
+            let (final_route, used_keys) = args.expand(route).map_err(|missing_args| {
+                anyhow!("missing arguments for task {job_id}: {:?}", missing_args)
+            })?;
 
             // Retain only the used keys (excluding those used only as escaped)
             args.retain(|(k, _)| used_keys.contains(k));
@@ -180,22 +496,47 @@ impl Domain {
         }
     }
 
+    /// Return every registered job along with its route, sorted by route and job id
+    pub(crate) fn registered_jobs(&self) -> Vec<(&str, &Job)> {
+        let mut jobs: Vec<(&str, &Job)> = self
+            .routes
+            .iter()
+            .flat_map(|(route, jobs)| jobs.iter().map(move |job| (route.as_str(), job)))
+            .collect();
+        jobs.sort_by(|(a, job_a), (b, job_b)| a.cmp(b).then_with(|| job_a.id().cmp(job_b.id())));
+        jobs
+    }
+
+    // Return true if a job with the given id has been registered
+    pub(crate) fn contains_job(&self, job_id: &str) -> bool {
+        self.index.contains_key(job_id)
+    }
+
     // Find a job given the path and the id
     pub(crate) fn find_job(&self, path: &str, job_id: &str) -> Option<&Job> {
-        self.router
-            .at(path)
-            .ok()
-            .and_then(|matched| matched.value.iter().find(|job| job.id() == job_id))
+        self.match_path(path)
+            .and_then(|(route, _)| self.routes.get(route))
+            .and_then(|jobs| jobs.iter().find(|job| job.id() == job_id))
     }
 
     /// Find matches for the given path in the domain
     /// the matches are sorted in order that they should be
-    /// tested
-    pub(crate) fn find_matching_jobs(&self, path: &str) -> Option<(PathArgs, Iter<Job>)> {
-        self.router
-            .at(path)
-            .map(|matched| (PathArgs::from(matched.params), matched.value.iter()))
-            .ok()
+    /// tested, i.e. jobs with higher priority first. The route template
+    /// that matched the path is returned along with the path arguments
+    pub(crate) fn find_matching_jobs(
+        &self,
+        path: &str,
+    ) -> Option<(&str, PathArgs, std::vec::IntoIter<&Job>)> {
+        let (route, params) = self.match_path(path)?;
+        let (route, jobs) = self.routes.get_key_value(route)?;
+        let args = params.into_iter().map(|(k, v)| (Arc::from(k), v)).collect();
+
+        // The sort is stable, so jobs with the same priority
+        // are returned in order of id
+        let mut jobs: Vec<&Job> = jobs.iter().collect();
+        jobs.sort_by_key(|job| Reverse(job.priority()));
+
+        Some((route.as_str(), PathArgs(args), jobs.into_iter()))
     }
 }
 
@@ -243,6 +584,182 @@ mod tests {
             .job("/numbers/{counter}", create(plus_one));
     }
 
+    #[test]
+    #[should_panic(
+        expected = "cannot assign job 'plus_one_copy' (action, fallback: false) to route '/counters/{counter}', job 'mahler::planner::domain::tests::plus_one' (action, fallback: false) has the same operation 'Update' and priority 0"
+    )]
+    fn it_fails_if_jobs_are_ambiguous_in_strict_mode() {
+        Domain::new()
+            .with_strict_priorities(true)
+            .job("/counters/{counter}", update(plus_one))
+            .job(
+                "/counters/{counter}",
+                update(plus_one).with_id("plus_one_copy"),
+            );
+    }
+
+    #[test]
+    fn it_allows_ambiguous_jobs_if_not_in_strict_mode() {
+        let domain = Domain::new()
+            .job("/counters/{counter}", update(plus_one))
+            .job(
+                "/counters/{counter}",
+                update(plus_one).with_id("plus_one_copy"),
+            );
+
+        // Jobs are returned in order of id
+        let jobs: Vec<&str> = domain
+            .find_matching_jobs("/counters/one")
+            .map(|(_, _, jobs)| jobs.map(|j| j.id()).collect())
+            .unwrap();
+        assert_eq!(
+            jobs,
+            vec!["mahler::planner::domain::tests::plus_one", "plus_one_copy"]
+        );
+    }
+
+    #[test]
+    fn it_allows_jobs_with_explicit_tiebreaks_in_strict_mode() {
+        let domain = Domain::new()
+            .with_strict_priorities(true)
+            .job("/counters/{counter}", update(plus_one))
+            .job(
+                "/counters/{counter}",
+                update(plus_one).with_id("plus_one_copy").with_priority(1),
+            )
+            .job("/counters/{counter}", update(plus_two))
+            .job(
+                "/counters/{counter}",
+                create(plus_one).with_id("create_one"),
+            );
+
+        assert!(domain.find_job("/counters/one", "create_one").is_some());
+    }
+
+    #[test]
+    fn it_allows_assigning_the_same_handler_to_multiple_routes_with_distinct_ids() {
+        let domain = Domain::new()
+            .job("/a/{x}", update(plus_one).with_id("plus_one_a"))
+            .job("/b/{x}", update(plus_one).with_id("plus_one_b"));
+
+        assert!(domain.find_job("/a/one", "plus_one_a").is_some());
+        assert!(domain.find_job("/b/one", "plus_one_b").is_some());
+        assert!(domain.find_job("/a/one", "plus_one_b").is_none());
+
+        let mut args = PathArgs(vec![(Arc::from("x"), String::from("one"))]);
+        let path = domain.find_path_for_job("plus_one_b", &mut args).unwrap();
+        assert_eq!(path, String::from("/b/one"));
+    }
+
+    #[test]
+    fn it_reports_operations_covered_by_each_route() {
+        let domain = Domain::new()
+            .job("/counters/{counter}", update(plus_one))
+            .job("/counters/{counter}", none(plus_two))
+            .job("", any(plus_three));
+
+        let coverage = domain.coverage();
+        assert_eq!(
+            coverage.routes().collect::<Vec<&str>>(),
+            vec!["", "/counters/{counter}"]
+        );
+        assert_eq!(
+            coverage
+                .operations("/counters/{counter}")
+                .collect::<Vec<_>>(),
+            vec![&Operation::None, &Operation::Update]
+        );
+
+        // The update-only route cannot handle deleting counters
+        assert!(coverage.covers("/counters/{counter}", &Operation::Update));
+        assert!(!coverage.covers("/counters/{counter}", &Operation::Delete));
+        assert!(!coverage.covers("/counters/{counter}", &Operation::None));
+
+        // Any jobs cover every operation
+        assert!(coverage.covers("", &Operation::Delete));
+        assert!(!coverage.covers("/unknown", &Operation::Update));
+    }
+
+    #[test]
+    fn it_allows_jobs_for_different_operations_on_the_same_route() {
+        let domain = Domain::new()
+            .job("/counters/{counter}", create(plus_one))
+            .job("/counters/{counter}", update(plus_two))
+            .job("/counters/{counter}", delete(plus_three));
+
+        for (operation, id) in [
+            (Operation::Create, plus_one.id()),
+            (Operation::Update, plus_two.id()),
+            (Operation::Delete, plus_three.id()),
+        ] {
+            let (route, _, jobs) = domain.find_matching_jobs("/counters/one").unwrap();
+            assert_eq!(route, "/counters/{counter}");
+            assert_eq!(
+                jobs.filter(|job| job.operation() == &operation)
+                    .map(|job| job.id())
+                    .collect::<Vec<_>>(),
+                vec![id]
+            );
+        }
+
+        let coverage = domain.coverage();
+        assert_eq!(
+            coverage
+                .operations("/counters/{counter}")
+                .collect::<Vec<_>>(),
+            // Operations are listed in declaration order of the enum
+            vec![&Operation::Delete, &Operation::Create, &Operation::Update]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "a previous assignment exists")]
+    fn it_fails_if_assigning_closures_without_ids_to_multiple_routes() {
+        // Closures defined in the same function share the same type name
+        Domain::new()
+            .job("/a/{x}", update(|counter: View<i32>| counter))
+            .job(
+                "/b/{x}",
+                update(|mut counter: View<i32>| {
+                    *counter += 1;
+                    counter
+                }),
+            );
+    }
+
+    #[test]
+    fn it_allows_assigning_closures_with_explicit_ids() {
+        let domain = Domain::new()
+            .job(
+                "/a/{x}",
+                update(|counter: View<i32>| counter).with_id("noop"),
+            )
+            .job(
+                "/b/{x}",
+                update(|mut counter: View<i32>| {
+                    *counter += 1;
+                    counter
+                })
+                .with_id("increment"),
+            );
+
+        assert!(domain.find_job("/a/one", "noop").is_some());
+        assert!(domain.find_job("/b/one", "increment").is_some());
+        assert!(domain.find_job("/a/one", "increment").is_none());
+
+        let mut args = PathArgs(vec![(Arc::from("x"), String::from("one"))]);
+        let path = domain.find_path_for_job("increment", &mut args).unwrap();
+        assert_eq!(path, String::from("/b/one"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_fails_if_assigning_the_same_overridden_id_to_multiple_routes() {
+        Domain::new()
+            .job("/a/{x}", update(plus_one).with_id("plus_one"))
+            .job("/b/{x}", update(plus_two).with_id("plus_one"));
+    }
+
     #[test]
     fn it_constructs_a_path_given_arguments() {
         let domain = Domain::new()
@@ -254,6 +771,102 @@ mod tests {
         assert_eq!(path, String::from("/counters/one"))
     }
 
+    #[test]
+    fn it_constructs_the_root_path_for_root_jobs() {
+        let domain = Domain::new().job("", update(plus_one));
+
+        let mut args = PathArgs::default();
+        let path = domain.find_path_for_job(plus_one.id(), &mut args).unwrap();
+        assert_eq!(path, String::new());
+
+        let (route, args, _) = domain.find_matching_jobs("").unwrap();
+        assert_eq!(route, "");
+        assert!(args.iter().next().is_none());
+    }
+
+    // Matches paths that are equal to a registered route
+    #[derive(Clone, Default)]
+    struct ExactMatcher(BTreeSet<String>);
+
+    impl RouteMatcher for ExactMatcher {
+        fn insert(&mut self, route: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.0.insert(route.to_string());
+            Ok(())
+        }
+
+        fn at<'r>(&'r self, path: &str) -> Option<(&'r str, Vec<(String, String)>)> {
+            self.0.get(path).map(|route| (route.as_str(), vec![]))
+        }
+    }
+
+    fn plus_three(counter: View<i32>, tgt: Target<i32>) -> Vec<Task> {
+        if *tgt - *counter < 3 {
+            return vec![];
+        }
+
+        vec![plus_two.with_target(*tgt), plus_one.with_target(*tgt)]
+    }
+
+    #[test]
+    fn it_returns_matching_jobs_in_order_of_priority() {
+        let domain = Domain::new().jobs(
+            "/counters/{counter}",
+            [
+                update(plus_one).with_priority(0),
+                update(plus_three).with_priority(2),
+                update(plus_two).with_priority(1),
+            ],
+        );
+
+        let (_, _, jobs) = domain.find_matching_jobs("/counters/one").unwrap();
+        assert_eq!(
+            jobs.map(|job| job.id()).collect::<Vec<_>>(),
+            vec![plus_three.id(), plus_two.id(), plus_one.id()]
+        );
+    }
+
+    #[test]
+    fn it_finds_jobs_using_a_custom_matcher() {
+        let domain = Domain::new()
+            .with_matcher(ExactMatcher::default())
+            .job("/counters/{counter}", update(plus_one))
+            .job("/counters/one", update(plus_two));
+
+        let (route, args, jobs) = domain.find_matching_jobs("/counters/one").unwrap();
+        assert_eq!(route, "/counters/one");
+        assert!(args.is_empty());
+        assert_eq!(
+            jobs.map(|job| job.id()).collect::<Vec<_>>(),
+            vec![plus_two.id()]
+        );
+        assert!(domain.find_job("/counters/one", plus_two.id()).is_some());
+
+        // The placeholder is not interpreted by the matcher
+        assert!(domain.find_matching_jobs("/counters/two").is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_fails_to_set_a_matcher_after_registering_jobs() {
+        Domain::new()
+            .job("/counters/{counter}", update(plus_one))
+            .with_matcher(ExactMatcher::default());
+    }
+
+    #[test]
+    fn it_returns_the_matched_route_template() {
+        let domain = Domain::new()
+            .job("/counters/{counter}", update(plus_one))
+            .job("/counters", update(plus_two));
+
+        let (route, args, _) = domain.find_matching_jobs("/counters/one").unwrap();
+        assert_eq!(route, "/counters/{counter}");
+        assert_eq!(
+            args,
+            PathArgs(vec![(Arc::from("counter"), String::from("one"))])
+        );
+    }
+
     #[test]
     fn test_wildcard_parameter_replacement() {
         let func = |file: View<()>| file;
@@ -317,6 +930,134 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn it_uses_default_args_when_not_provided() {
+        let func = |region: View<()>| region;
+        let domain =
+            Domain::new().job_with_defaults("/x/{region}", update(func), [("region", "us-east")]);
+
+        // The default is used if no argument is given
+        let mut args = PathArgs(vec![]);
+        let path = domain.find_path_for_job(func.id(), &mut args).unwrap();
+        assert_eq!(path, "/x/us-east".to_string());
+        assert_eq!(
+            args,
+            PathArgs(vec![(Arc::from("region"), "us-east".to_string())])
+        );
+
+        // The provided argument takes precedence over the default
+        let mut args = PathArgs(vec![(Arc::from("region"), "eu-west".to_string())]);
+        let path = domain.find_path_for_job(func.id(), &mut args).unwrap();
+        assert_eq!(path, "/x/eu-west".to_string());
+    }
+
+    #[test]
+    fn it_matches_normalized_routes() {
+        let domain = Domain::new()
+            .with_normalizer(str::to_lowercase)
+            .job("/Counters/{counter}", update(plus_one))
+            .job("/counters/{counter}", update(plus_two))
+            .job("/Files/{*path}", update(plus_three));
+
+        // Argument values are read from the original path
+        let (_, args, jobs) = domain.find_matching_jobs("/Counters/One").unwrap();
+        assert_eq!(
+            args,
+            PathArgs(vec![(Arc::from("counter"), String::from("One"))])
+        );
+        assert_eq!(jobs.count(), 2);
+        assert!(domain.find_job("/COUNTERS/one", plus_one.id()).is_some());
+
+        let (_, args, _) = domain.find_matching_jobs("/FILES/Docs/Q1.txt").unwrap();
+        assert_eq!(
+            args,
+            PathArgs(vec![(Arc::from("path"), String::from("Docs/Q1.txt"))])
+        );
+
+        // Task paths are built from the route as registered
+        let mut args = PathArgs(vec![(Arc::from("counter"), String::from("Two"))]);
+        let path = domain.find_path_for_job(plus_one.id(), &mut args).unwrap();
+        assert_eq!(path, String::from("/Counters/Two"));
+
+        let mut args = PathArgs(vec![(Arc::from("counter"), String::from("Two"))]);
+        let path = domain.find_path_for_job(plus_two.id(), &mut args).unwrap();
+        assert_eq!(path, String::from("/counters/Two"));
+    }
+
+    #[test]
+    fn it_collects_a_domain_from_an_iterator() {
+        let domain: Domain = [
+            ("/counters/{counter}", update(plus_one)),
+            ("/counters/{counter}", update(plus_two)),
+            (
+                "/numbers/{number}",
+                create(plus_one).with_id("create_number"),
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let (_, _, jobs) = domain.find_matching_jobs("/counters/one").unwrap();
+        assert_eq!(
+            jobs.map(|job| job.id()).collect::<Vec<_>>(),
+            vec![plus_one.id(), plus_two.id()]
+        );
+        assert!(domain.find_job("/numbers/one", "create_number").is_some());
+
+        let mut args = PathArgs(vec![(Arc::from("number"), String::from("two"))]);
+        let path = domain
+            .find_path_for_job("create_number", &mut args)
+            .unwrap();
+        assert_eq!(path, String::from("/numbers/two"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_panics_when_collecting_a_job_assigned_to_multiple_routes() {
+        let _: Domain = [("/a/{x}", update(plus_one)), ("/b/{x}", update(plus_one))]
+            .into_iter()
+            .collect();
+    }
+
+    #[test]
+    fn it_ignores_trailing_slashes_in_routes_and_paths() {
+        let domain = Domain::new()
+            .job("/counters/{c}", update(plus_one))
+            .job("/numbers/{n}/", update(plus_two));
+
+        for path in ["/counters/one", "/counters/one/"] {
+            let (route, args, jobs) = domain.find_matching_jobs(path).unwrap();
+            assert_eq!(route, "/counters/{c}");
+            assert_eq!(args, PathArgs(vec![(Arc::from("c"), String::from("one"))]));
+            assert_eq!(jobs.count(), 1);
+            assert!(domain.find_job(path, plus_one.id()).is_some());
+        }
+
+        // The trailing slash is removed from the route on registration
+        let (route, _, _) = domain.find_matching_jobs("/numbers/one").unwrap();
+        assert_eq!(route, "/numbers/{n}");
+
+        let mut args = PathArgs(vec![(Arc::from("n"), String::from("two"))]);
+        let path = domain.find_path_for_job(plus_two.id(), &mut args).unwrap();
+        assert_eq!(path, String::from("/numbers/two"));
+    }
+
+    #[test]
+    fn it_does_not_trim_the_route_for_the_empty_key() {
+        let domain = Domain::new().job("/", update(plus_two));
+
+        // The route does not match the root
+        assert!(domain.find_matching_jobs("").is_none());
+
+        let (route, _, mut jobs) = domain.find_matching_jobs("/").unwrap();
+        assert_eq!(route, "/");
+        assert_eq!(jobs.next().map(|job| job.id()), Some(plus_two.id()));
+
+        let mut args = PathArgs(vec![]);
+        let path = domain.find_path_for_job(plus_two.id(), &mut args).unwrap();
+        assert_eq!(path, String::from("/"));
+    }
+
     #[test]
     fn test_error_if_unmatched_placeholders_remain() {
         let func = |file: View<()>| file;
@@ -1,30 +1,271 @@
-use std::collections::BTreeMap;
-use std::fmt::Debug;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::fmt::{self, Debug};
+use std::sync::Arc;
 
 use anyhow::{anyhow, Context as AnyhowCtx};
 use json_patch::{Patch, PatchOperation};
-use jsonptr::PointerBuf;
+use jsonptr::{Pointer, PointerBuf};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value;
 use thiserror::Error;
-use tracing::{debug_span, error, field, instrument, warn, Level, Span};
+use tracing::{debug_span, field, instrument, trace, warn, Level, Span};
 
 use crate::errors::{InternalError, MethodError, SerializationError};
 use crate::path::Path;
 use crate::system::System;
 use crate::task::{self, Context, Operation, Task};
-use crate::workflow::{WorkUnit, Workflow};
+use crate::workflow::{PlanResult, WorkUnit, Workflow};
 use crate::Dag;
 
 mod distance;
 mod domain;
 
 use distance::*;
+pub use distance::{Distance, NumberPolicy, PathComparators};
 pub use domain::*;
 
+/// Heuristic function used by the [A*](`SearchStrategy::AStar`) search strategy
+///
+/// The heuristic receives the [`Distance`] between a candidate state and the target
+/// and returns an estimate of the number of steps needed to reach the target.
+///
+/// The planner only finds workflows with the minimal number of steps if the heuristic is
+/// admissible, i.e. it never overestimates the number of remaining steps. Otherwise the search
+/// behaves as a greedy best-first search, which may find a workflow faster but with no
+/// guarantees about its length.
+pub type Heuristic = Arc<dyn Fn(&Distance) -> usize + Send + Sync>;
+
+/// Strategy used by the planner to explore the search space
+#[derive(Clone, Default)]
+pub enum SearchStrategy {
+    /// Explore candidates depth first, following the order given by the path, operation and
+    /// priority of the jobs. This is the default strategy.
+    #[default]
+    DepthFirst,
+
+    /// Explore candidates in order of `g + h`, where `g` is the number of steps taken so far and
+    /// `h` is the estimate given by the heuristic to reach the target.
+    ///
+    /// Use [`SearchStrategy::a_star`] for the default admissible heuristic.
+    AStar(Heuristic),
+}
+
+impl SearchStrategy {
+    /// Create an A* strategy with an admissible heuristic
+    ///
+    /// A single step may run multiple tasks, and a task may change any number of values, so the
+    /// only admissible estimate from the distance alone is that a state different from the
+    /// target needs at least one more step. The search finds workflows with the minimal number
+    /// of steps.
+    pub fn a_star() -> Self {
+        Self::AStar(Arc::new(|distance: &Distance| {
+            usize::from(!distance.is_empty())
+        }))
+    }
+
+    /// Create an A* strategy with a custom heuristic
+    ///
+    /// Workflows are only guaranteed to have the minimal number of steps if the heuristic is
+    /// [admissible](`Heuristic`). For instance, using the [count](`Distance::count`) of
+    /// differing values overestimates the remaining steps when tasks change multiple values or
+    /// run concurrently, but it may guide the search faster towards the target.
+    ///
+    /// ```rust
+    /// use mahler::worker::SearchStrategy;
+    ///
+    /// let greedy = SearchStrategy::a_star_with(|distance| distance.count());
+    /// ```
+    pub fn a_star_with<F>(heuristic: F) -> Self
+    where
+        F: Fn(&Distance) -> usize + Send + Sync + 'static,
+    {
+        Self::AStar(Arc::new(heuristic))
+    }
+}
+
+impl Debug for SearchStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DepthFirst => write!(f, "DepthFirst"),
+            Self::AStar(_) => write!(f, "AStar"),
+        }
+    }
+}
+
+/// Planner configuration options
+#[derive(Debug, Clone, Default)]
+pub struct PlannerConfig {
+    /// Discard candidates that do not take the state closer
+    /// to the target
+    pub prune_regressive: bool,
+
+    /// Strategy used to explore the search space
+    pub strategy: SearchStrategy,
+
+    /// Maximum number of actions that can act on the same path
+    /// within a single plan branch. Branches reaching the limit are discarded
+    pub path_visit_limit: Option<u32>,
+
+    /// Policy used to compare numbers in the state with the target. Defaults
+    /// to [`NumberPolicy::Exact`]
+    pub number_policy: NumberPolicy,
+
+    /// Custom comparators used instead of the default equality for values at
+    /// matching paths of the state and target
+    pub path_comparators: PathComparators,
+
+    /// Maximum depth of the search. Planning fails if a branch of the search
+    /// reaches this depth. Defaults to [`DEFAULT_MAX_DEPTH`] if not set
+    pub max_depth: Option<usize>,
+
+    /// Do not expand states that were already expanded by a different branch of the
+    /// search. This reduces redundant exploration when different task orderings lead
+    /// to the same state, but it may prevent finding a workflow if the branch that
+    /// first reached a state fails because of path visit limits or job groups
+    pub skip_visited: bool,
+}
+
+// Default maximum search depth used by the planner
+const DEFAULT_MAX_DEPTH: usize = 256;
+
+// The job selected for each job group in a plan
+type GroupChoices = BTreeMap<&'static str, String>;
+
+// The number of actions acting on each path in a plan
+type PathVisits = BTreeMap<Path, u32>;
+
+// A node in the search space: (current_state, parent_plan, branch, depth, group_choices, path_visits).
+// The plan of the node is the parent plan followed by the branch, which are only joined when the
+// node is expanded
+type SearchNode = (
+    System,
+    Workflow,
+    Dag<WorkUnit>,
+    usize,
+    GroupChoices,
+    PathVisits,
+);
+
+// The state of a search in progress, kept between calls to
+// allow resuming the search after a workflow is found
+struct Search {
+    frontier: Frontier,
+    // The states expanded so far, only used if skip_visited is enabled
+    visited: Visited,
+}
+
+// The set of states expanded by the search, indexed by their fingerprint. Different
+// states may have the same fingerprint, so the states are compared within each bucket
+#[derive(Default)]
+struct Visited(HashMap<u64, Vec<Value>>);
+
+impl Visited {
+    // Add the state to the set, returning false if it was already visited
+    fn insert(&mut self, state: &System) -> bool {
+        self.insert_with_fingerprint(state.fingerprint(), state.root())
+    }
+
+    fn insert_with_fingerprint(&mut self, fingerprint: u64, state: &Value) -> bool {
+        let bucket = self.0.entry(fingerprint).or_default();
+        if bucket.contains(state) {
+            return false;
+        }
+        bucket.push(state.clone());
+        true
+    }
+}
+
+// A search node ranked by its estimated cost. Nodes with the lower
+// cost are explored first, ties are broken in favor of the latest node
+// so the candidate ordering is preserved
+struct RankedNode {
+    cost: usize,
+    seq: usize,
+    node: SearchNode,
+}
+
+impl PartialEq for RankedNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost && self.seq == other.seq
+    }
+}
+
+impl Eq for RankedNode {}
+
+impl PartialOrd for RankedNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RankedNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap so the cost comparison is reversed
+        other.cost.cmp(&self.cost).then(self.seq.cmp(&other.seq))
+    }
+}
+
+// The set of nodes pending exploration. The heap keeps a counter of the
+// nodes pushed so far to assign increasing sequence numbers
+enum Frontier {
+    Stack(Vec<SearchNode>),
+    Heap(
+        BinaryHeap<RankedNode>,
+        usize,
+        Heuristic,
+        NumberPolicy,
+        PathComparators,
+    ),
+}
+
+impl Frontier {
+    fn new(config: &PlannerConfig) -> Self {
+        match &config.strategy {
+            SearchStrategy::DepthFirst => Frontier::Stack(Vec::new()),
+            SearchStrategy::AStar(heuristic) => Frontier::Heap(
+                BinaryHeap::new(),
+                0,
+                Arc::clone(heuristic),
+                config.number_policy,
+                config.path_comparators.clone(),
+            ),
+        }
+    }
+
+    // Add a node to the frontier, the normalized state is used
+    // to compute the heuristic and it is ignored for depth first search
+    fn push(&mut self, node: SearchNode, normalized: &Value, tgt: &Value) {
+        match self {
+            Frontier::Stack(stack) => stack.push(node),
+            Frontier::Heap(heap, pushed, heuristic, policy, comparators) => {
+                let (_, _, _, depth, _, _) = node;
+                let distance = Distance::with_comparators(normalized, tgt, *policy, comparators);
+                let cost = depth + heuristic(&distance);
+                *pushed += 1;
+                heap.push(RankedNode {
+                    cost,
+                    seq: *pushed,
+                    node,
+                });
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<SearchNode> {
+        match self {
+            Frontier::Stack(stack) => stack.pop(),
+            Frontier::Heap(heap, ..) => heap.pop().map(|ranked| ranked.node),
+        }
+    }
+}
+
 #[derive(Debug)]
-pub struct Planner(Domain);
+pub struct Planner {
+    domain: Domain,
+    config: PlannerConfig,
+}
 
 #[derive(Debug, Error)]
 enum SearchFailed {
@@ -37,9 +278,15 @@ enum SearchFailed {
     #[error("task empty")]
     EmptyTask,
 
+    #[error("child job not registered")]
+    ChildNotRegistered { parent: String, child: String },
+
     #[error("loop detected")]
     LoopDetected,
 
+    #[error("path visit limit reached")]
+    PathVisitLimit { path: Path, limit: u32 },
+
     // this is probably a bug if this error
     // happens
     #[error("internal error: {0:?}")]
@@ -84,6 +331,90 @@ fn longest_non_conflicting(paths: Vec<Path>) -> Vec<Path> {
     result
 }
 
+/// Parallel branches act on overlapping paths
+#[derive(Debug, Error)]
+#[error("parallel branches act on overlapping paths '{path_a}' and '{path_b}'")]
+struct ScopeConflict {
+    path_a: String,
+    path_b: String,
+}
+
+/// Checks that the actions in each of the given branches act on disjoint paths
+///
+/// Two branches conflict if a path written by an action in one of the branches is
+/// equal to or a prefix of a path read or written by an action in the other branch, or
+/// vice versa. Running conflicting branches concurrently would result in a race on the
+/// overlapping state.
+///
+/// # Returns
+///
+/// [`ScopeConflict`] with the first pair of conflicting paths found
+fn check_scope_conflicts(branches: &[Dag<WorkUnit>]) -> Result<(), ScopeConflict> {
+    // The paths read and written by each branch. Reads include the written paths
+    let branch_paths: Vec<(Vec<Path>, Vec<Path>)> = branches
+        .iter()
+        .map(|branch| {
+            branch
+                .values()
+                .fold((Vec::new(), Vec::new()), |(mut reads, mut writes), unit| {
+                    reads.extend(unit.reads());
+                    writes.extend(unit.writes());
+                    (reads, writes)
+                })
+        })
+        .collect();
+
+    let overlaps = |a: &Path, b: &Path| {
+        let (pa, pb): (&Pointer, &Pointer) = (a.as_ref(), b.as_ref());
+        pa.starts_with(pb) || pb.starts_with(pa)
+    };
+
+    for (i, (reads, writes)) in branch_paths.iter().enumerate() {
+        for (other_reads, other_writes) in branch_paths.iter().skip(i + 1) {
+            let conflict = writes
+                .iter()
+                .flat_map(|a| other_reads.iter().map(move |b| (a, b)))
+                .chain(
+                    reads
+                        .iter()
+                        .flat_map(|a| other_writes.iter().map(move |b| (a, b))),
+                )
+                .find(|(a, b)| overlaps(a, b));
+
+            if let Some((a, b)) = conflict {
+                return Err(ScopeConflict {
+                    path_a: a.to_string(),
+                    path_b: b.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects the path of every value in the state, starting with the root
+fn collect_paths(value: &Value, pointer: &mut PointerBuf, paths: &mut Vec<Path>) {
+    paths.push(Path::new(pointer));
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                pointer.push_back(key.as_str());
+                collect_paths(child, pointer, paths);
+                pointer.pop_back();
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                pointer.push_back(i);
+                collect_paths(child, pointer, paths);
+                pointer.pop_back();
+            }
+        }
+        _ => {}
+    }
+}
+
 /// Computes the longest common prefix over a list of `Path`
 fn longest_common_prefix<'a, I>(paths: I) -> Path
 where
@@ -122,8 +453,36 @@ where
     Path::new(&buf)
 }
 
+/// Merge the `other` target into `tgt`
+///
+/// Objects are merged recursively, any other values must be equal.
+/// Returns false if the targets conflict.
+pub(crate) fn merge_target(tgt: &mut Value, other: &Value) -> bool {
+    match (tgt, other) {
+        (Value::Object(tgt), Value::Object(other)) => {
+            for (key, value) in other {
+                match tgt.get_mut(key) {
+                    Some(existing) => {
+                        if !merge_target(existing, value) {
+                            return false;
+                        }
+                    }
+                    None => {
+                        tgt.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            true
+        }
+        (tgt, other) => tgt == other,
+    }
+}
+
 #[derive(Clone, PartialEq, Eq)]
 struct Candidate {
+    id: String,
+    // The ids of the jobs in the candidate, parallel candidates combine multiple jobs
+    jobs: Vec<String>,
     workflow: Dag<WorkUnit>,
     changes: Vec<PatchOperation>,
     path: Path,
@@ -131,6 +490,8 @@ struct Candidate {
     priority: u8,
     parallelizable: bool,
     is_method: bool,
+    groups: Vec<(&'static str, String)>,
+    visits: PathVisits,
 }
 
 impl PartialOrd for Candidate {
@@ -154,6 +515,120 @@ impl Ord for Candidate {
     }
 }
 
+/// Statistics about the search space explored by the planner
+///
+/// This helps to tune a domain when the planner fails to find a workflow after
+/// exploring a large number of states.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PlanStats {
+    /// Number of search nodes expanded
+    pub nodes_expanded: usize,
+    /// Maximum plan depth reached
+    pub max_depth: usize,
+    /// Candidates discarded because they would apply the same task to the same state
+    pub pruned_loop: usize,
+    /// Candidates discarded because the task condition did not hold
+    pub pruned_condition: usize,
+    /// Candidates discarded because they do not take the state closer to the target
+    pub pruned_regressive: usize,
+    /// Candidates discarded because a different job of the same group was selected
+    pub pruned_group: usize,
+    /// Nodes not expanded because the state was already expanded by a different branch
+    pub pruned_visited: usize,
+    /// Candidates discarded because the branch reached the visit limit for a path
+    pub pruned_visit_limit: usize,
+    /// Nodes not expanded because the branch reached the maximum search depth
+    pub pruned_max_depth: usize,
+    /// Statistics for each job tried by the planner, only collected if set
+    pub jobs: Option<BTreeMap<String, JobStats>>,
+}
+
+impl PlanStats {
+    /// Update the statistics for the job with the given id, if collecting job statistics
+    fn record_job<F: FnOnce(&mut JobStats)>(&mut self, id: &str, update: F) {
+        if let Some(jobs) = self.jobs.as_mut() {
+            update(jobs.entry(id.to_string()).or_default());
+        }
+    }
+}
+
+/// Statistics about how a job was used by the planner during a search
+///
+/// This allows to identify jobs that are often matched by changes in the state, but
+/// never selected for the workflow.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct JobStats {
+    /// Number of times the job route and operation matched a change to the target
+    pub matched: usize,
+    /// Number of times a task for the job was selected as a candidate for the workflow
+    pub selected: usize,
+    /// Candidates discarded because a different job of the same group was selected
+    pub pruned_group: usize,
+    /// Candidates discarded because the job predicate did not hold
+    pub pruned_predicate: usize,
+    /// Candidates discarded because they would apply the same task to the same state
+    pub pruned_loop: usize,
+    /// Candidates discarded because the task condition did not hold
+    pub pruned_condition: usize,
+    /// Candidates discarded because the task produced no changes
+    pub pruned_empty: usize,
+    /// Candidates discarded because they do not take the state closer to the target
+    pub pruned_regressive: usize,
+    /// Candidates discarded because the branch reached the visit limit for a path
+    pub pruned_visit_limit: usize,
+}
+
+impl JobStats {
+    /// Return the total number of times the job was discarded by the planner
+    pub fn pruned(&self) -> usize {
+        self.pruned_group
+            + self.pruned_predicate
+            + self.pruned_loop
+            + self.pruned_condition
+            + self.pruned_empty
+            + self.pruned_regressive
+            + self.pruned_visit_limit
+    }
+}
+
+/// A problem found when trying a job against a sample state
+///
+/// See [`Worker::validate`](`crate::worker::Worker::validate`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobIssue {
+    /// The id of the job
+    pub job: String,
+    /// The path of the sample state where the job was tried, or the job route if the route
+    /// does not match any path of the sample
+    pub path: String,
+    /// Description of the problem
+    pub reason: String,
+}
+
+impl fmt::Display for JobIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "job '{}' at '{}': {}", self.job, self.path, self.reason)
+    }
+}
+
+impl fmt::Display for PlanStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "nodes expanded: {}, max depth: {}, pruned: {} loop, {} condition, {} regressive, {} group, {} visited, {} visit limit, {} max depth",
+            self.nodes_expanded,
+            self.max_depth,
+            self.pruned_loop,
+            self.pruned_condition,
+            self.pruned_regressive,
+            self.pruned_group,
+            self.pruned_visited,
+            self.pruned_visit_limit,
+            self.pruned_max_depth
+        )
+    }
+}
+
 #[derive(Debug, Error)]
 pub(crate) enum Error {
     #[error(transparent)]
@@ -162,8 +637,13 @@ pub(crate) enum Error {
     #[error(transparent)]
     Task(#[from] task::Error),
 
-    #[error("workflow not found")]
-    NotFound,
+    /// No workflow was found. Includes the reason if a task declared
+    /// the target as unreachable
+    #[error("workflow not found{reason} ({0})", reason = .1.as_ref().map(|r| format!(": {r}")).unwrap_or_default())]
+    NotFound(Box<PlanStats>, Option<String>),
+
+    #[error("method '{parent}' returned a task for job '{child}', which is not registered in the domain")]
+    ChildJobNotRegistered { parent: String, child: String },
 
     #[error(transparent)]
     Internal(#[from] InternalError),
@@ -171,11 +651,128 @@ pub(crate) enum Error {
 
 impl Planner {
     pub fn new(domain: Domain) -> Self {
-        Self(domain)
+        Self {
+            domain,
+            config: PlannerConfig::default(),
+        }
+    }
+
+    pub fn with_config(self, config: PlannerConfig) -> Self {
+        Self { config, ..self }
+    }
+
+    /// Use a custom comparator for values at paths matching the pattern
+    ///
+    /// See [`PlannerConfig::path_comparators`]
+    #[cfg(test)]
+    pub fn with_path_comparator<F>(mut self, pattern: &str, comparator: F) -> Self
+    where
+        F: Fn(&Value, &Value) -> bool + Send + Sync + 'static,
+    {
+        self.config
+            .path_comparators
+            .insert(pattern, Arc::new(comparator));
+        self
     }
 
     pub fn domain(&self) -> &Domain {
-        &self.0
+        &self.domain
+    }
+
+    pub fn config(&self) -> &PlannerConfig {
+        &self.config
+    }
+
+    /// Check that the target can be converted to the internal representation used for planning
+    pub fn check_target<S: Serialize>(&self, tgt: &S) -> Result<(), SerializationError> {
+        serde_json::to_value(tgt)?;
+        Ok(())
+    }
+
+    /// Try every registered job against a sample state
+    ///
+    /// A task is created for each job matching a path of the sample, using the value at that
+    /// path as target, and it is dry-run (actions) or expanded (methods) on the sample. Panics
+    /// and errors, other than failed conditions or tasks producing no changes, are reported as
+    /// issues. Jobs whose route does not match any path of the sample cannot be tried and are
+    /// also reported as issues.
+    pub fn validate<S: Serialize>(&self, sample: S) -> Result<Vec<JobIssue>, SerializationError> {
+        let system = System::try_from(sample)?;
+
+        let mut paths = Vec::new();
+        collect_paths(system.root(), &mut PointerBuf::new(), &mut paths);
+
+        let mut issues = Vec::new();
+        let mut matched_routes = HashSet::new();
+        for path in paths {
+            let Some((route, args, jobs)) = self.domain.find_matching_jobs(path.as_str()) else {
+                continue;
+            };
+            matched_routes.insert(route);
+
+            let pointer = path.as_ref();
+            let value = pointer.resolve(system.root()).unwrap_or(&Value::Null);
+            for job in jobs {
+                // Delete tasks are always given a null target by the planner
+                let target = if job.operation() == &Operation::Delete {
+                    Value::Null
+                } else {
+                    value.clone()
+                };
+
+                let context = Context {
+                    path: path.clone(),
+                    args: args.clone(),
+                    target,
+                    ..Context::default()
+                };
+                let task = job.new_task(context);
+
+                let reason = match self.try_task(
+                    &task,
+                    &system,
+                    Workflow::default(),
+                    &mut Vec::new(),
+                    &mut PathVisits::new(),
+                ) {
+                    Ok(_)
+                    | Err(SearchFailed::EmptyTask)
+                    | Err(SearchFailed::LoopDetected)
+                    | Err(SearchFailed::PathVisitLimit { .. })
+                    | Err(SearchFailed::BadTask(
+                        task::Error::ConditionFailed | task::Error::Unreachable(_),
+                    )) => continue,
+                    Err(SearchFailed::BadTask(err)) => err.to_string(),
+                    Err(SearchFailed::ChildNotRegistered { child, .. }) => {
+                        format!("returned a task for job '{child}', which is not registered")
+                    }
+                    Err(err) => err.to_string(),
+                };
+
+                warn!(job = job.id(), path = %path, "job validation failed: {reason}");
+                issues.push(JobIssue {
+                    job: job.id().to_string(),
+                    path: path.to_string(),
+                    reason,
+                });
+            }
+        }
+
+        for (route, job) in self.domain.registered_jobs() {
+            if matched_routes.contains(route) {
+                continue;
+            }
+
+            let reason = "the job route does not match any path of the sample".to_string();
+            warn!(job = job.id(), route, "job validation failed: {reason}");
+            issues.push(JobIssue {
+                job: job.id().to_string(),
+                path: route.to_string(),
+                reason,
+            });
+        }
+
+        Ok(issues)
     }
 
     #[instrument(level = "trace", skip_all, fields(task=?task, changes=?pending_changes, selected=field::Empty), err(level=Level::TRACE))]
@@ -185,6 +782,7 @@ impl Planner {
         cur_state: &System,
         cur_plan: Workflow,
         pending_changes: &mut Vec<PatchOperation>,
+        visits: &mut PathVisits,
     ) -> Result<Workflow, SearchFailed> {
         match task {
             Task::Action(action) => {
@@ -196,9 +794,22 @@ impl Planner {
                     return Err(SearchFailed::LoopDetected)?;
                 }
 
+                // Count the visits to the action path within the branch
+                if let Some(limit) = self.config.path_visit_limit {
+                    let path = &action.context().path;
+                    let count = visits.entry(path.clone()).or_default();
+                    if *count >= limit {
+                        return Err(SearchFailed::PathVisitLimit {
+                            path: path.clone(),
+                            limit,
+                        });
+                    }
+                    *count += 1;
+                }
+
                 // Simulate the task and get the list of changes
                 let Patch(changes) = action.dry_run(cur_state).map_err(SearchFailed::BadTask)?;
-                if changes.is_empty() {
+                if changes.is_empty() && !action.allows_empty() {
                     return Err(SearchFailed::EmptyTask);
                 }
 
@@ -224,22 +835,47 @@ impl Planner {
                 for mut t in tasks.into_iter() {
                     // The subtask is not allowed to override arguments
                     // in the parent task, so we first make sure to propagate
-                    // arguments from the parent
+                    // arguments from the parent, unless the subtask explicitly
+                    // overrides them
                     for (k, v) in method.context().args.iter() {
-                        t = t.with_arg(k, v);
+                        if !t.context().is_override(k) {
+                            t = t.with_arg(k, v);
+                        }
                     }
 
                     let task_id = t.id().to_string();
+
+                    // Built-in tasks that are not registered in the domain
+                    // take the path of the parent method
+                    if !self.domain.contains_job(&task_id) {
+                        if let Some(task) = task::builtin(&task_id) {
+                            let context = t.context().to_owned();
+                            extended_tasks.push(
+                                task.with_context(context)
+                                    .with_path(method.context().path.as_str()),
+                            );
+                            continue;
+                        }
+                    }
+
+                    // The child job must be registered in the domain
+                    if !self.domain.contains_job(&task_id) {
+                        return Err(SearchFailed::ChildNotRegistered {
+                            parent: method.id().to_string(),
+                            child: task_id,
+                        });
+                    }
+
                     let Context { args, .. } = t.context_mut();
 
                     // Find the job path on the domain list
-                    let path = self.0.find_path_for_job(&task_id, args)?;
+                    let path = self.domain.find_path_for_job(&task_id, args)?;
 
                     // Using the path, now find the actual job on the domain.
                     // The domain job includes metadata like the description that
                     // we want to use in the workflow
                     let job = self
-                        .0
+                        .domain
                         .find_job(&path, &task_id)
                         // this should never happen
                         .ok_or(anyhow!("failed to find job for path {path}"))?;
@@ -269,61 +905,252 @@ impl Planner {
 
                 if parallelizable {
                     let mut branches = vec![];
+                    let mut branch_changes = vec![];
+                    let mut branch_visits = visits.clone();
 
                     // Create a branch for each task
-                    for task in extended_tasks {
-                        let Workflow(dag) =
-                            self.try_task(&task, cur_state, Workflow::default(), pending_changes)?;
+                    for task in &extended_tasks {
+                        let Workflow(dag) = self.try_task(
+                            task,
+                            cur_state,
+                            Workflow::default(),
+                            &mut branch_changes,
+                            &mut branch_visits,
+                        )?;
 
                         branches.push(dag);
                     }
 
-                    // Extend the current plan with the forking dag
-                    cur_plan = Workflow(cur_plan.0 + Dag::new(branches));
-                } else {
-                    // Clone the state in order to apply sequential changes
-                    let mut cur_state = cur_state.clone();
-
-                    // If the task is not parallelizable, run tasks in sequence making
-                    // sure to apply changes before calling the next task
-                    for task in extended_tasks {
-                        let mut changes = vec![];
-                        let Workflow(dag) =
-                            self.try_task(&task, &cur_state, cur_plan, &mut changes)?;
-
-                        // Apply changes before the next task
-                        cur_state.patch(Patch(changes.clone())).with_context(|| {
-                            format!("failed to apply patch {pending_changes:?}")
-                        })?;
-
-                        // Add the changes to the pending list
-                        pending_changes.extend(changes);
-                        cur_plan = Workflow(dag);
+                    // Sub-tasks may expand to actions outside the task path, so
+                    // make sure the branches are still safe to run concurrently
+                    match check_scope_conflicts(&branches) {
+                        Ok(()) => {
+                            pending_changes.extend(branch_changes);
+                            *visits = branch_visits;
+
+                            // Extend the current plan with the forking dag
+                            return Ok(Workflow(cur_plan.0 + Dag::new(branches)));
+                        }
+                        Err(err) => {
+                            trace!("{err} ... falling back to sequential execution");
+                        }
                     }
                 }
 
+                // Clone the state in order to apply sequential changes
+                let mut cur_state = cur_state.clone();
+
+                // If the task is not parallelizable, run tasks in sequence making
+                // sure to apply changes before calling the next task
+                for task in extended_tasks {
+                    let mut changes = vec![];
+                    let Workflow(dag) =
+                        self.try_task(&task, &cur_state, cur_plan, &mut changes, visits)?;
+
+                    // Apply changes before the next task
+                    cur_state
+                        .patch(Patch(changes.clone()))
+                        .with_context(|| format!("failed to apply patch {pending_changes:?}"))?;
+
+                    // Add the changes to the pending list
+                    pending_changes.extend(changes);
+                    cur_plan = Workflow(dag);
+                }
+
                 // Include changes in the returned plan
                 Ok(cur_plan)
             }
         }
     }
 
+    /// Find a single workflow reaching all the given targets
+    ///
+    /// The targets are merged into a single target before planning, meaning steps
+    /// shared by multiple targets are only included once in the resulting workflow.
+    /// The search fails with [`Error::NotFound`] if two targets require different values
+    /// for the same path.
+    pub(crate) fn find_workflow_multi<T>(
+        &self,
+        system: &System,
+        targets: &[Value],
+    ) -> Result<Workflow, Error>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let mut tgt = match targets.first() {
+            Some(tgt) => tgt.clone(),
+            None => return Ok(Workflow::default()),
+        };
+
+        for other in targets.iter().skip(1) {
+            if !merge_target(&mut tgt, other) {
+                warn!("conflicting targets, no workflow can reach all of them");
+                return Err(Error::NotFound(Box::default(), None));
+            }
+        }
+
+        self.find_workflow::<T>(system, &tgt)
+    }
+
+    /// Find a workflow to the target, reporting explicitly if the target was already reached
+    pub(crate) fn find_plan<T>(&self, system: &System, tgt: &Value) -> Result<PlanResult, Error>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        self.find_workflow::<T>(system, tgt).map(PlanResult::from)
+    }
+
+    /// Find a workflow to the target along with the predicted system states
+    ///
+    /// The states are calculated from the changes of each action in the workflow, as
+    /// predicted during planning, and are returned in the order actions are visited by
+    /// [`Dag::values`](crate::Dag::values).
+    pub(crate) fn find_workflow_with_states<T>(
+        &self,
+        system: &System,
+        tgt: &Value,
+    ) -> Result<(Workflow, Vec<Value>), Error>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let workflow = self.find_workflow::<T>(system, tgt)?;
+        let states = workflow.predicted_states(system)?;
+        Ok((workflow, states))
+    }
+
+    /// Find a workflow to the target, collecting statistics for every job tried by the planner
+    #[instrument(name = "find_workflow", skip_all, fields(ini=%system.root(), tgt=%tgt))]
+    pub(crate) fn find_plan_with_job_stats<T>(
+        &self,
+        system: &System,
+        tgt: &Value,
+    ) -> (Result<PlanResult, Error>, BTreeMap<String, JobStats>)
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let mut stats = PlanStats {
+            jobs: Some(BTreeMap::new()),
+            ..PlanStats::default()
+        };
+        let res = self.search::<T>(system, tgt, &mut stats);
+        (res.map(PlanResult::from), stats.jobs.unwrap_or_default())
+    }
+
     #[instrument(skip_all, fields(ini=%system.root(), tgt=%tgt), err, ret(Display))]
     pub(crate) fn find_workflow<T>(&self, system: &System, tgt: &Value) -> Result<Workflow, Error>
     where
         T: Serialize + DeserializeOwned,
     {
-        // The search stack stores (current_state, current_plan, depth)
-        let mut stack = vec![(system.clone(), Workflow::default(), 0)];
+        self.search::<T>(system, tgt, &mut PlanStats::default())
+    }
+
+    /// Search for a workflow to the target, updating the given search statistics
+    fn search<T>(
+        &self,
+        system: &System,
+        tgt: &Value,
+        stats: &mut PlanStats,
+    ) -> Result<Workflow, Error>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let mut search = self.start_search(system, tgt);
+        match self.resume_search::<T>(&mut search, tgt, stats)? {
+            Some(workflow) => Ok(workflow),
+            // No candidate plan reached the goal state
+            None => Err(Error::NotFound(Box::new(stats.clone()), None)),
+        }
+    }
+
+    /// Iterate over the workflows to the target, in the order they are found
+    ///
+    /// The search is resumed on every call to `next`, allowing callers to stop early or
+    /// to choose between alternative workflows. The iterator ends when no more workflows
+    /// can be found, or after yielding an error.
+    pub(crate) fn plan_iter<'a, T>(
+        &'a self,
+        system: &System,
+        tgt: &Value,
+    ) -> impl Iterator<Item = Result<Workflow, Error>> + 'a
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let tgt = tgt.clone();
+        let mut search = Some(self.start_search(system, &tgt));
+        let mut stats = PlanStats::default();
+        std::iter::from_fn(move || {
+            let res = self.resume_search::<T>(search.as_mut()?, &tgt, &mut stats);
+            match res {
+                Ok(Some(workflow)) => Some(Ok(workflow)),
+                Ok(None) => {
+                    search = None;
+                    None
+                }
+                Err(err) => {
+                    search = None;
+                    Some(Err(err))
+                }
+            }
+        })
+    }
+
+    /// Initialize the search frontier with the given system state
+    fn start_search(&self, system: &System, tgt: &Value) -> Search {
+        // Keep a snapshot of the initial state for tasks to compare with
+        let mut system = system.clone();
+        system.set_baseline();
+
+        // The search frontier stores (current_state, parent_plan, branch, depth, ...)
+        let mut frontier = Frontier::new(&self.config);
+        frontier.push(
+            (
+                system.clone(),
+                Workflow::default(),
+                Dag::default(),
+                0,
+                GroupChoices::new(),
+                PathVisits::new(),
+            ),
+            system.root(),
+            tgt,
+        );
+
+        Search {
+            frontier,
+            visited: Visited::default(),
+        }
+    }
+
+    /// Continue the search until the next workflow to the target is found
+    ///
+    /// Returns `None` if there are no more nodes left to explore
+    fn resume_search<T>(
+        &self,
+        search: &mut Search,
+        tgt: &Value,
+        stats: &mut PlanStats,
+    ) -> Result<Option<Workflow>, Error>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let Search { frontier, visited } = search;
         let find_workflow_span = Span::current();
 
-        while let Some((cur_state, cur_plan, depth)) = stack.pop() {
+        let max_depth = self.config.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+
+        while let Some((cur_state, parent_plan, branch, depth, choices, visits)) = frontier.pop() {
             // Prevent infinite recursion (e.g., from buggy tasks or recursive methods)
-            if depth >= 256 {
-                warn!(parent: &find_workflow_span, "reached max search depth (256)");
-                return Err(Error::NotFound)?;
+            if depth >= max_depth {
+                warn!(parent: &find_workflow_span, "reached max search depth ({max_depth})");
+                stats.pruned_max_depth += 1;
+                return Err(Error::NotFound(Box::new(stats.clone()), None))?;
             }
 
+            // Extend a copy of the parent plan. Dag nodes are shared between clones, so
+            // concatenating to the parent plan would modify the plan of sibling nodes
+            let parent_plan = Dag::from_steps(parent_plan.as_dag().to_steps());
+            let cur_plan = Workflow(parent_plan + branch);
+
             // Normalize state: deserialize into T and re-serialize to remove internal fields
             let cur = cur_state
                 .state::<T>()
@@ -331,13 +1158,27 @@ impl Planner {
                 .map_err(SerializationError::from)?;
 
             // Compute the difference between current and target state
-            let distance = Distance::new(&cur, tgt);
+            let distance = Distance::with_comparators(
+                &cur,
+                tgt,
+                self.config.number_policy,
+                &self.config.path_comparators,
+            );
 
             // If no difference, we’ve reached the goal
             if distance.is_empty() {
-                return Ok(cur_plan);
+                return Ok(Some(cur_plan));
             }
 
+            // Skip the node if the state was already expanded by another branch
+            if self.config.skip_visited && !visited.insert(&cur) {
+                stats.pruned_visited += 1;
+                continue;
+            }
+
+            stats.nodes_expanded += 1;
+            stats.max_depth = stats.max_depth.max(depth);
+
             let next_span = debug_span!("find_next", cur = %&cur_state.root());
             let _enter = next_span.enter();
 
@@ -349,7 +1190,9 @@ impl Planner {
                 let path = Path::new(op.path());
 
                 // Retrieve matching jobs at this path
-                if let Some((args, jobs)) = self.0.find_matching_jobs(path.as_str()) {
+                if let Some((route, args, jobs)) = self.domain.find_matching_jobs(path.as_str()) {
+                    // The path may not exist on the target (e.g. for delete
+                    // operations), in which case the task gets a null target
                     let pointer = path.as_ref();
                     let target = pointer.resolve(tgt).unwrap_or(&Value::Null);
 
@@ -357,13 +1200,48 @@ impl Planner {
                         path: path.clone(),
                         args,
                         target: target.clone(),
+                        ..Context::default()
                     };
 
-                    // Filter `None` jobs from the list
-                    for job in jobs.filter(|j| j.operation() != &Operation::None) {
-                        if op.matches(job.operation()) || job.operation() == &Operation::Any {
+                    // Filter `None` jobs from the list, these can only
+                    // be used as part of a method. Fallback jobs are tried last
+                    let (jobs, fallbacks): (Vec<_>, Vec<_>) = jobs
+                        .filter(|j| j.operation() != &Operation::None)
+                        .partition(|j| !j.is_fallback());
+
+                    // Keep track of whether a job is applicable to the operation
+                    let mut selected = false;
+                    for job in jobs.into_iter().chain(fallbacks) {
+                        if op.matches(job.operation()) {
+                            // Only try fallback jobs if no other job is applicable
+                            if job.is_fallback() && selected {
+                                trace!(task = job.id(), path = %path, depth, "candidate skipped: fallback");
+                                continue;
+                            }
+                            stats.record_job(job.id(), |s| s.matched += 1);
+
+                            // Skip the job if a different job of the same group was already
+                            // selected in the current plan
+                            if let Some(group) = job.group() {
+                                if choices.get(group).is_some_and(|id| id != job.id()) {
+                                    trace!(task = job.id(), path = %path, depth, group, "candidate skipped: group conflict");
+                                    stats.pruned_group += 1;
+                                    stats.record_job(job.id(), |s| s.pruned_group += 1);
+                                    continue;
+                                }
+                            }
+
+                            // Skip the job if its predicate does not hold for the path
+                            let current = pointer.resolve(cur_state.root()).unwrap_or(&Value::Null);
+                            if !job.applies_to(current, target) {
+                                trace!(task = job.id(), path = %path, depth, "candidate skipped: predicate failed");
+                                stats.record_job(job.id(), |s| s.pruned_predicate += 1);
+                                continue;
+                            }
+
                             let task = job.new_task(context.clone());
                             let mut changes = Vec::new();
+                            let mut task_visits = visits.clone();
 
                             // Try applying this task to the current state
                             match self.try_task(
@@ -371,9 +1249,15 @@ impl Planner {
                                 &cur_state,
                                 Workflow::default(),
                                 &mut changes,
+                                &mut task_visits,
                             ) {
                                 Ok(Workflow(workflow)) if !changes.is_empty() => {
+                                    trace!(task = task.id(), path = %path, depth, "candidate selected");
+                                    stats.record_job(job.id(), |s| s.selected += 1);
+                                    selected = true;
                                     candidates.push(Candidate {
+                                        id: task.id().to_string(),
+                                        jobs: vec![task.id().to_string()],
                                         workflow,
                                         changes,
                                         path: path.clone(),
@@ -381,26 +1265,64 @@ impl Planner {
                                         is_method: task.is_method(),
                                         operation: job.operation().clone(),
                                         priority: job.priority(),
+                                        groups: job
+                                            .group()
+                                            .map(|group| (group, job.id().to_string()))
+                                            .into_iter()
+                                            .collect(),
+                                        visits: task_visits,
                                     });
                                 }
 
                                 // Non-critical errors are ignored (loop, empty, condition failure)
-                                Err(SearchFailed::LoopDetected)
-                                | Err(SearchFailed::EmptyTask)
-                                | Err(SearchFailed::BadTask(task::Error::ConditionFailed)) => {}
+                                Err(SearchFailed::LoopDetected) => {
+                                    trace!(task = task.id(), path = %path, depth, "candidate skipped: loop detected");
+                                    stats.pruned_loop += 1;
+                                    stats.record_job(job.id(), |s| s.pruned_loop += 1);
+                                }
+                                Err(SearchFailed::BadTask(task::Error::ConditionFailed)) => {
+                                    trace!(task = task.id(), path = %path, depth, "candidate skipped: condition failed");
+                                    stats.pruned_condition += 1;
+                                    stats.record_job(job.id(), |s| s.pruned_condition += 1);
+                                }
+                                Err(SearchFailed::PathVisitLimit { path, limit }) => {
+                                    trace!(task = task.id(), path = %path, depth, limit, "candidate skipped: path visit limit reached");
+                                    stats.pruned_visit_limit += 1;
+                                    stats.record_job(job.id(), |s| s.pruned_visit_limit += 1);
+                                }
+                                Ok(_) | Err(SearchFailed::EmptyTask) => {
+                                    stats.record_job(job.id(), |s| s.pruned_empty += 1);
+                                }
+
+                                // The task declared the target unreachable, stop the search
+                                Err(SearchFailed::BadTask(task::Error::Unreachable(reason))) => {
+                                    warn!(parent: &find_workflow_span, "task {} declared the target unreachable: {reason}", task.id());
+                                    return Err(Error::NotFound(
+                                        Box::new(stats.clone()),
+                                        Some(reason),
+                                    ));
+                                }
 
                                 // Critical internal errors terminate the search
                                 Err(SearchFailed::Internal(err)) => {
                                     return Err(InternalError::from(err))?;
                                 }
 
+                                // The method references a job that is not in the domain
+                                Err(SearchFailed::ChildNotRegistered { parent, child }) => {
+                                    if cfg!(debug_assertions) {
+                                        return Err(Error::ChildJobNotRegistered { parent, child });
+                                    }
+                                    warn!(parent: &find_workflow_span, "task {} failed: child job {} is not registered ... ignoring", task.id(), child);
+                                }
+
                                 // Method expansion failure
                                 Err(SearchFailed::BadMethod(err)) => {
                                     let err = MethodError::new(err);
                                     if cfg!(debug_assertions) {
                                         return Err(task::Error::from(err))?;
                                     }
-                                    warn!(parent: &find_workflow_span, "task {} failed: {} ... ignoring", task.id(), err);
+                                    warn!(parent: &find_workflow_span, "task {} at route {} failed: {} ... ignoring", task.id(), route, err);
                                 }
 
                                 // Other task failure (non-debug: warn and skip)
@@ -408,10 +1330,8 @@ impl Planner {
                                     if cfg!(debug_assertions) {
                                         return Err(err)?;
                                     }
-                                    warn!(parent: &find_workflow_span, "task {} failed: {} ... ignoring", task.id(), err);
+                                    warn!(parent: &find_workflow_span, "task {} at route {} failed: {} ... ignoring", task.id(), route, err);
                                 }
-
-                                _ => {}
                             }
                         }
                     }
@@ -424,34 +1344,72 @@ impl Planner {
 
             // Find candidates that can be parallelized
             let mut parallelizable: BTreeMap<Path, Candidate> = BTreeMap::new();
+            let mut parallel_groups = GroupChoices::new();
             for candidate in candidates.iter() {
+                // Jobs in the same group cannot be combined with each other
+                let conflicts_with_group = candidate
+                    .groups
+                    .iter()
+                    .any(|(group, id)| parallel_groups.get(group).is_some_and(|other| other != id));
+
                 // If the candidate is scoped and the path belongs to the non conflicting path list
                 // then add the candidate to the parallelizable list if there isn't a path already
                 if candidate.parallelizable
+                    && !conflicts_with_group
                     && !parallelizable.contains_key(&candidate.path)
                     && non_conflicting_paths.iter().any(|p| p == &candidate.path)
                 {
+                    parallel_groups.extend(candidate.groups.iter().cloned());
                     parallelizable.insert(candidate.path.clone(), candidate.clone());
                 }
             }
 
             if parallelizable.len() > 1 {
+                // Candidate workflows may include actions outside the candidate path
+                // (e.g. methods), so the branches are only combined if they are disjoint
+                let branches: Vec<_> = parallelizable
+                    .values()
+                    .map(|c| c.workflow.clone())
+                    .collect();
+                if let Err(err) = check_scope_conflicts(&branches) {
+                    trace!(parent: &find_workflow_span, "{err} ... skipping parallel candidate");
+                    parallelizable.clear();
+                }
+            }
+
+            if parallelizable.len() > 1 {
+                let mut ids = Vec::new();
+                let mut jobs = Vec::new();
+                let mut groups = Vec::new();
                 let mut branches = Vec::new();
                 let mut changes = Vec::new();
                 let mut total_priority = 0;
+                let mut parallel_visits = PathVisits::new();
                 // The path for the candidate is the longest common prefix between child paths
                 // XXX: maybe we need to skip the candidate if there is a method for the
                 // same path?
                 let path = longest_common_prefix(parallelizable.keys());
                 for Candidate {
+                    id,
+                    jobs: branch_jobs,
                     workflow,
                     changes: pending,
                     priority,
+                    groups: branch_groups,
+                    visits: branch_visits,
                     ..
                 } in parallelizable.into_values()
                 {
+                    ids.push(id);
+                    jobs.extend(branch_jobs);
+                    groups.extend(branch_groups);
                     branches.push(workflow);
                     changes.extend(pending);
+                    // Branches act on different paths, so keep the highest count for each path
+                    for (path, count) in branch_visits {
+                        let total = parallel_visits.entry(path).or_default();
+                        *total = (*total).max(count);
+                    }
                     // Aggregate each branch priority
                     total_priority += priority;
                 }
@@ -460,6 +1418,8 @@ impl Planner {
                 // NOTE: we could keep adding branches to the DAG as long as there are non conflicting
                 // paths with the candidate path. For now we just do this operation once
                 candidates.push(Candidate {
+                    id: ids.join(", "),
+                    jobs,
                     workflow: Dag::new(branches),
                     changes,
                     parallelizable: true,
@@ -468,15 +1428,41 @@ impl Planner {
                     is_method: false,
                     operation: Operation::Update,
                     priority: total_priority,
+                    groups,
+                    visits: parallel_visits,
                 })
             }
 
             // sort candidates
             candidates.sort();
 
+            // Measure of the distance to the target, only needed if
+            // regressive candidates are pruned. Values are compared using
+            // the configured number policy and path comparators
+            let magnitude = |state: &Value| {
+                magnitude_with_comparators(
+                    state,
+                    tgt,
+                    self.config.number_policy,
+                    &self.config.path_comparators,
+                )
+            };
+            let cur_magnitude = if self.config.prune_regressive {
+                magnitude(cur.root())
+            } else {
+                0.0
+            };
+
             // For each candidate add a new plan to the stack
             for Candidate {
-                workflow, changes, ..
+                id,
+                jobs,
+                workflow,
+                changes,
+                path,
+                groups,
+                visits,
+                ..
             } in candidates.into_iter()
             {
                 let mut new_sys = cur_state.clone();
@@ -485,17 +1471,51 @@ impl Planner {
                     .with_context(|| "failed to apply patch")
                     .map_err(InternalError::from)?;
 
-                // Extend current plan
-                let Workflow(cur_plan) = cur_plan.clone();
-                let new_plan = Workflow(cur_plan + workflow);
+                // Normalize the new state if needed for pruning or ranking
+                let next = if self.config.prune_regressive
+                    || matches!(self.config.strategy, SearchStrategy::AStar(_))
+                {
+                    let next = new_sys
+                        .state::<T>()
+                        .and_then(System::try_from)
+                        .map_err(SerializationError::from)?;
+                    next.root().clone()
+                } else {
+                    Value::Null
+                };
+
+                // Skip the candidate if it does not take the state closer to the target
+                if self.config.prune_regressive && magnitude(&next) >= cur_magnitude {
+                    trace!(task = id, path = %path, depth, "candidate skipped: regressive");
+                    stats.pruned_regressive += 1;
+                    for job in jobs.iter() {
+                        stats.record_job(job, |s| s.pruned_regressive += 1);
+                    }
+                    continue;
+                }
 
-                // Add updated plan/state to the search stack
-                stack.push((new_sys, new_plan, depth + 1));
+                // Add updated plan/state to the search frontier. The plan is only
+                // copied if the node is selected for expansion
+                // Record the group choices for the new plan
+                let mut choices = choices.clone();
+                choices.extend(groups);
+
+                frontier.push(
+                    (
+                        new_sys,
+                        cur_plan.clone(),
+                        workflow,
+                        depth + 1,
+                        choices,
+                        visits,
+                    ),
+                    &next,
+                    tgt,
+                );
             }
         }
 
-        // No candidate plan reached the goal state
-        Err(Error::NotFound)?
+        Ok(None)
     }
 }
 
@@ -507,7 +1527,7 @@ mod tests {
     use std::fmt::Display;
 
     use super::*;
-    use crate::extract::{Args, System, Target, View};
+    use crate::extract::{Args, Pointer, Previous, System, Target, View};
     use crate::{dag, par, task::*};
     use crate::{seq, Dag};
     use tracing_subscriber::fmt::format::FmtSpan;
@@ -570,11 +1590,37 @@ mod tests {
         counter
     }
 
-    pub fn find_plan<S>(planner: Planner, cur: S, tgt: S) -> Result<Workflow, super::Error>
-    where
-        S: Serialize + DeserializeOwned,
-    {
-        let tgt = serde_json::to_value(tgt).expect("failed to serialize target state");
+    fn triple_increment(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+        if *counter < tgt {
+            *counter += 3;
+        }
+
+        counter
+    }
+
+    fn remove_counter(counter: Pointer<i32>, Target(tgt): Target<Option<i32>>) -> Pointer<i32> {
+        // The target for a removed path is null
+        assert!(tgt.is_none());
+        counter.unassign()
+    }
+
+    fn notify() -> Effect<()> {
+        Effect::side_effect(|| async { Ok(()) })
+    }
+
+    fn plus_one_and_notify(counter: View<i32>, Target(tgt): Target<i32>) -> Vec<Task> {
+        if *counter < tgt {
+            return vec![plus_one.with_target(tgt), notify.into_task()];
+        }
+
+        vec![]
+    }
+
+    pub fn find_plan<S>(planner: Planner, cur: S, tgt: S) -> Result<Workflow, super::Error>
+    where
+        S: Serialize + DeserializeOwned,
+    {
+        let tgt = serde_json::to_value(tgt).expect("failed to serialize target state");
 
         let system =
             crate::system::System::try_from(cur).expect("failed to serialize current state");
@@ -584,19 +1630,630 @@ mod tests {
     }
 
     #[test]
-    fn it_calculates_a_linear_workflow() {
+    fn it_calculates_a_linear_workflow() {
+        let domain = Domain::new()
+            .job("", update(plus_one))
+            .job("", update(minus_one));
+
+        let planner = Planner::new(domain);
+        let workflow = find_plan(planner, 0, 2).unwrap();
+
+        // We expect a linear DAG with two tasks
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::plus_one() -> 2",
+            "mahler::planner::tests::plus_one() -> 2"
+        );
+
+        assert_eq!(workflow.to_string(), expected.to_string(),);
+    }
+
+    #[test]
+    fn it_allows_side_effect_only_actions_when_requested() {
+        let domain = Domain::new()
+            .job("", update(plus_one_and_notify))
+            .job("", none(plus_one))
+            .job("", none(notify).allow_empty_changes());
+
+        let planner = Planner::new(domain);
+        let workflow = find_plan(planner, 0, 1).unwrap();
+
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::plus_one() -> 1",
+            "mahler::planner::tests::notify()"
+        );
+
+        assert_eq!(workflow.to_string(), expected.to_string(),);
+    }
+
+    #[test]
+    fn it_rejects_side_effect_only_actions_by_default() {
+        let domain = Domain::new()
+            .job("", update(plus_one_and_notify))
+            .job("", none(plus_one))
+            .job("", none(notify));
+
+        let planner = Planner::new(domain);
+        let workflow = find_plan(planner, 0, 1);
+
+        assert!(matches!(workflow, Err(super::Error::NotFound(..))));
+    }
+
+    #[test]
+    fn it_prunes_regressive_tasks_if_configured() {
+        // Give the buggy task more priority so it is tried first
+        let domain = Domain::new()
+            .job("", update(buggy_plus_one).with_priority(1))
+            .job("", update(plus_one));
+
+        let planner = Planner::new(domain).with_config(PlannerConfig {
+            prune_regressive: true,
+            ..Default::default()
+        });
+        let workflow = find_plan(planner, 0, 2).unwrap();
+
+        // The buggy task is never chosen
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::plus_one() -> 2",
+            "mahler::planner::tests::plus_one() -> 2"
+        );
+
+        assert_eq!(workflow.to_string(), expected.to_string(),);
+    }
+
+    #[test]
+    fn it_records_regressive_pruning_for_each_job_of_a_parallel_candidate() {
+        let domain = Domain::new().job("/{counter}", update(buggy_plus_one));
+        let planner = Planner::new(domain).with_config(PlannerConfig {
+            prune_regressive: true,
+            ..Default::default()
+        });
+
+        let system = crate::system::System::try_from(serde_json::json!({"a": 0, "b": 0})).unwrap();
+        let tgt = serde_json::json!({"a": 1, "b": 1});
+        let (res, stats) = planner.find_plan_with_job_stats::<Value>(&system, &tgt);
+        assert!(matches!(res, Err(super::Error::NotFound(..))));
+
+        // The candidates for each counter and the parallel candidate
+        // combining both are pruned
+        let job = stats.get(buggy_plus_one.id()).unwrap();
+        assert_eq!(job.pruned_regressive, 4);
+    }
+
+    #[test]
+    fn it_finds_shorter_plans_with_a_star() {
+        #[derive(Serialize, Deserialize)]
+        struct MyState {
+            counters: HashMap<String, i32>,
+        }
+
+        let initial = || MyState {
+            counters: HashMap::from([("a".to_string(), 0), ("b".to_string(), 0)]),
+        };
+
+        let target = || MyState {
+            counters: HashMap::from([("a".to_string(), 3), ("b".to_string(), 3)]),
+        };
+
+        // Depth first search tries plus_one first because of the job ordering
+        let domain = || {
+            Domain::new()
+                .job("/counters/{counter}", update(plus_one))
+                .job("/counters/{counter}", update(triple_increment))
+        };
+
+        let dfs = find_plan(Planner::new(domain()), initial(), target()).unwrap();
+        let expected: Dag<&str> = par!(
+            "mahler::planner::tests::plus_one(/counters/a) -> 3",
+            "mahler::planner::tests::plus_one(/counters/b) -> 3",
+        ) + par!(
+            "mahler::planner::tests::plus_one(/counters/a) -> 3",
+            "mahler::planner::tests::plus_one(/counters/b) -> 3",
+        ) + par!(
+            "mahler::planner::tests::plus_one(/counters/a) -> 3",
+            "mahler::planner::tests::plus_one(/counters/b) -> 3",
+        );
+        assert_eq!(dfs.to_string(), expected.to_string());
+
+        // A* finds the plan with the minimal number of tasks
+        let planner = Planner::new(domain()).with_config(PlannerConfig {
+            strategy: SearchStrategy::a_star(),
+            ..Default::default()
+        });
+        let astar = find_plan(planner, initial(), target()).unwrap();
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::triple_increment(/counters/b) -> 3",
+            "mahler::planner::tests::triple_increment(/counters/a) -> 3",
+        );
+        assert_eq!(astar.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_fails_if_a_method_returns_an_unregistered_job() {
+        let domain = Domain::new().job("", update(plus_two));
+
+        let planner = Planner::new(domain);
+        let res = find_plan(planner, 0, 2);
+
+        if cfg!(debug_assertions) {
+            assert!(matches!(
+                res,
+                Err(super::Error::ChildJobNotRegistered { parent, child })
+                    if parent == "mahler::planner::tests::plus_two"
+                        && child == "mahler::planner::tests::plus_one"
+            ));
+        } else {
+            assert!(matches!(res, Err(super::Error::NotFound(..))));
+        }
+    }
+
+    #[test]
+    fn it_reports_jobs_failing_on_a_sample_state() {
+        let domain = Domain::new()
+            .job("/counters/{counter}", update(plus_one))
+            // The handler expects a counter but the route points to the map of counters
+            .job("/counters", update(plus_one).with_id("misplaced_plus_one"));
+
+        let planner = Planner::new(domain);
+        let issues = planner
+            .validate(serde_json::json!({"counters": {"one": 1, "two": 2}}))
+            .unwrap();
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].job, "misplaced_plus_one");
+        assert_eq!(issues[0].path, "/counters");
+    }
+
+    #[test]
+    fn it_reports_jobs_not_matching_the_sample_state() {
+        let domain = Domain::new()
+            .job("/counters/{counter}", update(plus_one))
+            .job(
+                "/limits/{counter}",
+                update(plus_one).with_id("limit_plus_one"),
+            );
+
+        let planner = Planner::new(domain);
+        let issues = planner
+            .validate(serde_json::json!({"counters": {"one": 1}}))
+            .unwrap();
+
+        assert_eq!(
+            issues,
+            vec![JobIssue {
+                job: "limit_plus_one".to_string(),
+                path: "/limits/{counter}".to_string(),
+                reason: "the job route does not match any path of the sample".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn it_simulates_a_workflow_to_the_target() {
+        let domain = Domain::new()
+            .job("/{counter}", update(plus_one))
+            .job("/{counter}", update(minus_one));
+        let planner = Planner::new(domain);
+
+        let initial = HashMap::from([("a".to_string(), 0), ("b".to_string(), 2)]);
+        let target = HashMap::from([("a".to_string(), 2), ("b".to_string(), 0)]);
+        let workflow = find_plan(planner, initial.clone(), target.clone()).unwrap();
+
+        let system = crate::system::System::try_from(initial).unwrap();
+        let predicted = workflow.simulate(&system).unwrap();
+        assert_eq!(predicted.state::<HashMap<String, i32>>().unwrap(), target);
+    }
+
+    fn create_value(mut value: Pointer<i32>, Target(tgt): Target<Option<i32>>) -> Pointer<i32> {
+        if value.is_none() {
+            *value = tgt;
+        }
+        value
+    }
+
+    fn update_value(mut value: Pointer<i32>, Target(tgt): Target<Option<i32>>) -> Pointer<i32> {
+        if value.is_some() && tgt.is_some() {
+            *value = tgt;
+        }
+        value
+    }
+
+    fn delete_value(mut value: Pointer<i32>, Target(tgt): Target<Option<i32>>) -> Pointer<i32> {
+        if tgt.is_none() {
+            value.take();
+        }
+        value
+    }
+
+    fn root_domain() -> Domain {
+        Domain::new()
+            .job("", create(create_value))
+            .job("", update(update_value))
+            .job("", delete(delete_value))
+    }
+
+    #[test]
+    fn it_creates_a_value_at_the_root() {
+        let workflow = find_plan(Planner::new(root_domain()), None, Some(1)).unwrap();
+
+        let expected: Dag<&str> = seq!("mahler::planner::tests::create_value() -> 1");
+        assert_eq!(workflow.to_string(), expected.to_string());
+        assert_eq!(workflow.simulate_state(None).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn it_updates_a_value_at_the_root() {
+        let workflow = find_plan(Planner::new(root_domain()), Some(0), Some(1)).unwrap();
+
+        let expected: Dag<&str> = seq!("mahler::planner::tests::update_value() -> 1");
+        assert_eq!(workflow.to_string(), expected.to_string());
+        assert_eq!(workflow.simulate_state(Some(0)).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn it_deletes_a_value_at_the_root() {
+        let workflow = find_plan(Planner::new(root_domain()), Some(0), None).unwrap();
+
+        let expected: Dag<&str> = seq!("mahler::planner::tests::delete_value()");
+        assert_eq!(workflow.to_string(), expected.to_string());
+        assert_eq!(workflow.simulate_state(Some(0)).unwrap(), None);
+    }
+
+    #[test]
+    fn it_finds_a_combined_workflow_for_multiple_targets() {
+        let domain = Domain::new().job("/{counter}", update(plus_one));
+        let planner = Planner::new(domain);
+
+        let initial = HashMap::from([("a".to_string(), 0), ("b".to_string(), 0)]);
+        let targets = [
+            serde_json::json!({"a": 2}),
+            serde_json::json!({"a": 2, "b": 1}),
+        ];
+
+        let system = crate::system::System::try_from(initial).unwrap();
+        let workflow = planner
+            .find_workflow_multi::<HashMap<String, i32>>(&system, &targets)
+            .unwrap();
+
+        // The steps to reach `a` are only included once
+        let expected: Dag<&str> = par!(
+            "mahler::planner::tests::plus_one(/a) -> 2",
+            "mahler::planner::tests::plus_one(/b) -> 1",
+        ) + seq!("mahler::planner::tests::plus_one(/a) -> 2");
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_fails_to_find_a_workflow_for_conflicting_targets() {
+        let domain = Domain::new().job("/{counter}", update(plus_one));
+        let planner = Planner::new(domain);
+
+        let initial = HashMap::from([("a".to_string(), 0)]);
+        let targets = [serde_json::json!({"a": 1}), serde_json::json!({"a": 2})];
+
+        let system = crate::system::System::try_from(initial).unwrap();
+        let res = planner.find_workflow_multi::<HashMap<String, i32>>(&system, &targets);
+        assert!(matches!(res, Err(super::Error::NotFound(..))));
+    }
+
+    fn bounded_counter(
+        mut counter: Pointer<i32>,
+        Target(tgt): Target<i32>,
+    ) -> Option<Pointer<i32>> {
+        if tgt >= 10 {
+            return None;
+        }
+        counter.assign(tgt);
+        Some(counter)
+    }
+
+    fn unbounded_counter(mut counter: Pointer<i32>, Target(tgt): Target<i32>) -> Pointer<i32> {
+        counter.assign(tgt);
+        counter
+    }
+
+    #[test]
+    fn it_does_not_combine_jobs_in_the_same_group() {
+        let initial: HashMap<String, i32> = HashMap::new();
+        let target = HashMap::from([("a".to_string(), 1), ("b".to_string(), 20)]);
+
+        // Without a group, the planner uses both variants
+        let domain = Domain::new()
+            .job("/{counter}", create(bounded_counter))
+            .job("/{counter}", create(unbounded_counter));
+        let workflow = find_plan(Planner::new(domain), initial.clone(), target.clone()).unwrap();
+        let expected: Dag<&str> = par!(
+            "mahler::planner::tests::bounded_counter(/a) -> 1",
+            "mahler::planner::tests::unbounded_counter(/b) -> 20",
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+
+        // With a group, only one of the variants is used
+        let domain = Domain::new()
+            .job("/{counter}", create(bounded_counter).with_group("create"))
+            .job("/{counter}", create(unbounded_counter).with_group("create"));
+        let workflow = find_plan(Planner::new(domain), initial, target).unwrap();
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::unbounded_counter(/b) -> 20",
+            "mahler::planner::tests::unbounded_counter(/a) -> 1",
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_skips_jobs_if_their_predicate_does_not_hold() {
+        fn scale_to(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+            *counter = tgt;
+            counter
+        }
+
+        // Only scale directly when the target grows
+        let domain = Domain::new()
+            .job(
+                "",
+                update(scale_to)
+                    .with_priority(1)
+                    .when(|cur, tgt| tgt.as_i64() > cur.as_i64()),
+            )
+            .job("", update(minus_one));
+
+        let planner = Planner::new(domain);
+        let workflow = find_plan(planner, 2, 0).unwrap();
+
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::minus_one() -> 0",
+            "mahler::planner::tests::minus_one() -> 0"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_skips_jobs_past_their_deadline() {
+        let now = std::time::Instant::now();
+        let domain = Domain::new()
+            .job("", update(plus_one).with_deadline(now).with_priority(1))
+            .job(
+                "",
+                update(plus_one)
+                    .with_id("fresh_plus_one")
+                    .with_deadline(now + std::time::Duration::from_secs(3600)),
+            );
+
+        let planner = Planner::new(domain);
+        let workflow = find_plan(planner, 0, 2).unwrap();
+
+        let expected: Dag<&str> = seq!("fresh_plus_one() -> 2", "fresh_plus_one() -> 2");
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_allows_methods_to_compute_child_args_from_the_state() {
+        #[derive(Serialize, Deserialize)]
+        struct MyState {
+            counters: BTreeMap<String, i32>,
+        }
+
+        // Increase the lowest counter first. The child argument is
+        // computed from the state, and may contain any characters
+        fn plus_one_lowest(
+            counters: View<BTreeMap<String, i32>>,
+            Target(tgt): Target<BTreeMap<String, i32>>,
+        ) -> Vec<Task> {
+            let lowest = counters
+                .iter()
+                .filter(|(name, value)| tgt.get(*name).is_some_and(|t| t > value))
+                .min_by_key(|(_, value)| **value);
+
+            if let Some((name, _)) = lowest {
+                let tgt = tgt[name];
+                return vec![plus_one.with_arg("counter", name.as_str()).with_target(tgt)];
+            }
+            vec![]
+        }
+
+        let initial = MyState {
+            counters: BTreeMap::from([("{a}".to_string(), 1), ("b".to_string(), 0)]),
+        };
+
+        let target = MyState {
+            counters: BTreeMap::from([("{a}".to_string(), 2), ("b".to_string(), 2)]),
+        };
+
+        let domain = Domain::new()
+            .job("/counters", update(plus_one_lowest))
+            .job("/counters/{counter}", none(plus_one));
+
+        let planner = Planner::new(domain);
+        let workflow = find_plan(planner, initial, target).unwrap();
+
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::plus_one(/counters/b) -> 2",
+            "mahler::planner::tests::plus_one(/counters/b) -> 2",
+            "mahler::planner::tests::plus_one(/counters/{a}) -> 2",
+        );
+
+        assert_eq!(workflow.to_string(), expected.to_string(),);
+    }
+
+    #[test]
+    fn it_uses_the_original_path_with_normalized_routes() {
+        let domain = Domain::new()
+            .with_normalizer(str::to_lowercase)
+            .job("/Counters/{counter}", update(plus_one))
+            .job("/Counters/{counter}", update(plus_two));
+
+        let initial = serde_json::json!({ "Counters": { "One": 0 } });
+        let target = serde_json::json!({ "Counters": { "One": 2 } });
+
+        // Method sub-tasks are assigned the path with the original case
+        let workflow = find_plan(Planner::new(domain), initial, target).unwrap();
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::plus_one(/Counters/One) -> 2",
+            "mahler::planner::tests::plus_one(/Counters/One) -> 2"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_finds_jobs_with_custom_ids_for_method_sub_tasks() {
+        fn plus_two_b(counter: View<i32>, Target(tgt): Target<i32>) -> Vec<Task> {
+            if tgt - *counter > 1 {
+                return vec![
+                    plus_one.with_target(tgt).with_id("plus_one_b"),
+                    plus_one.with_target(tgt).with_id("plus_one_b"),
+                ];
+            }
+            vec![]
+        }
+
+        let domain = Domain::new()
+            .job("/a/{counter}", update(plus_one).with_id("plus_one_a"))
+            .job("/b/{counter}", none(plus_one).with_id("plus_one_b"))
+            .job("/b/{counter}", update(plus_two_b));
+
+        let initial = serde_json::json!({ "a": { "one": 0 }, "b": { "one": 0 } });
+        let target = serde_json::json!({ "a": { "one": 0 }, "b": { "one": 2 } });
+
+        let workflow = find_plan(Planner::new(domain), initial, target).unwrap();
+        let expected: Dag<&str> = seq!("plus_one_b(/b/one) -> 2", "plus_one_b(/b/one) -> 2");
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_allows_methods_to_override_parent_args() {
+        #[derive(Serialize, Deserialize)]
+        struct MyState {
+            counters: HashMap<String, i32>,
+        }
+
+        // Move a unit from the counter to the sibling counter `b`
+        fn transfer(counter: View<i32>, Target(tgt): Target<i32>) -> Vec<Task> {
+            if *counter > tgt {
+                return vec![
+                    minus_one.with_target(tgt),
+                    plus_one.with_arg_override("counter", "b").with_target(1),
+                ];
+            }
+            vec![]
+        }
+
+        let initial = MyState {
+            counters: HashMap::from([("a".to_string(), 1), ("b".to_string(), 0)]),
+        };
+
+        let target = MyState {
+            counters: HashMap::from([("a".to_string(), 0), ("b".to_string(), 1)]),
+        };
+
+        let domain = Domain::new()
+            .job("/counters/{counter}", update(transfer))
+            .job("/counters/{counter}", none(minus_one))
+            .job("/counters/{counter}", none(plus_one));
+
+        let planner = Planner::new(domain);
+        let workflow = find_plan(planner, initial, target).unwrap();
+
+        let expected: Dag<&str> = par!(
+            "mahler::planner::tests::minus_one(/counters/a) -> 0",
+            "mahler::planner::tests::plus_one(/counters/b) -> 1",
+        );
+
+        assert_eq!(workflow.to_string(), expected.to_string(),);
+    }
+
+    #[test]
+    fn it_parallelizes_tasks_with_disjoint_declared_access() {
+        #[derive(Serialize, Deserialize, Clone)]
+        struct MyState {
+            config: i32,
+            items: BTreeMap<String, i32>,
+        }
+
+        // Set the item to the target scaled by the global config
+        fn scale_item(
+            mut item: View<i32>,
+            Target(tgt): Target<i32>,
+            System(state): System<MyState>,
+        ) -> View<i32> {
+            *item = tgt * state.config;
+            item
+        }
+
+        let initial = MyState {
+            config: 1,
+            items: BTreeMap::from([("a".to_string(), 0), ("b".to_string(), 0)]),
+        };
+
+        let target = MyState {
+            config: 1,
+            items: BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 1)]),
+        };
+
+        // Without declaring access, the handler may read any part of the state
+        let domain = Domain::new().job("/items/{id}", update(scale_item));
+        let workflow = find_plan(Planner::new(domain), initial.clone(), target.clone()).unwrap();
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::it_parallelizes_tasks_with_disjoint_declared_access::scale_item(/items/b) -> 1",
+            "mahler::planner::tests::it_parallelizes_tasks_with_disjoint_declared_access::scale_item(/items/a) -> 1"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+
+        // The tasks only read the config and write to their own item
+        let domain = Domain::new().job(
+            "/items/{id}",
+            update(scale_item)
+                .reads(&["/config"])
+                .writes(&["/items/{id}"]),
+        );
+        let workflow = find_plan(Planner::new(domain), initial, target).unwrap();
+        let expected: Dag<&str> = par!(
+            "mahler::planner::tests::it_parallelizes_tasks_with_disjoint_declared_access::scale_item(/items/a) -> 1",
+            "mahler::planner::tests::it_parallelizes_tasks_with_disjoint_declared_access::scale_item(/items/b) -> 1"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_sequences_parallel_candidates_acting_on_overlapping_paths() {
+        #[derive(Serialize, Deserialize)]
+        struct MyState {
+            counters: HashMap<String, i32>,
+        }
+
+        // Move a unit from the counter to the sibling counter `b`
+        fn transfer(counter: View<i32>, Target(tgt): Target<i32>) -> Vec<Task> {
+            if *counter > tgt {
+                return vec![
+                    minus_one.with_target(tgt),
+                    plus_one.with_arg_override("counter", "b").with_target(2),
+                ];
+            }
+            vec![]
+        }
+
+        let initial = MyState {
+            counters: HashMap::from([("a".to_string(), 1), ("b".to_string(), 0)]),
+        };
+
+        let target = MyState {
+            counters: HashMap::from([("a".to_string(), 0), ("b".to_string(), 2)]),
+        };
+
+        // Both `transfer(/counters/a)` and `plus_one(/counters/b)` are scoped candidates
+        // for non-overlapping paths, but both end up modifying `/counters/b`
         let domain = Domain::new()
-            .job("", update(plus_one))
-            .job("", update(minus_one));
+            .job("/counters/{counter}", update(transfer))
+            .job("/counters/{counter}", none(minus_one))
+            .job("/counters/{counter}", update(plus_one));
 
         let planner = Planner::new(domain);
-        let workflow = find_plan(planner, 0, 2).unwrap();
+        let workflow = find_plan(planner, initial, target).unwrap();
 
-        // We expect a linear DAG with two tasks
-        let expected: Dag<&str> = seq!(
-            "mahler::planner::tests::plus_one()",
-            "mahler::planner::tests::plus_one()"
-        );
+        // The candidates are not combined into a parallel branch
+        let expected: Dag<&str> = seq!("mahler::planner::tests::plus_one(/counters/b) -> 2")
+            + par!(
+                "mahler::planner::tests::minus_one(/counters/a) -> 0",
+                "mahler::planner::tests::plus_one(/counters/b) -> 2",
+            );
 
         assert_eq!(workflow.to_string(), expected.to_string(),);
     }
@@ -608,7 +2265,106 @@ mod tests {
         let planner = Planner::new(domain);
         let workflow = find_plan(planner, 0, 2);
 
-        assert!(matches!(workflow, Err(super::Error::NotFound)));
+        assert!(matches!(workflow, Err(super::Error::NotFound(..))));
+    }
+
+    #[test]
+    fn it_uses_none_jobs_only_as_method_children() {
+        // `plus_one` can only be used through `plus_two`
+        let domain = || {
+            Domain::new()
+                .job("", none(plus_one))
+                .job("", update(plus_two))
+        };
+
+        let workflow = find_plan(Planner::new(domain()), 0, 4).unwrap();
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::plus_one() -> 4",
+            "mahler::planner::tests::plus_one() -> 4",
+            "mahler::planner::tests::plus_one() -> 4",
+            "mahler::planner::tests::plus_one() -> 4",
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+
+        // Reaching the target requires a direct call to `plus_one`
+        // after applying `plus_two`, but it can never be selected
+        let workflow = find_plan(Planner::new(domain()), 0, 3);
+        assert!(matches!(workflow, Err(super::Error::NotFound(..))));
+    }
+
+    #[test]
+    fn it_finds_jobs_at_an_ancestor_of_the_changed_path() {
+        fn update_counters(
+            mut counters: View<HashMap<String, i32>>,
+            Target(tgt): Target<HashMap<String, i32>>,
+        ) -> View<HashMap<String, i32>> {
+            for (name, value) in tgt {
+                counters.insert(name, value);
+            }
+            counters
+        }
+
+        // Only the whole map can be updated, there is no job for `/counters/one`
+        let domain = Domain::new().job("/counters", update(update_counters));
+
+        let workflow = find_plan(
+            Planner::new(domain),
+            serde_json::json!({"counters": {"one": 0, "two": 0}}),
+            serde_json::json!({"counters": {"one": 1, "two": 0}}),
+        )
+        .unwrap();
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::it_finds_jobs_at_an_ancestor_of_the_changed_path::update_counters(/counters) -> {\"one\":1,\"two\":0}"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_allows_builtin_tasks_in_method_expansions() {
+        fn plus_one_and_noop(counter: View<i32>, Target(tgt): Target<i32>) -> Vec<Task> {
+            if *counter < tgt {
+                return vec![plus_one.with_target(tgt), task::noop()];
+            }
+            vec![]
+        }
+
+        // The noop task does not need to be registered in the domain
+        let domain = Domain::new()
+            .job("", none(plus_one))
+            .job("", update(plus_one_and_noop));
+
+        let workflow = find_plan(Planner::new(domain), 0, 2).unwrap();
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::plus_one() -> 2",
+            "mahler::task::noop()",
+            "mahler::planner::tests::plus_one() -> 2",
+            "mahler::task::noop()",
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+
+        fn plus_one_unreachable(counter: View<i32>, Target(tgt): Target<i32>) -> Vec<Task> {
+            if *counter < tgt {
+                return vec![plus_one.with_target(tgt), task::unreachable()];
+            }
+            vec![]
+        }
+
+        // A method using the unreachable task is never selected
+        let domain = Domain::new()
+            .job("", update(plus_one))
+            .job("", update(plus_one_unreachable).with_priority(1));
+
+        let workflow = find_plan(Planner::new(domain), 0, 2).unwrap();
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::plus_one() -> 2",
+            "mahler::planner::tests::plus_one() -> 2",
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+
+        // Registered as a job, a noop task is never selected as it produces no changes
+        let domain = Domain::new().job("", Job::from(task::noop()));
+        let workflow = find_plan(Planner::new(domain), 0, 1);
+        assert!(matches!(workflow, Err(super::Error::NotFound(..))));
     }
 
     #[test]
@@ -622,6 +2378,422 @@ mod tests {
         assert!(workflow.is_err());
     }
 
+    #[test]
+    fn it_reports_search_stats_if_no_workflow_is_found() {
+        let domain = Domain::new().job("", update(buggy_plus_one));
+
+        let planner = Planner::new(domain);
+        let Err(super::Error::NotFound(stats, _)) = find_plan(planner, 0, 2) else {
+            panic!("expected the search to fail");
+        };
+
+        assert!(stats.nodes_expanded > 0);
+        assert_eq!(stats.max_depth, 255);
+        assert_eq!(stats.pruned_max_depth, 1);
+    }
+
+    #[test]
+    fn it_aborts_the_search_when_a_branch_reaches_the_max_depth() {
+        // The buggy job is tried first because of the priority, and keeps
+        // moving away from the target until the branch reaches the maximum depth
+        let domain = Domain::new()
+            .job("", update(plus_one))
+            .job("", update(buggy_plus_one).with_priority(1));
+
+        let planner = Planner::new(domain).with_config(PlannerConfig {
+            max_depth: Some(3),
+            ..PlannerConfig::default()
+        });
+        let Err(super::Error::NotFound(stats, _)) = find_plan(planner, 0, 2) else {
+            panic!("expected the search to fail");
+        };
+
+        // The search stops at the first branch reaching the limit, without
+        // trying the remaining candidates
+        assert_eq!(stats.nodes_expanded, 3);
+        assert_eq!(stats.max_depth, 2);
+        assert_eq!(stats.pruned_max_depth, 1);
+    }
+
+    #[test]
+    fn it_stops_the_search_if_a_task_declares_the_target_unreachable() {
+        fn max_ten(counter: View<i32>, Target(tgt): Target<i32>) -> Result<View<i32>, task::Error> {
+            if tgt > 10 {
+                return Err(task::Error::Unreachable(
+                    "counter cannot go above 10".to_string(),
+                ));
+            }
+            Ok(counter)
+        }
+
+        let domain = Domain::new()
+            .job("", update(plus_one))
+            .job("", update(max_ten));
+
+        let planner = Planner::new(domain);
+        let Err(super::Error::NotFound(stats, reason)) = find_plan(planner, 0, 11) else {
+            panic!("expected the search to fail");
+        };
+
+        assert_eq!(stats.nodes_expanded, 1);
+        assert_eq!(reason.as_deref(), Some("counter cannot go above 10"));
+    }
+
+    #[test]
+    fn it_compares_numbers_using_the_configured_epsilon() {
+        fn add_tenth(mut value: View<f64>, Target(tgt): Target<f64>) -> View<f64> {
+            if *value < tgt {
+                *value += 0.1;
+            }
+            value
+        }
+
+        // 0.1 + 0.1 + 0.1 is not exactly equal to 0.3
+        let planner = Planner::new(Domain::new().job("", update(add_tenth)));
+        assert!(find_plan(planner, 0.0, 0.3).is_err());
+
+        let planner =
+            Planner::new(Domain::new().job("", update(add_tenth))).with_config(PlannerConfig {
+                number_policy: NumberPolicy::Epsilon(1e-9),
+                ..Default::default()
+            });
+        let workflow = find_plan(planner, 0.0, 0.3).unwrap();
+
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::it_compares_numbers_using_the_configured_epsilon::add_tenth() -> 0.3",
+            "mahler::planner::tests::it_compares_numbers_using_the_configured_epsilon::add_tenth() -> 0.3",
+            "mahler::planner::tests::it_compares_numbers_using_the_configured_epsilon::add_tenth() -> 0.3"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_compares_values_using_path_comparators() {
+        #[derive(Clone, Serialize, Deserialize)]
+        struct Service {
+            tags: Vec<String>,
+        }
+
+        fn set_tags(
+            mut tags: View<Vec<String>>,
+            Target(tgt): Target<Vec<String>>,
+        ) -> View<Vec<String>> {
+            *tags = tgt;
+            tags
+        }
+
+        let domain = Domain::new().job("/tags", update(set_tags));
+        let cur = Service {
+            tags: vec!["a".to_string(), "b".to_string()],
+        };
+        let tgt = Service {
+            tags: vec!["b".to_string(), "a".to_string()],
+        };
+
+        // Reordering the tags is a change by default
+        let workflow = find_plan(Planner::new(domain.clone()), cur.clone(), tgt.clone()).unwrap();
+        assert!(!workflow.is_empty());
+
+        // The tags are compared as a set
+        let planner = Planner::new(domain).with_path_comparator("/tags", |a, b| {
+            let (Some(a), Some(b)) = (a.as_array(), b.as_array()) else {
+                return a == b;
+            };
+            a.len() == b.len() && a.iter().all(|v| b.contains(v))
+        });
+        let workflow = find_plan(planner, cur, tgt).unwrap();
+        assert!(workflow.is_empty());
+    }
+
+    #[test]
+    fn it_prunes_regressive_candidates_using_path_comparators() {
+        #[derive(Clone, Serialize, Deserialize)]
+        struct Service {
+            tags: Vec<String>,
+        }
+
+        // Set the tags in sorted order
+        fn sort_tags(
+            mut tags: View<Vec<String>>,
+            Target(mut tgt): Target<Vec<String>>,
+        ) -> View<Vec<String>> {
+            tgt.sort();
+            *tags = tgt;
+            tags
+        }
+
+        let planner = || {
+            Planner::new(Domain::new().job("/tags", update(sort_tags))).with_config(PlannerConfig {
+                prune_regressive: true,
+                ..Default::default()
+            })
+        };
+        let cur = Service {
+            tags: vec!["a".to_string(), "c".to_string()],
+        };
+        let tgt = Service {
+            tags: vec!["b".to_string(), "a".to_string()],
+        };
+
+        // Sorting the tags does not reduce the distance if tags are compared in order
+        assert!(matches!(
+            find_plan(planner(), cur.clone(), tgt.clone()),
+            Err(super::Error::NotFound(..))
+        ));
+
+        // The tags are compared as a set, so sorting reaches the target
+        let planner = planner().with_path_comparator("/tags", |a, b| {
+            let (Some(a), Some(b)) = (a.as_array(), b.as_array()) else {
+                return a == b;
+            };
+            a.len() == b.len() && a.iter().all(|v| b.contains(v))
+        });
+        let workflow = find_plan(planner, cur, tgt).unwrap();
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::it_prunes_regressive_candidates_using_path_comparators::sort_tags(/tags) -> [\"b\",\"a\"]"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_reaches_large_integer_targets_exactly() {
+        fn plus_one_u64(mut counter: View<u64>, Target(tgt): Target<u64>) -> View<u64> {
+            if *counter < tgt {
+                *counter += 1;
+            }
+            counter
+        }
+
+        let planner =
+            Planner::new(Domain::new().job("", update(plus_one_u64))).with_config(PlannerConfig {
+                prune_regressive: true,
+                number_policy: NumberPolicy::Epsilon(1e-9),
+                ..Default::default()
+            });
+
+        // 2^53 + 1 cannot be represented as f64
+        let workflow = find_plan(planner, 9007199254740991u64, 9007199254740993u64).unwrap();
+
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::it_reaches_large_integer_targets_exactly::plus_one_u64() -> 9007199254740993",
+            "mahler::planner::tests::it_reaches_large_integer_targets_exactly::plus_one_u64() -> 9007199254740993"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_does_not_select_jobs_if_their_precondition_fails() {
+        let domain = || {
+            Domain::new().job(
+                "",
+                update(plus_one).require(|system, _| system.root().as_i64() < Some(2)),
+            )
+        };
+
+        let workflow = find_plan(Planner::new(domain()), 0, 2).unwrap();
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::plus_one() -> 2",
+            "mahler::planner::tests::plus_one() -> 2"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+
+        // The job is no longer applicable once the counter reaches 2
+        assert!(matches!(
+            find_plan(Planner::new(domain()), 0, 3),
+            Err(super::Error::NotFound(..))
+        ));
+    }
+
+    #[test]
+    fn it_returns_the_predicted_states_of_the_workflow() {
+        let domain = Domain::new().job("/{counter}", update(plus_one));
+        let planner = Planner::new(domain);
+
+        let system =
+            crate::system::System::try_from(serde_json::json!({"one": 0, "two": 1})).unwrap();
+        let (workflow, states) = planner
+            .find_workflow_with_states::<Value>(&system, &serde_json::json!({"one": 2, "two": 1}))
+            .unwrap();
+
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::plus_one(/one) -> 2",
+            "mahler::planner::tests::plus_one(/one) -> 2"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+        assert_eq!(
+            states,
+            vec![
+                serde_json::json!({"one": 1, "two": 1}),
+                serde_json::json!({"one": 2, "two": 1})
+            ]
+        );
+    }
+
+    #[test]
+    fn it_only_selects_fallback_jobs_if_no_other_job_is_applicable() {
+        let domain = || {
+            Domain::new()
+                .job(
+                    "",
+                    update(plus_one)
+                        .require(|system, _| system.root().as_i64() < Some(2))
+                        .with_description(|| "+1"),
+                )
+                .job(
+                    "",
+                    update(plus_one)
+                        .with_id("plus_one_fallback")
+                        // the priority is ignored for fallback jobs
+                        .with_priority(10)
+                        .with_description(|| "fallback +1")
+                        .fallback(),
+                )
+        };
+
+        let workflow = find_plan(Planner::new(domain()), 0, 2).unwrap();
+        let expected: Dag<&str> = seq!("+1", "+1");
+        assert_eq!(workflow.to_string(), expected.to_string());
+
+        // The fallback is used once the primary job fails its condition
+        let workflow = find_plan(Planner::new(domain()), 0, 3).unwrap();
+        let expected: Dag<&str> = seq!("+1", "+1", "fallback +1");
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_skips_states_already_visited_by_other_branches() {
+        // Counters can only go up to one, so the search needs to explore every
+        // ordering of the counter updates before failing
+        fn up_to_one(mut counter: View<i32>) -> View<i32> {
+            if *counter < 1 {
+                *counter += 1;
+            }
+            counter
+        }
+
+        let find = |skip_visited| {
+            let domain = Domain::new().job("/{counter}", update(up_to_one));
+            let planner = Planner::new(domain).with_config(PlannerConfig {
+                skip_visited,
+                ..Default::default()
+            });
+            let Err(super::Error::NotFound(stats, _)) = find_plan(
+                planner,
+                serde_json::json!({"a": 0, "b": 0, "c": 0}),
+                serde_json::json!({"a": 1, "b": 1, "c": 2}),
+            ) else {
+                panic!("expected the search to fail");
+            };
+            stats
+        };
+
+        let stats = find(false);
+        let dedup_stats = find(true);
+
+        assert_eq!(stats.pruned_visited, 0);
+        assert!(dedup_stats.pruned_visited > 0);
+        assert!(dedup_stats.nodes_expanded < stats.nodes_expanded);
+    }
+
+    #[test]
+    fn it_does_not_skip_different_states_with_the_same_fingerprint() {
+        let mut visited = Visited::default();
+
+        assert!(visited.insert_with_fingerprint(0, &serde_json::json!({"a": 1})));
+        assert!(visited.insert_with_fingerprint(0, &serde_json::json!({"a": 2})));
+        assert!(!visited.insert_with_fingerprint(0, &serde_json::json!({"a": 1})));
+    }
+
+    #[test]
+    fn it_allows_tasks_to_read_the_state_before_planning() {
+        // Increase the counter at most by two within the same plan
+        fn plus_one_limited(
+            mut counter: View<i32>,
+            Target(tgt): Target<i32>,
+            Previous(initial): Previous<i32>,
+        ) -> View<i32> {
+            if *counter < tgt && *counter - initial < 2 {
+                *counter += 1;
+            }
+            counter
+        }
+
+        let domain = || Domain::new().job("", update(plus_one_limited));
+
+        let workflow = find_plan(Planner::new(domain()), 1, 3).unwrap();
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::it_allows_tasks_to_read_the_state_before_planning::plus_one_limited() -> 3",
+            "mahler::planner::tests::it_allows_tasks_to_read_the_state_before_planning::plus_one_limited() -> 3"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+
+        // The second step reads the original value and stops
+        assert!(find_plan(Planner::new(domain()), 1, 4).is_err());
+    }
+
+    #[test]
+    fn it_fails_if_every_branch_visits_a_path_too_many_times() {
+        let domain = Domain::new().job("", update(buggy_plus_one));
+
+        let planner = Planner::new(domain).with_config(PlannerConfig {
+            path_visit_limit: Some(10),
+            ..PlannerConfig::default()
+        });
+        let Err(super::Error::NotFound(stats, None)) = find_plan(planner, 0, 2) else {
+            panic!("expected the search to fail");
+        };
+
+        assert_eq!(stats.pruned_visit_limit, 1);
+    }
+
+    #[test]
+    fn it_discards_branches_visiting_a_path_too_many_times() {
+        // The buggy job is tried first because of the priority, and moves the counter
+        // away from the target. The branches using it should be discarded without
+        // aborting the search
+        let domain = Domain::new()
+            .job("/{counter}", update(plus_one))
+            .job("/{counter}", update(buggy_plus_one).with_priority(1));
+
+        let planner = Planner::new(domain).with_config(PlannerConfig {
+            path_visit_limit: Some(3),
+            ..PlannerConfig::default()
+        });
+        let workflow = find_plan(
+            planner,
+            Counters(HashMap::from([("a".to_string(), 0)])),
+            Counters(HashMap::from([("a".to_string(), 2)])),
+        )
+        .unwrap();
+
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::plus_one(/a) -> 2",
+            "mahler::planner::tests::plus_one(/a) -> 2"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn it_iterates_over_alternative_workflows() {
+        let domain = Domain::new()
+            .job("", update(plus_one))
+            .job("", update(triple_increment));
+
+        let planner = Planner::new(domain);
+        let system = crate::system::System::try_from(0).unwrap();
+        let workflows: Vec<Workflow> = planner
+            .plan_iter::<i32>(&system, &serde_json::json!(3))
+            .take(2)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(workflows.len(), 2);
+        assert_ne!(workflows[0].to_string(), workflows[1].to_string());
+        for workflow in workflows {
+            assert_eq!(workflow.simulate_state(0).unwrap(), 3);
+        }
+    }
+
     #[test]
     fn it_calculates_a_linear_workflow_with_compound_tasks() {
         let domain = Domain::new()
@@ -633,13 +2805,36 @@ mod tests {
 
         // We expect a linear DAG with two tasks
         let expected: Dag<&str> = seq!(
-            "mahler::planner::tests::plus_one()",
-            "mahler::planner::tests::plus_one()"
+            "mahler::planner::tests::plus_one() -> 2",
+            "mahler::planner::tests::plus_one() -> 2"
         );
 
         assert_eq!(workflow.to_string(), expected.to_string(),);
     }
 
+    #[test]
+    fn it_assigns_distinct_ids_to_identical_tasks_from_the_same_expansion() {
+        let domain = Domain::new()
+            .job("", update(plus_two))
+            .job("", none(plus_one));
+
+        let planner = Planner::new(domain);
+        let workflow = find_plan(planner, 0, 2).unwrap();
+
+        // Both tasks come from a single expansion of `plus_two`. The ids
+        // must not collide, otherwise the second task would be discarded
+        // as a loop
+        let ids: Vec<u64> = workflow.as_dag().values().map(|unit| unit.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+
+        let expected: Dag<&str> = seq!(
+            "mahler::planner::tests::plus_one() -> 2",
+            "mahler::planner::tests::plus_one() -> 2"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
     #[test]
     fn it_calculates_a_linear_workflow_on_a_complex_state() {
         #[derive(Serialize, Deserialize)]
@@ -664,16 +2859,104 @@ mod tests {
 
         // We expect counters to be updated in parallel
         let expected: Dag<&str> = par!(
-            "mahler::planner::tests::plus_one(/counters/one)",
-            "mahler::planner::tests::plus_one(/counters/two)",
+            "mahler::planner::tests::plus_one(/counters/one) -> 2",
+            "mahler::planner::tests::plus_one(/counters/two) -> 2",
         ) + par!(
-            "mahler::planner::tests::plus_one(/counters/one)",
-            "mahler::planner::tests::plus_one(/counters/two)",
+            "mahler::planner::tests::plus_one(/counters/one) -> 2",
+            "mahler::planner::tests::plus_one(/counters/two) -> 2",
         );
 
         assert_eq!(workflow.to_string(), expected.to_string(),);
     }
 
+    #[test]
+    fn it_calculates_a_workflow_for_a_delete_missing_from_the_target() {
+        #[derive(Serialize, Deserialize)]
+        struct MyState {
+            counters: HashMap<String, i32>,
+        }
+
+        let initial = MyState {
+            counters: HashMap::from([("one".to_string(), 0), ("two".to_string(), 0)]),
+        };
+
+        let target = MyState {
+            counters: HashMap::from([("one".to_string(), 0)]),
+        };
+
+        let domain = Domain::new().job("/counters/{counter}", delete(remove_counter));
+
+        let planner = Planner::new(domain);
+        let workflow = find_plan(planner, initial, target).unwrap();
+
+        let expected: Dag<&str> = seq!("mahler::planner::tests::remove_counter(/counters/two)");
+
+        assert_eq!(workflow.to_string(), expected.to_string(),);
+    }
+
+    #[test]
+    fn it_removes_a_subtree_with_a_single_delete_at_the_parent() {
+        fn remove_config(config: Pointer<Value>) -> Pointer<Value> {
+            config.unassign()
+        }
+
+        fn remove_port(port: Pointer<i32>) -> Pointer<i32> {
+            port.unassign()
+        }
+
+        let initial = serde_json::json!({
+            "configs": {
+                "web": {"port": 80, "env": {"debug": "1", "level": "info"}, "hosts": ["a", "b"]},
+                "db": {"port": 5432}
+            }
+        });
+        let target = serde_json::json!({
+            "configs": {
+                "db": {"port": 5432}
+            }
+        });
+
+        let domain = Domain::new()
+            .job("/configs/{name}/port", delete(remove_port))
+            .job("/configs/{name}", delete(remove_config));
+
+        let planner = Planner::new(domain);
+        let workflow = find_plan(planner, initial, target).unwrap();
+
+        let expected: Dag<&str> = seq!("mahler::planner::tests::it_removes_a_subtree_with_a_single_delete_at_the_parent::remove_config(/configs/web)");
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    fn set_counter(mut counter: Pointer<i32>, Target(tgt): Target<Option<i32>>) -> Pointer<i32> {
+        *counter = tgt;
+        counter
+    }
+
+    #[test]
+    fn it_uses_any_jobs_for_create_update_and_delete() {
+        let initial = HashMap::from([("a".to_string(), 0)]);
+
+        for (target, task) in [
+            // add
+            (
+                HashMap::from([("a".to_string(), 0), ("b".to_string(), 1)]),
+                "(/b) -> 1",
+            ),
+            // replace
+            (HashMap::from([("a".to_string(), 1)]), "(/a) -> 1"),
+            // remove
+            (HashMap::new(), "(/a)"),
+        ] {
+            let domain = Domain::new().job("/{counter}", any(set_counter));
+            let planner = Planner::new(domain);
+
+            let workflow = find_plan(planner, initial.clone(), target).unwrap();
+            let expected: Dag<String> = seq!(format!("mahler::planner::tests::set_counter{task}"));
+
+            assert_eq!(workflow.to_string(), expected.to_string());
+        }
+    }
+
     #[test]
     fn it_calculates_a_linear_workflow_on_a_complex_state_with_compound_tasks() {
         #[derive(Serialize, Deserialize)]
@@ -699,12 +2982,12 @@ mod tests {
         // We expect a parallel dag with two tasks on each branch
         let expected: Dag<&str> = dag!(
             seq!(
-                "mahler::planner::tests::plus_one(/counters/one)",
-                "mahler::planner::tests::plus_one(/counters/one)",
+                "mahler::planner::tests::plus_one(/counters/one) -> 2",
+                "mahler::planner::tests::plus_one(/counters/one) -> 2",
             ),
             seq!(
-                "mahler::planner::tests::plus_one(/counters/two)",
-                "mahler::planner::tests::plus_one(/counters/two)",
+                "mahler::planner::tests::plus_one(/counters/two) -> 2",
+                "mahler::planner::tests::plus_one(/counters/two) -> 2",
             )
         );
 
@@ -736,9 +3019,9 @@ mod tests {
 
         // We expect a linear DAG with two tasks
         let expected: Dag<&str> = seq!(
-            "mahler::planner::tests::plus_one(/counters/one)",
-            "mahler::planner::tests::plus_one(/counters/one)",
-            "mahler::planner::tests::plus_one(/counters/one)",
+            "mahler::planner::tests::plus_one(/counters/one) -> 3",
+            "mahler::planner::tests::plus_one(/counters/one) -> 3",
+            "mahler::planner::tests::plus_one(/counters/one) -> 3",
         );
 
         assert_eq!(workflow.to_string(), expected.to_string(),);
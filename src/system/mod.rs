@@ -1,15 +1,16 @@
-use json_patch::{patch, Patch};
+use json_patch::{merge, patch, Patch};
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
 use std::{
     any::{Any, TypeId},
-    collections::HashMap,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
     ops::Deref,
     sync::Arc,
 };
 
 #[derive(Clone)]
-pub struct Resources(HashMap<TypeId, Arc<dyn Any + Send + Sync>>);
+pub(crate) struct Resources(HashMap<TypeId, Arc<dyn Any + Send + Sync>>);
 
 impl Default for Resources {
     fn default() -> Self {
@@ -46,10 +47,16 @@ impl Resources {
     }
 }
 
+/// The system state managed by the [Worker](`crate::worker::Worker`)
+///
+/// The system is passed to [extractors](`crate::task::FromSystem`) to initialize them, and it
+/// provides read-only access to the serialized state.
 #[derive(Clone)]
 pub struct System {
     state: Value,
     resources: Resources,
+    // Snapshot of the state before planning
+    baseline: Option<Arc<Value>>,
 }
 
 impl std::fmt::Debug for System {
@@ -62,23 +69,124 @@ impl std::fmt::Debug for System {
 }
 
 impl System {
+    /// Create a new system from a serializable state
     pub fn try_from<S: Serialize>(state: S) -> Result<Self, serde_json::Error> {
         let state = serde_json::to_value(state)?;
         Ok(Self {
             state,
             resources: Resources::new(),
+            baseline: None,
         })
     }
 
+    /// Get the serialized value of the system state
     pub fn root(&self) -> &Value {
         &self.state
     }
 
+    /// Get the serialized value of the system state
+    ///
+    /// This is the same as [`System::root`]
+    pub fn to_value(&self) -> &Value {
+        self.root()
+    }
+
+    /// Format the system state as indented JSON
+    ///
+    /// ```rust
+    /// use mahler::System;
+    /// use serde_json::json;
+    ///
+    /// let system = System::try_from(json!({"counters": {"one": 0}})).unwrap();
+    /// println!("{}", system.to_pretty());
+    /// ```
+    pub fn to_pretty(&self) -> String {
+        // Serializing a Value cannot fail
+        serde_json::to_string_pretty(&self.state).unwrap_or_default()
+    }
+
+    /// Get a mutable reference to the serialized value of the system state
+    ///
+    /// Changes made through the reference are not validated against the state model
+    pub(crate) fn root_mut(&mut self) -> &mut Value {
+        &mut self.state
+    }
+
+    /// Store a snapshot of the current state as the baseline
+    ///
+    /// The baseline is kept as changes are applied to the system, allowing
+    /// tasks to read the state before planning via the [Previous](`crate::extract::Previous`)
+    /// extractor
+    pub(crate) fn set_baseline(&mut self) {
+        self.baseline = Some(Arc::new(self.state.clone()));
+    }
+
+    /// Get the state snapshot stored as the baseline
+    ///
+    /// Returns the current state if no baseline has been stored
+    pub(crate) fn baseline(&self) -> &Value {
+        self.baseline.as_deref().unwrap_or(&self.state)
+    }
+
+    /// Get a hash of the system state
+    ///
+    /// Systems with equal state have the same fingerprint, independently of their resources
+    pub(crate) fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.state.hash(&mut hasher);
+        hasher.finish()
+    }
+
     pub(crate) fn patch(&mut self, changes: Patch) -> Result<(), json_patch::PatchError> {
-        patch(&mut self.state, &changes)?;
+        patch(self.root_mut(), &changes)?;
         Ok(())
     }
 
+    /// Apply a [JSON Merge Patch](https://datatracker.ietf.org/doc/html/rfc7386) to the system state
+    ///
+    /// Object members in the patch replace the members of the same name on the state, and
+    /// members with a `null` value are removed. Any other value replaces the state value at the
+    /// same location.
+    ///
+    /// ```rust
+    /// use mahler::System;
+    /// use serde_json::json;
+    ///
+    /// let mut system = System::try_from(json!({"counters": {"one": 0, "two": 0}})).unwrap();
+    /// system.patch_merge(&json!({"counters": {"one": 1, "two": null}}));
+    ///
+    /// assert_eq!(system.root(), &json!({"counters": {"one": 1}}));
+    /// ```
+    pub fn patch_merge(&mut self, changes: &Value) {
+        merge(self.root_mut(), changes);
+    }
+
+    /// Transform the raw state value using the given function
+    ///
+    /// This allows to migrate a state serialized with a previous version of
+    /// the state model before deserializing it. The state is not modified if
+    /// the migration fails
+    ///
+    /// ```rust
+    /// use mahler::System;
+    /// use serde_json::{json, Value};
+    ///
+    /// let mut system = System::try_from(json!({"count": 1})).unwrap();
+    /// system
+    ///     .migrate(|state| Ok::<Value, ()>(json!({"counters": {"one": state["count"]}})))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(system.root(), &json!({"counters": {"one": 1}}));
+    /// ```
+    pub fn migrate<F, E>(&mut self, f: F) -> Result<(), E>
+    where
+        F: FnOnce(Value) -> Result<Value, E>,
+    {
+        self.state = f(self.state.clone())?;
+        Ok(())
+    }
+
+    /// Deserialize the system state into the given type
     pub fn state<S: DeserializeOwned>(&self) -> Result<S, serde_json::Error> {
         let s = serde_json::from_value(self.state.clone())?;
         Ok(s)
@@ -89,6 +197,9 @@ impl System {
         self
     }
 
+    /// Add a shared resource to the system
+    ///
+    /// Resources are available to jobs via the [Res](`crate::extract::Res`) extractor
     pub fn with_res<R>(mut self, res: R) -> Self
     where
         R: Send + Sync + 'static,
@@ -112,3 +223,94 @@ impl Deref for System {
         &self.state
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct State {
+        counter: i32,
+    }
+
+    fn rename_count(mut state: Value) -> Result<Value, serde_json::Error> {
+        if let Some(count) = state.as_object_mut().and_then(|obj| obj.remove("count")) {
+            state["counter"] = count;
+        }
+        Ok(state)
+    }
+
+    #[test]
+    fn it_allows_modifying_the_root_value() {
+        let before = System::try_from(json!({"counter": 1, "name": "one"})).unwrap();
+
+        let mut after = before.clone();
+        after.root_mut()["counter"] = json!(2);
+
+        let changes = json_patch::diff(before.root(), after.root());
+        assert_eq!(
+            serde_json::to_value(changes).unwrap(),
+            json!([{"op": "replace", "path": "/counter", "value": 2}])
+        );
+        assert_eq!(after.state::<State>().unwrap(), State { counter: 2 });
+    }
+
+    #[test]
+    fn it_formats_the_state_as_indented_json() {
+        let system = System::try_from(json!({"counters": {"one": 1, "two": 2}})).unwrap();
+
+        let pretty = system.to_pretty();
+        assert!(pretty.contains("\"counters\": {"));
+        assert!(pretty.contains("\"one\": 1"));
+        assert!(pretty.contains("\"two\": 2"));
+        assert!(pretty.lines().count() > 1);
+
+        assert_eq!(
+            system.to_value(),
+            &json!({"counters": {"one": 1, "two": 2}})
+        );
+    }
+
+    #[test]
+    fn it_calculates_the_same_fingerprint_for_equal_states() {
+        let one = System::try_from(json!({"a": 1, "b": [1, 2]})).unwrap();
+        let two = System::try_from(json!({"b": [1, 2], "a": 1})).unwrap();
+        let three = System::try_from(json!({"a": 1, "b": [2, 1]})).unwrap();
+
+        assert_eq!(one.fingerprint(), two.fingerprint());
+        assert_ne!(one.fingerprint(), three.fingerprint());
+    }
+
+    #[test]
+    fn it_keeps_the_baseline_after_changes() {
+        let mut system = System::try_from(json!({"counter": 1})).unwrap();
+        assert_eq!(system.baseline(), &json!({"counter": 1}));
+
+        system.set_baseline();
+        system.root_mut()["counter"] = json!(2);
+
+        assert_eq!(system.baseline(), &json!({"counter": 1}));
+        assert_eq!(system.clone().baseline(), &json!({"counter": 1}));
+    }
+
+    #[test]
+    fn it_migrates_the_state_before_deserializing() {
+        let mut system = System::try_from(json!({"count": 1})).unwrap();
+        assert!(system.state::<State>().is_err());
+
+        system.migrate(rename_count).unwrap();
+        assert_eq!(system.state::<State>().unwrap(), State { counter: 1 });
+    }
+
+    #[test]
+    fn it_keeps_the_state_if_the_migration_fails() {
+        let mut system = System::try_from(json!({"count": 1})).unwrap();
+
+        let res = system.migrate(|_| Err("unsupported version"));
+        assert_eq!(res, Err("unsupported version"));
+        assert_eq!(system.root(), &json!({"count": 1}));
+    }
+}
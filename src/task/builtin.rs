@@ -0,0 +1,66 @@
+use super::{Handler, Task};
+
+const NOOP: &str = "mahler::task::noop";
+const UNREACHABLE: &str = "mahler::task::unreachable";
+
+fn noop_action() {}
+
+/// Create a task that makes no changes to the system and always succeeds
+///
+/// This is useful as a placeholder while wiring a domain, or to provide a
+/// branch of a method that should be accepted without modifying the state.
+///
+/// Built-in tasks do not need to be registered as jobs to be used in a method expansion; they
+/// take the path and arguments of the parent method. They can still be registered as a job
+/// for a route by converting them with [`Job::from`](`super::Job`).
+///
+/// # Planner semantics
+///
+/// A `noop` task produces an empty patch. Within a method expansion this is accepted and the
+/// task is added to the workflow, but if the task is selected directly by the planner (e.g.
+/// when registered as a job), the lack of changes means it is treated the same as a condition
+/// failure and the candidate is skipped.
+///
+/// ```rust
+/// use mahler::task::{self, prelude::*};
+///
+/// fn placeholder() -> Vec<Task> {
+///     vec![task::noop()]
+/// }
+/// ```
+pub fn noop() -> Task {
+    noop_action.into_task().with_id(NOOP).allow_empty_changes()
+}
+
+/// Create a task that is never applicable
+///
+/// The task condition always fails, which means the planner will always discard it. When
+/// used as part of a method expansion, the whole method is discarded. This is useful to
+/// mark branches of a method that should never be reached, or to disable a job while
+/// testing.
+///
+/// Like [`noop`], the task does not need to be registered as a job to be used in a method
+/// expansion.
+///
+/// ```rust
+/// use mahler::task::{self, prelude::*};
+///
+/// fn not_yet_implemented() -> Vec<Task> {
+///     vec![task::unreachable()]
+/// }
+/// ```
+pub fn unreachable() -> Task {
+    noop_action
+        .into_task()
+        .with_id(UNREACHABLE)
+        .with_condition(|_, _| false)
+}
+
+/// Return the built-in task with the given id, if any
+pub(crate) fn builtin(id: &str) -> Option<Task> {
+    match id {
+        NOOP => Some(noop()),
+        UNREACHABLE => Some(unreachable()),
+        _ => None,
+    }
+}
@@ -1,26 +1,81 @@
 use jsonptr::PointerBuf;
 use serde_json::Value;
+use std::collections::BTreeSet;
 
 use super::errors::Error;
 use crate::path::{Path, PathArgs};
 
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
 /// Describes the Task applicability context
+///
+/// The builder methods allow to create a context outside of a Worker, which is useful to
+/// test custom extractors.
+///
+/// # Example
+///
+/// ```rust
+/// use anyhow::anyhow;
+/// use serde_json::json;
+/// use mahler::System;
+/// use mahler::errors::ExtractionError;
+/// use mahler::task::{Context, FromSystem};
+///
+/// /// Remaining increments needed to reach the target of a counter
+/// struct Remaining(i64);
+///
+/// impl FromSystem for Remaining {
+///     type Error = ExtractionError;
+///
+///     fn from_system(system: &System, context: &Context) -> Result<Self, Self::Error> {
+///         let name = context.arg("counter").ok_or_else(|| anyhow!("no counter name"))?;
+///         let current = system
+///             .root()
+///             .pointer(&format!("/{name}"))
+///             .and_then(|value| value.as_i64())
+///             .ok_or_else(|| anyhow!("counter {name} not found"))?;
+///         let target = context
+///             .target()
+///             .as_i64()
+///             .ok_or_else(|| anyhow!("invalid target for {name}"))?;
+///
+///         Ok(Remaining(target - current))
+///     }
+/// }
+///
+/// let system = System::try_from(json!({"one": 1, "two": 0})).unwrap();
+/// let context = Context::new()
+///     .with_path("/one")
+///     .with_arg("counter", "one")
+///     .with_target(json!(3));
+///
+/// let Remaining(remaining) = Remaining::from_system(&system, &context).unwrap();
+/// assert_eq!(remaining, 2);
+/// ```
 pub struct Context {
     pub(crate) target: Value,
     pub(crate) path: Path,
     pub(crate) args: PathArgs,
+    // Arguments that should not be replaced by the
+    // parent task arguments
+    pub(crate) overrides: BTreeSet<String>,
 }
 
 impl Context {
+    /// Create an empty context
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Set the target value for the context
     pub fn with_target(self, target: Value) -> Self {
         Self { target, ..self }
     }
 
+    /// Set the path for the context
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the path is not a valid JSON pointer
     pub fn with_path(self, path: impl AsRef<str>) -> Self {
         let path = Path::new(
             PointerBuf::parse(path.as_ref())
@@ -31,11 +86,41 @@ impl Context {
         Self { path, ..self }
     }
 
+    /// Add a path argument to the context
     pub fn with_arg(self, key: impl AsRef<str>, value: impl Into<String>) -> Self {
         let Self { mut args, .. } = self;
         args.insert(key, value);
         Self { args, ..self }
     }
+
+    /// Get the path the task applies to
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    /// Get the target value for the task path
+    pub fn target(&self) -> &Value {
+        &self.target
+    }
+
+    /// Get the value of a path argument
+    pub fn arg(&self, key: &str) -> Option<&str> {
+        self.args
+            .iter()
+            .find(|(k, _)| k.as_ref() == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub(crate) fn with_arg_override(self, key: impl AsRef<str>, value: impl Into<String>) -> Self {
+        let mut context = self.with_arg(&key, value);
+        context.overrides.insert(String::from(key.as_ref()));
+        context
+    }
+
+    /// Return true if the argument should not be replaced by the parent task argument
+    pub(crate) fn is_override(&self, key: &str) -> bool {
+        self.overrides.contains(key)
+    }
 }
 
 /// Trait for types that can be initialized from a context
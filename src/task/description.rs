@@ -1,4 +1,5 @@
-use super::{Context, Error, FromContext};
+use super::{Context, Error, FromContext, FromSystemRef};
+use crate::system::System;
 
 /// Trait for functions that can be used to describe a Job/Task
 ///
@@ -11,6 +12,26 @@ use super::{Context, Error, FromContext};
 /// - [`crate::extract::Target`] to extract the task target
 /// - [`crate::extract::Args`] to extract the task args
 /// - [`crate::extract::Path`] to extract the task path
+///
+/// Descriptions are rendered from the task context alone, so extractors that require access to
+/// the system state, such as [`crate::extract::View`] or [`crate::extract::System`], cannot be
+/// used. Use a [`SystemDescription`] to describe a task using the system state.
+///
+/// ```rust
+/// use mahler::extract::{Args, Target, View};
+/// use mahler::task::prelude::*;
+///
+/// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+///     if *counter < tgt {
+///         *counter += 1;
+///     }
+///     counter
+/// }
+///
+/// let job = update(plus_one).with_description(|Args(counter): Args<String>, Target(tgt): Target<i32>| {
+///     format!("increment counter {counter} until {tgt}")
+/// });
+/// ```
 pub trait Description<T>: Clone + Sync + Send + 'static {
     fn call(&self, context: &Context) -> Result<String, Error>;
 }
@@ -51,3 +72,75 @@ impl_description!(T1, T2, T3, T4, T5);
 impl_description!(T1, T2, T3, T4, T5, T6);
 impl_description!(T1, T2, T3, T4, T5, T6, T7);
 impl_description!(T1, T2, T3, T4, T5, T6, T7, T8);
+
+/// Trait for functions that can be used to describe a Job/Task from the system state
+///
+/// A system description is any function that accepts zero or more
+/// [extractors](`crate::extract`) as arguments and returns a String. Unlike a [`Description`],
+/// extractors that read the system state, such as [`crate::extract::View`] or
+/// [`crate::extract::System`], can be used, allowing the description to include the current value
+/// at the task path.
+///
+/// When the task is executed by the Worker, the description is evaluated against the system
+/// state at the time the task runs.
+///
+/// ```rust
+/// use mahler::extract::{Args, System};
+/// use mahler::task::prelude::*;
+/// use serde_json::Value;
+///
+/// fn foo() {}
+///
+/// let job = update(foo).with_system_description(|Args(counter): Args<String>, System(state): System<Value>| {
+///     format!("increment counter {counter} from {}", state["counters"][&counter])
+/// });
+/// ```
+pub trait SystemDescription<T>: Clone + Sync + Send + 'static {
+    fn call(&self, system: &System, context: &Context) -> Result<String, Error>;
+}
+
+macro_rules! impl_system_description {
+    (
+        $first:ident, $($ty:ident),*
+    ) => {
+        #[allow(non_snake_case, unused)]
+        impl<F, Res, $($ty,)*> SystemDescription<($($ty,)*)> for F
+        where
+            F: Fn($($ty,)*) -> Res
+                + for<'a> Fn($(<$ty as FromSystemRef>::Output<'a>,)*) -> Res
+                + Clone
+                + Send
+                + Sync
+                + 'static,
+            Res: Into<String>,
+            $($ty: FromSystemRef,)*
+        {
+            fn call(&self, system: &System, context: &Context) -> Result<String, Error> {
+                $(
+                    let $ty = match $ty::from_system_ref(system, context) {
+                        Ok(value) => value,
+                        Err(failure) => {
+                            return Err(failure.into())
+                        }
+                    };
+                )*
+
+                // See the handler implementation
+                fn call_inner<Res, $($ty,)*>(f: impl Fn($($ty,)*) -> Res, ($($ty,)*): ($($ty,)*)) -> Res {
+                    f($($ty,)*)
+                }
+                Ok(call_inner(self, ($($ty,)*)).into())
+            }
+
+        }
+    };
+}
+
+impl_system_description!(T1,);
+impl_system_description!(T1, T2);
+impl_system_description!(T1, T2, T3);
+impl_system_description!(T1, T2, T3, T4);
+impl_system_description!(T1, T2, T3, T4, T5);
+impl_system_description!(T1, T2, T3, T4, T5, T6);
+impl_system_description!(T1, T2, T3, T4, T5, T6, T7);
+impl_system_description!(T1, T2, T3, T4, T5, T6, T7, T8);
@@ -1,10 +1,78 @@
+use futures::{stream, Stream, StreamExt};
+use serde::Serialize;
 use std::convert::Infallible;
 use std::future::Future;
 use std::pin::Pin;
+use std::task::{ready, Poll};
 
 type IOResult<O, E> = Pin<Box<dyn Future<Output = Result<O, E>> + Send>>;
 type IO<O, E = Infallible, I = O> = Box<dyn FnOnce(I) -> IOResult<O, E> + Send>;
 type Pure<O, E, I> = Box<dyn FnOnce(I) -> Result<O, E> + Send>;
+type Steps<O, E> = Pin<Box<dyn Stream<Item = Result<Step<O>, E>> + Send>>;
+type IOStream<O, E, I> = Box<dyn FnOnce(I) -> Steps<O, E> + Send>;
+type Parts<O, E> = (Result<O, E>, Pure<O, E, O>, IO<O, E>);
+
+// A result of a streaming computation. The stream of steps of a computation
+// terminates with the first error or after the final result
+enum Step<O> {
+    // An intermediate result
+    Partial(O),
+    // The result of the computation
+    Done(O),
+}
+
+impl<O> Step<O> {
+    fn map<U>(self, f: impl FnOnce(O) -> U) -> Step<U> {
+        match self {
+            Step::Partial(o) => Step::Partial(f(o)),
+            Step::Done(o) => Step::Done(f(o)),
+        }
+    }
+}
+
+mod streaming {
+    use super::{IOStream, Pure};
+
+    /// Effectful computation reporting intermediate results
+    ///
+    /// This type cannot be constructed or inspected outside of the crate, use
+    /// [`Effect::with_io_stream`](`super::Effect::with_io_stream`) to create it.
+    pub struct Streaming<O, E, I> {
+        pub(super) input: Result<I, E>,
+        pub(super) pure: Pure<O, E, I>,
+        pub(super) io: IOStream<O, E, I>,
+    }
+}
+
+use streaming::Streaming;
+
+// Run the steps of a streaming computation, passing the intermediate results to the callback
+async fn run_steps<O, E>(mut steps: Steps<O, E>, mut report: impl FnMut(O)) -> Result<O, E> {
+    while let Some(step) = steps.next().await {
+        match step? {
+            Step::Partial(o) => report(o),
+            Step::Done(o) => return Ok(o),
+        }
+    }
+    unreachable!("streaming computations terminate with an error or a result")
+}
+
+// Calls the cleanup function when dropped, unless disarmed
+struct CancelGuard<F: FnOnce()>(Option<F>);
+
+impl<F: FnOnce()> CancelGuard<F> {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl<F: FnOnce()> Drop for CancelGuard<F> {
+    fn drop(&mut self) {
+        if let Some(cleanup) = self.0.take() {
+            cleanup();
+        }
+    }
+}
 
 /// Encode pure and IO operations on a single type
 ///
@@ -15,8 +83,11 @@ type Pure<O, E, I> = Box<dyn FnOnce(I) -> Result<O, E> + Send>;
 /// `Effect` is used as a return type for the Job handler to allow the planner to safely test the
 /// Job effect without actually having to run the function.
 ///
-/// An effect may be `Pure`, meaning it has no side-effects (is just a value), or `IO`, meaning it
-/// has pure and effectful computations.
+/// An effect may be `Pure`, meaning it has no side-effects (is just a value), `IO`, meaning it
+/// has pure and effectful computations, or `Stream`, meaning the effectful computation reports
+/// intermediate results before producing the final value. The representation of `Stream`
+/// effects is private, see [`Effect::with_io_stream`].
+#[non_exhaustive]
 pub enum Effect<O, E = Infallible, I = O> {
     /// Pure computation with no side-effects
     Pure(Result<O, E>),
@@ -29,6 +100,37 @@ pub enum Effect<O, E = Infallible, I = O> {
         /// Effectful operation for the given input
         io: IO<O, E, I>,
     },
+    /// Effectful computation reporting intermediate results
+    Stream(Streaming<O, E, I>),
+}
+
+/// Payload of an effect created with [`Effect::from_value`]
+///
+/// When returned from a job, the wrapped value is serialized and written to the job path.
+#[derive(Debug, Clone)]
+pub struct Assign<T>(pub T);
+
+impl<T, E> Effect<Assign<T>, E> {
+    /// Create a pure `Effect` that writes the value to the task path
+    ///
+    /// This allows actions to compute a new value for the path without having to use a
+    /// [`Pointer`](`crate::extract::Pointer`) or [`View`](`crate::extract::View`) extractor.
+    ///
+    /// ```rust
+    /// use mahler::task::{Effect, Set};
+    /// use mahler::extract::Target;
+    ///
+    /// // Write the target value to the job path
+    /// fn reset(Target(tgt): Target<i32>) -> Set<i32> {
+    ///     Effect::from_value(tgt)
+    /// }
+    /// ```
+    pub fn from_value(value: T) -> Self
+    where
+        T: Serialize,
+    {
+        Effect::of(Assign(value))
+    }
 }
 
 impl<O, E> Effect<O, E> {
@@ -87,9 +189,149 @@ impl<O, E> Effect<O, E> {
                 pure,
                 io,
             },
-            Effect::IO { input, .. } => Effect::IO { input, pure, io },
+            Effect::IO { input, .. } | Effect::Stream(Streaming { input, .. }) => {
+                Effect::IO { input, pure, io }
+            }
         }
     }
+
+    /// Convert the effect to an effectful computation reporting intermediate results
+    ///
+    /// The effectful computation returns a [`Stream`](`futures::Stream`) of partial results.
+    /// When the effect is used in a Job, the Worker applies the changes of every partial result
+    /// to the system state as they are yielded, which is useful for long running operations
+    /// (e.g. downloads) to report progress. The last value yielded by the stream is the result of
+    /// the effect, if the stream yields no values, the result is the input value.
+    ///
+    /// Note that only the first error yielded by the stream is returned, terminating the
+    /// computation.
+    ///
+    /// ```rust
+    /// use mahler::task::Effect;
+    /// use futures::stream;
+    ///
+    /// let e: Effect<i32> = Effect::of(0)
+    ///         .with_io_stream(|i| {
+    ///             // report each step of the computation
+    ///             stream::iter([Ok(i + 1), Ok(i + 2), Ok(i + 3)])
+    ///         });
+    ///
+    /// # tokio_test::block_on(async move {
+    /// assert_eq!(e.run().await, Ok(3));
+    /// # })
+    /// ```
+    pub fn with_io_stream<F, S>(self, f: F) -> Effect<O, E>
+    where
+        F: FnOnce(O) -> S + Send + 'static,
+        S: futures::Stream<Item = Result<O, E>> + Send + 'static,
+        O: Clone + Send + 'static,
+        E: Send + 'static,
+    {
+        let io: IOStream<O, E, O> = Box::new(|o| {
+            let state = Some((Box::pin(f(o.clone())), o));
+            Box::pin(stream::unfold(state, |state| async move {
+                let (mut results, last) = state?;
+                match results.next().await {
+                    Some(Ok(o)) => Some((Ok(Step::Partial(o.clone())), Some((results, o)))),
+                    Some(Err(e)) => Some((Err(e), None)),
+                    None => Some((Ok(Step::Done(last)), None)),
+                }
+            }))
+        });
+        let pure = Box::new(|o| Ok(o));
+        let input = match self {
+            Effect::Pure(output) => output,
+            Effect::IO { input, .. } | Effect::Stream(Streaming { input, .. }) => input,
+        };
+        Effect::Stream(Streaming { input, pure, io })
+    }
+
+    /// Combine two effects, running their effectful computations concurrently
+    ///
+    /// The pure part of the resulting effect combines the pure values of both effects.
+    /// Intermediate results reported by effects created with [`Effect::with_io_stream`] are
+    /// not reported by the combined effect.
+    ///
+    /// ```rust
+    /// use mahler::task::Effect;
+    /// use tokio::time::{sleep, Duration};
+    ///
+    /// let read_a: Effect<i32> = Effect::of(0).with_io(|i| async move {
+    ///     sleep(Duration::from_millis(10)).await;
+    ///     Ok(i + 1)
+    /// });
+    /// let read_b: Effect<String> = Effect::of(String::from("b"));
+    ///
+    /// let e = read_a.join(read_b);
+    ///
+    /// # tokio_test::block_on(async move {
+    /// assert_eq!(e.run().await, Ok((1, String::from("b"))));
+    /// # })
+    /// ```
+    pub fn join<U>(self, other: Effect<U, E>) -> Effect<(O, U), E>
+    where
+        O: Send + 'static,
+        U: Send + 'static,
+        E: Send + 'static,
+    {
+        let (input_o, pure_o, io_o) = self.into_parts();
+        let (input_u, pure_u, io_u) = other.into_parts();
+
+        let input = input_o.and_then(|o| input_u.map(|u| (o, u)));
+        let pure: Pure<(O, U), E, (O, U)> = Box::new(|(o, u)| Ok((pure_o(o)?, pure_u(u)?)));
+        let io: IO<(O, U), E> =
+            Box::new(|(o, u)| Box::pin(futures::future::try_join(io_o(o), io_u(u))));
+
+        Effect::IO { input, pure, io }
+    }
+
+    // Split the effect into its input, pure and effectful computations
+    fn into_parts(self) -> Parts<O, E>
+    where
+        O: Send + 'static,
+        E: Send + 'static,
+    {
+        match self {
+            Effect::Pure(output) => (
+                output,
+                Box::new(|o| Ok(o)),
+                Box::new(|o| Box::pin(async { Ok(o) })),
+            ),
+            Effect::IO { input, pure, io } => (input, pure, io),
+            Effect::Stream(Streaming { input, pure, io }) => (
+                input,
+                pure,
+                Box::new(|o| Box::pin(run_steps(io(o), |_| {}))),
+            ),
+        }
+    }
+}
+
+impl<E> Effect<(), E> {
+    /// Create an `Effect` that performs IO but produces no changes
+    ///
+    /// The pure part of the effect returns `()`, so an action returning the effect will
+    /// produce no changes to the system state. See
+    /// [`Job::allow_empty_changes`](`super::Job::allow_empty_changes`) for how to use
+    /// these actions with the planner.
+    ///
+    /// ```rust
+    /// use mahler::task::Effect;
+    ///
+    /// let e: Effect<()> = Effect::side_effect(|| async {
+    ///     // perform some IO
+    ///     Ok(())
+    /// });
+    ///
+    /// assert_eq!(e.pure(), Ok(()));
+    /// ```
+    pub fn side_effect<F, Res>(f: F) -> Effect<(), E>
+    where
+        F: FnOnce() -> Res + Send + 'static,
+        Res: Future<Output = Result<(), E>> + Send,
+    {
+        Effect::of(()).with_io(|_| f())
+    }
 }
 
 impl<T: 'static, E: 'static, I: Send + 'static> Effect<T, E, I> {
@@ -100,6 +342,9 @@ impl<T: 'static, E: 'static, I: Send + 'static> Effect<T, E, I> {
 
     /// Transform the effect output type using a pure function
     ///
+    /// Intermediate results of a [streaming](`Effect::with_io_stream`) effect are transformed
+    /// by the same function.
+    ///
     ///```rust
     /// use mahler::task::Effect;
     /// use std::convert::Infallible;
@@ -134,6 +379,16 @@ impl<T: 'static, E: 'static, I: Send + 'static> Effect<T, E, I> {
                     }),
                 }
             }
+            Effect::Stream(Streaming { input, pure, io }) => {
+                let fc = fu.clone();
+                Effect::Stream(Streaming {
+                    input,
+                    pure: Box::new(|i| pure(i).map(fc)),
+                    io: Box::new(|i| {
+                        Box::pin(io(i).map(move |step| step.map(|s| s.map(fu.clone()))))
+                    }),
+                })
+            }
         }
     }
 
@@ -169,11 +424,136 @@ impl<T: 'static, E: 'static, I: Send + 'static> Effect<T, E, I> {
                     })
                 }),
             },
+            // Only the final result of the stream is transformed
+            Effect::Stream(Streaming { input, pure, io }) => Effect::Stream(Streaming {
+                input,
+                pure,
+                io: Box::new(|i| {
+                    let mut fu = Some(fu);
+                    Box::pin(io(i).map(move |step| match step {
+                        Ok(Step::Done(t)) => {
+                            let fu = fu.take().expect("the stream has a single final result");
+                            Ok(Step::Done(fu(t)))
+                        }
+                        step => step,
+                    }))
+                }),
+            }),
+        }
+    }
+
+    /// Call a function if the effectful computation is cancelled
+    ///
+    /// The function is called if the future running the effectful part of the effect is dropped
+    /// after it started but before it completed, e.g. when the workflow is interrupted or the
+    /// task times out. This allows to release locks or handles acquired by the computation. The
+    /// function is not called if the computation completes, even with an error. It is called,
+    /// however, if the computation panics, as the future is dropped without completing while
+    /// the panic unwinds.
+    ///
+    /// The function is attached to the current effectful computation, so `on_cancel` needs to be
+    /// called after [`Effect::with_io`] or [`Effect::with_io_stream`]. Calling it on a pure
+    /// effect has no effect, and the function is discarded if the effectful computation is
+    /// replaced afterwards.
+    ///
+    ///```rust
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// use mahler::task::Effect;
+    /// use tokio::time::{sleep, timeout, Duration};
+    ///
+    /// fn new_effect(released: Arc<AtomicBool>) -> Effect<i32> {
+    ///     Effect::of(0)
+    ///         .with_io(|i| async move {
+    ///             sleep(Duration::from_secs(10)).await;
+    ///             Ok(i + 1)
+    ///         })
+    ///         .on_cancel(move || released.store(true, Ordering::SeqCst))
+    /// }
+    ///
+    /// # tokio_test::block_on(async move {
+    /// let released = Arc::new(AtomicBool::new(false));
+    ///
+    /// // the effect is cancelled when the timeout elapses
+    /// let effect = new_effect(released.clone());
+    /// assert!(timeout(Duration::from_millis(10), effect.run()).await.is_err());
+    /// assert!(released.load(Ordering::SeqCst));
+    /// # })
+    /// ```
+    pub fn on_cancel<F: FnOnce() + Send + 'static>(self, f: F) -> Effect<T, E, I> {
+        match self {
+            Effect::Pure(output) => Effect::Pure(output),
+            Effect::IO { input, pure, io } => Effect::IO {
+                input,
+                pure,
+                io: Box::new(|i| {
+                    Box::pin(async move {
+                        let guard = CancelGuard(Some(f));
+                        let res = io(i).await;
+                        guard.disarm();
+                        res
+                    })
+                }),
+            },
+            Effect::Stream(Streaming { input, pure, io }) => Effect::Stream(Streaming {
+                input,
+                pure,
+                io: Box::new(|i| {
+                    // Arm the guard on the first poll, as with the future above
+                    Box::pin(
+                        stream::once(async move {
+                            let mut guard = Some(CancelGuard(Some(f)));
+                            io(i).inspect(move |step| {
+                                // The computation completes with the first error or the final result
+                                if matches!(step, Err(_) | Ok(Step::Done(_))) {
+                                    if let Some(guard) = guard.take() {
+                                        guard.disarm();
+                                    }
+                                }
+                            })
+                        })
+                        .flatten(),
+                    )
+                }),
+            }),
         }
     }
 
+    /// Call a function on the effect output without modifying it
+    ///
+    /// The function is called with the result of the pure computation when simulating the
+    /// effect, and with the result of the effectful computation when running it. This is useful
+    /// for logging or metrics, in the same way as [`Iterator::inspect`].
+    ///
+    ///```rust
+    /// use mahler::task::Effect;
+    ///
+    /// fn new_effect() -> Effect<i32> {
+    ///     Effect::of(0)
+    ///         .with_io(|i| async move {
+    ///             Ok(i + 1)
+    ///         })
+    ///         .inspect(|i| println!("the value is {i}"))
+    /// }
+    ///
+    /// assert_eq!(new_effect().pure(), Ok(0));
+    /// # tokio_test::block_on(async move {
+    /// assert_eq!(new_effect().run().await, Ok(1));
+    /// # })
+    /// ```
+    pub fn inspect<F: Fn(&T) + Clone + Send + 'static>(self, f: F) -> Effect<T, E, I> {
+        self.map(move |t| {
+            f(&t);
+            t
+        })
+    }
+
     /// Transform the effect output type using a pure function returning a Result
     ///
+    /// Intermediate results of a [streaming](`Effect::with_io_stream`) effect that fail the
+    /// transformation are not reported.
+    ///
     ///```rust
     /// use mahler::task::Effect;
     /// use std::convert::Infallible;
@@ -210,6 +590,30 @@ impl<T: 'static, E: 'static, I: Send + 'static> Effect<T, E, I> {
                     }),
                 }
             }
+            Effect::Stream(Streaming { input, pure, io }) => {
+                let fc = fu.clone();
+                Effect::Stream(Streaming {
+                    input,
+                    pure: Box::new(|i| pure(i).and_then(fc)),
+                    io: Box::new(|i| {
+                        let mut steps = io(i);
+                        Box::pin(stream::poll_fn(move |cx| loop {
+                            let step = match ready!(steps.poll_next_unpin(cx)) {
+                                Some(Ok(Step::Partial(t))) => match fu.clone()(t) {
+                                    Ok(o) => Ok(Step::Partial(o)),
+                                    // Intermediate results that fail the transformation
+                                    // are not reported
+                                    Err(_) => continue,
+                                },
+                                Some(Ok(Step::Done(t))) => fu.clone()(t).map(Step::Done),
+                                Some(Err(e)) => Err(e),
+                                None => return Poll::Ready(None),
+                            };
+                            return Poll::Ready(Some(step));
+                        }))
+                    }),
+                })
+            }
         }
     }
 
@@ -244,6 +648,15 @@ impl<T: 'static, E: 'static, I: Send + 'static> Effect<T, E, I> {
                     io: Box::new(|i| Box::pin(async { io(i).await.map_err(fe) })),
                 }
             }
+            Effect::Stream(Streaming { input, pure, io }) => {
+                let fc1 = fe.clone();
+                let fc2 = fe.clone();
+                Effect::Stream(Streaming {
+                    input: input.map_err(fc1),
+                    pure: Box::new(|i| pure(i).map_err(fc2)),
+                    io: Box::new(|i| Box::pin(io(i).map(move |step| step.map_err(fe.clone())))),
+                })
+            }
         }
     }
 
@@ -265,7 +678,9 @@ impl<T: 'static, E: 'static, I: Send + 'static> Effect<T, E, I> {
     pub fn pure(self) -> Result<T, E> {
         match self {
             Effect::Pure(output) => output,
-            Effect::IO { input, pure, .. } => input.and_then(pure),
+            Effect::IO { input, pure, .. } | Effect::Stream(Streaming { input, pure, .. }) => {
+                input.and_then(pure)
+            }
         }
     }
     /// Run the effectful (IO) part of the effect
@@ -293,6 +708,27 @@ impl<T: 'static, E: 'static, I: Send + 'static> Effect<T, E, I> {
                 let i = input?;
                 io(i).await
             }
+            Effect::Stream(Streaming { input, io, .. }) => {
+                let i = input?;
+                run_steps(io(i), |_| {}).await
+            }
+        }
+    }
+
+    /// Run the effectful part of the effect, passing intermediate results to
+    /// the given callback
+    ///
+    /// Only effects created with [`Effect::with_io_stream`] report intermediate results
+    pub(crate) async fn run_with_progress(
+        self,
+        report: impl FnMut(T) + Send + 'static,
+    ) -> Result<T, E> {
+        match self {
+            Effect::Stream(Streaming { input, io, .. }) => {
+                let i = input?;
+                run_steps(io(i), report).await
+            }
+            effect => effect.run().await,
         }
     }
 }
@@ -345,6 +781,200 @@ mod tests {
         assert_eq!(effect.pure(), Err("ERROR"))
     }
 
+    #[tokio::test]
+    async fn it_allows_inspecting_the_effect_output() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let new_effect = || {
+            let tx = tx.clone();
+            Effect::of(0)
+                .with_io(|x| async move { Ok(x + 1) as Result<i32, ()> })
+                .inspect(move |x| tx.send(*x).unwrap())
+        };
+
+        // The pure value is observed on dry-run
+        assert_eq!(new_effect().pure(), Ok(0));
+        assert_eq!(rx.recv().await, Some(0));
+
+        // The IO result is observed on run
+        assert_eq!(new_effect().run().await, Ok(1));
+        assert_eq!(rx.recv().await, Some(1));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn it_runs_joined_effects_concurrently() {
+        let sleepy = |x: i32| {
+            Effect::of(x).with_io(|x| async move {
+                sleep(Duration::from_millis(10)).await;
+                Ok(x + 1) as Result<i32, ()>
+            })
+        };
+
+        let effect = sleepy(0).join(sleepy(10));
+        assert_eq!(effect.pure(), Ok((0, 10)));
+
+        let start = tokio::time::Instant::now();
+        let effect = sleepy(0).join(sleepy(10));
+        assert_eq!(effect.run().await, Ok((1, 11)));
+        assert_eq!(start.elapsed(), Duration::from_millis(10));
+    }
+
+    #[tokio::test]
+    async fn it_fails_joined_effects_if_either_fails() {
+        let effect = Effect::of(0)
+            .with_io(|_| async { Err("ERROR") })
+            .join(Effect::of(1));
+
+        assert_eq!(effect.run().await, Err("ERROR"));
+    }
+
+    #[tokio::test]
+    async fn it_allows_side_effects_without_output() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let effect: Effect<(), ()> = Effect::side_effect(move || async move {
+            tx.send("notified").unwrap();
+            Ok(())
+        });
+
+        assert_eq!(effect.run().await, Ok(()));
+        assert_eq!(rx.recv().await, Some("notified"));
+    }
+
+    #[tokio::test]
+    async fn it_reports_intermediate_results() {
+        let effect: Effect<String, (), i32> = Effect::of(0)
+            .map(|x| x + 1)
+            .with_io_stream(|x| futures::stream::iter([Ok(x + 1), Ok(x + 2), Ok(x + 3)]))
+            .map(|x| format!("result: {}", x));
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let res: Result<String, ()> = effect.run_with_progress(move |o| tx.send(o).unwrap()).await;
+        assert_eq!(res, Ok("result: 4".to_string()));
+
+        let mut reported = Vec::new();
+        while let Some(o) = rx.recv().await {
+            reported.push(o);
+        }
+        assert_eq!(reported, vec!["result: 2", "result: 3", "result: 4"]);
+    }
+
+    #[tokio::test]
+    async fn it_skips_intermediate_results_failing_the_transformation() {
+        let effect: Effect<i32, &str> = Effect::of(0)
+            .with_io_stream(|x| futures::stream::iter([Ok(x + 1), Ok(x + 2), Ok(x + 3)]))
+            .and_then(|x| if x % 2 == 0 { Err("odd") } else { Ok(x) });
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let res = effect.run_with_progress(move |o| tx.send(o).unwrap()).await;
+        assert_eq!(res, Ok(3));
+
+        let mut reported = Vec::new();
+        while let Some(o) = rx.recv().await {
+            reported.push(o);
+        }
+        assert_eq!(reported, vec![1, 3]);
+    }
+
+    #[tokio::test]
+    async fn it_calls_the_cleanup_function_if_the_io_is_cancelled() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        use tokio::time::timeout;
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = cancelled.clone();
+        let effect: Effect<i32> = Effect::of(0)
+            .with_io(|x| async move {
+                sleep(Duration::from_millis(100)).await;
+                Ok(x + 1)
+            })
+            .on_cancel(move || flag.store(true, Ordering::SeqCst));
+
+        // The IO future is dropped before completion
+        assert!(timeout(Duration::from_millis(10), effect.run())
+            .await
+            .is_err());
+        assert!(cancelled.load(Ordering::SeqCst));
+
+        // The cleanup is not called if the IO completes
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = cancelled.clone();
+        let effect: Effect<i32> = Effect::of(0)
+            .with_io(|x| async move { Ok(x + 1) })
+            .on_cancel(move || flag.store(true, Ordering::SeqCst));
+
+        assert_eq!(effect.run().await, Ok(1));
+        assert!(!cancelled.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_calls_the_cleanup_function_if_the_io_panics() {
+        use futures::FutureExt;
+        use std::panic::AssertUnwindSafe;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = cancelled.clone();
+        let effect: Effect<i32> = Effect::of(0)
+            .with_io(|_| async move { panic!("this is a panic") })
+            .on_cancel(move || flag.store(true, Ordering::SeqCst));
+
+        assert!(AssertUnwindSafe(effect.run()).catch_unwind().await.is_err());
+        assert!(cancelled.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_arms_the_stream_cleanup_function_on_first_poll() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let new_effect = |flag: Arc<AtomicBool>| -> Effect<i32> {
+            Effect::of(0)
+                .with_io_stream(|x| futures::stream::iter([Ok(x + 1), Ok(x + 2)]))
+                .on_cancel(move || flag.store(true, Ordering::SeqCst))
+        };
+
+        // Dropping the stream before polling it does not call the cleanup
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let Effect::Stream(Streaming { input, io, .. }) = new_effect(cancelled.clone()) else {
+            panic!("expected a streaming effect");
+        };
+        drop(io(input.unwrap()));
+        assert!(!cancelled.load(Ordering::SeqCst));
+
+        // Dropping the stream after the first step calls the cleanup
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let Effect::Stream(Streaming { input, io, .. }) = new_effect(cancelled.clone()) else {
+            panic!("expected a streaming effect");
+        };
+        let mut stream = io(input.unwrap());
+        assert!(stream.next().await.is_some());
+        drop(stream);
+        assert!(cancelled.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_discards_the_cleanup_function_if_the_io_is_replaced() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        use tokio::time::timeout;
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = cancelled.clone();
+        let effect: Effect<i32> = Effect::of(0)
+            .on_cancel(move || flag.store(true, Ordering::SeqCst))
+            .with_io(|x| async move {
+                sleep(Duration::from_millis(100)).await;
+                Ok(x + 1)
+            });
+
+        assert!(timeout(Duration::from_millis(10), effect.run())
+            .await
+            .is_err());
+        assert!(!cancelled.load(Ordering::SeqCst));
+    }
+
     #[tokio::test]
     async fn it_propagates_errors_in_async_calls() {
         let effect = Effect::of(0).with_io(|_| async move {
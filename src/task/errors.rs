@@ -1,3 +1,4 @@
+use std::time::Duration;
 use thiserror::Error;
 
 use crate::errors::{ExtractionError, IOError, MethodError};
@@ -20,9 +21,57 @@ pub enum Error {
     /// The task condition was not met
     ConditionFailed,
 
+    #[error("target unreachable: {0}")]
+    /// The handler determined that the target cannot be reached from the
+    /// current state. Differently from [`Error::ConditionFailed`], returning this
+    /// error during planning stops the search instead of discarding the task
+    Unreachable(String),
+
+    #[error("task attempted to modify {0} outside of its assigned path")]
+    /// A scoped task, or a task declaring the paths it writes to, returned or
+    /// reported changes to a path outside of the paths it is allowed to modify.
+    /// This is likely an issue with the job definition
+    OutOfScope(String),
+
+    #[error("task panicked: {0}")]
+    /// The task handler panicked.
+    /// This is likely a bug in the handler code
+    Panicked(String),
+
+    #[error("task timed out after {0:?}")]
+    /// The task did not terminate within the time allowed by the worker.
+    /// These errors only happen at runtime.
+    Timeout(Duration),
+
     #[error(transparent)]
     /// An error happened while executing the task within the workflow.
     /// These errors only happen at runtime, never at the planning stage
     /// of the worker.
     IO(#[from] IOError),
 }
+
+impl Error {
+    /// Get the source of a runtime (IO) error as a concrete type
+    ///
+    /// Returns `None` if the error is not an IO error or if the source error
+    /// is not of type `E`.
+    ///
+    /// ```rust
+    /// use mahler::errors::IOError;
+    /// use mahler::task::Error;
+    ///
+    /// #[derive(Debug, thiserror::Error)]
+    /// #[error("connection failed")]
+    /// struct ConnectionError;
+    ///
+    /// let err = Error::from(IOError::new(ConnectionError));
+    /// assert!(err.runtime_source::<ConnectionError>().is_some());
+    /// assert!(Error::ConditionFailed.runtime_source::<ConnectionError>().is_none());
+    /// ```
+    pub fn runtime_source<E: std::error::Error + 'static>(&self) -> Option<&E> {
+        match self {
+            Error::IO(err) => err.downcast_ref::<E>(),
+            _ => None,
+        }
+    }
+}
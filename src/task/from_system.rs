@@ -6,6 +6,51 @@ use crate::task::Context;
 /// Trait for types that can be initialized from a system state and a given context
 ///
 /// See [`crate::extract`] for more info.
+///
+/// # Example
+///
+/// Custom extractors can read the system state and the task [`Context`]. The following
+/// extractor reads the `max` value next to the task path. As it reads outside of the task path,
+/// the extractor is not scoped, which prevents tasks using it from running concurrently with
+/// tasks modifying the `max` value.
+///
+/// ```rust
+/// use anyhow::anyhow;
+/// use serde_json::json;
+/// use mahler::System;
+/// use mahler::errors::ExtractionError;
+/// use mahler::task::{Context, FromSystem};
+///
+/// struct Max(i64);
+///
+/// impl FromSystem for Max {
+///     type Error = ExtractionError;
+///
+///     fn from_system(system: &System, context: &Context) -> Result<Self, Self::Error> {
+///         let path = context.path();
+///         let parent = &path[..path.rfind('/').unwrap_or(0)];
+///
+///         let max = system
+///             .root()
+///             .pointer(&format!("{parent}/max"))
+///             .and_then(|value| value.as_i64())
+///             .ok_or_else(|| anyhow!("no max value found for {path}"))?;
+///
+///         Ok(Max(max))
+///     }
+///
+///     fn is_scoped() -> bool {
+///         false
+///     }
+/// }
+///
+/// let system = System::try_from(json!({"counter": {"value": 0, "max": 10}})).unwrap();
+/// let context = Context::new().with_path("/counter/value");
+///
+/// let Max(max) = Max::from_system(&system, &context).unwrap();
+/// assert_eq!(max, 10);
+/// assert!(!Max::is_scoped());
+/// ```
 pub trait FromSystem: Sized {
     type Error: Into<Error> + 'static;
 
@@ -23,3 +68,45 @@ pub trait FromSystem: Sized {
         true
     }
 }
+
+/// Trait for extractors that may borrow from the system state
+///
+/// This is the trait used by [handlers](`crate::task::Handler`) to initialize their arguments.
+/// It is implemented for every [`FromSystem`] type, and it allows extractors such as
+/// [`ViewRef`](`crate::extract::ViewRef`) to keep a reference to the system for the duration of
+/// the handler call instead of copying the data they need.
+///
+/// Implementors are a marker type for the extractor, while [`FromSystemRef::Output`] is the
+/// value given to the handler, borrowing from the system state with lifetime `'a`.
+pub trait FromSystemRef {
+    type Error: Into<Error> + 'static;
+
+    /// The extractor type, borrowing from the system state
+    type Output<'a>;
+
+    /// Try to initialize the extractor from a borrowed system state and context
+    fn from_system_ref<'a>(
+        state: &'a System,
+        context: &Context,
+    ) -> Result<Self::Output<'a>, Self::Error>;
+
+    /// Return true if the extractor is scoped
+    ///
+    /// See [`FromSystem::is_scoped`]
+    fn is_scoped() -> bool {
+        true
+    }
+}
+
+impl<T: FromSystem> FromSystemRef for T {
+    type Error = T::Error;
+    type Output<'a> = T;
+
+    fn from_system_ref(state: &System, context: &Context) -> Result<T, T::Error> {
+        T::from_system(state, context)
+    }
+
+    fn is_scoped() -> bool {
+        <T as FromSystem>::is_scoped()
+    }
+}
@@ -1,8 +1,8 @@
 use json_patch::Patch;
 use serde::Serialize;
 
-use super::from_system::FromSystem;
-use super::{Action, Context, Effect, Error, Method, Task};
+use super::from_system::FromSystemRef;
+use super::{Action, Context, Effect, Error, IntoEffect, Method, Task};
 use crate::system::System;
 
 /// Trait for functions that can be used as worker jobs
@@ -19,7 +19,8 @@ pub trait Handler<T, O, I = O>: Clone + Sync + Send + 'static {
 
     /// Get the unique identifier of the job handler
     ///
-    /// The identifier is given by the type name of `Self`
+    /// The identifier is given by the type name of `Self`. Closures do not have a unique type
+    /// name, use [`Job::with_id`](`super::Job::with_id`) to give them a stable identifier.
     ///
     /// This is used accross the library to search for tasks for tracing purposes and human
     /// readable logs.
@@ -34,7 +35,7 @@ pub trait Handler<T, O, I = O>: Clone + Sync + Send + 'static {
     /// The scoping of the handler is determined from the extractor. If all extractors are scoped,
     /// then the handler is scoped.
     ///
-    /// See [`FromSystem::is_scoped`]
+    /// See [`FromSystem::is_scoped`](`super::FromSystem::is_scoped`)
     fn is_scoped(&self) -> bool;
 
     /// Create a task from the handler using the default context
@@ -79,6 +80,22 @@ pub trait Handler<T, O, I = O>: Clone + Sync + Send + 'static {
     fn with_arg(self, key: impl AsRef<str>, value: impl Into<String>) -> Task {
         self.into_task().with_arg(key, value)
     }
+
+    /// Create a task from the handler with a path argument that overrides the parent argument
+    ///
+    /// This is a convenience method that is equivalent to calling
+    /// `handler.into_task().with_arg_override()`. See [`Task::with_arg_override`] for details.
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    ///
+    /// fn foo() {}
+    ///
+    /// let task = foo.with_arg_override("foo", "123");
+    /// ```
+    fn with_arg_override(self, key: impl AsRef<str>, value: impl Into<String>) -> Task {
+        self.into_task().with_arg_override(key, value)
+    }
 }
 
 macro_rules! impl_action_handler {
@@ -88,15 +105,20 @@ macro_rules! impl_action_handler {
         #[allow(non_snake_case, unused)]
         impl<F, $($ty,)* Res, I> Handler<($($ty,)*), Patch, I> for F
         where
-            F: Fn($($ty,)*) -> Res + Clone + Send + Sync +'static,
-            Res: Into<Effect<Patch, Error, I>> + Send,
-            $($ty: FromSystem,)*
+            F: Fn($($ty,)*) -> Res
+                + for<'a> Fn($(<$ty as FromSystemRef>::Output<'a>,)*) -> Res
+                + Clone
+                + Send
+                + Sync
+                + 'static,
+            Res: IntoEffect<I> + Send,
+            $($ty: FromSystemRef,)*
             I: Send + 'static
         {
 
             fn call(&self, system: &System, context: &Context) -> Effect<Patch, Error, I>{
                 $(
-                    let $ty = match $ty::from_system(system, context) {
+                    let $ty = match $ty::from_system_ref(system, context) {
                         Ok(value) => value,
                         Err(failure) => {
                             return Effect::from_error(failure.into())
@@ -104,15 +126,20 @@ macro_rules! impl_action_handler {
                     };
                 )*
 
-                let res = (self)($($ty,)*);
+                // Call the handler with the extractors borrowing from the system, this
+                // disambiguates between the two `Fn` bounds of `F`
+                fn call_inner<Res, $($ty,)*>(f: impl Fn($($ty,)*) -> Res, ($($ty,)*): ($($ty,)*)) -> Res {
+                    f($($ty,)*)
+                }
+                let res = call_inner(self, ($($ty,)*));
 
                 // Convert to effect
-                res.into()
+                res.into_effect(system, context)
             }
 
             fn is_scoped(&self) -> bool {
                 // The handler is scoped if all of its arguments are scoped
-                true $(&& $ty::is_scoped())*
+                true $(&& <$ty as FromSystemRef>::is_scoped())*
             }
 
             fn into_task(self) -> Task {
@@ -146,14 +173,19 @@ macro_rules! impl_method_handler {
         #[allow(non_snake_case, unused)]
         impl<F, $($ty,)* Res> Handler<($($ty,)*), Vec<Task>> for F
         where
-            F: Fn($($ty,)*) -> Res + Clone + Send + Sync +'static,
+            F: Fn($($ty,)*) -> Res
+                + for<'a> Fn($(<$ty as FromSystemRef>::Output<'a>,)*) -> Res
+                + Clone
+                + Send
+                + Sync
+                + 'static,
             Res: Into<Effect<Vec<Task>, Error>>,
-            $($ty: FromSystem,)*
+            $($ty: FromSystemRef,)*
         {
 
             fn call(&self, system: &System, context: &Context) -> Effect<Vec<Task>, Error> {
                 $(
-                    let $ty = match $ty::from_system(system, context) {
+                    let $ty = match $ty::from_system_ref(system, context) {
                         Ok(value) => value,
                         Err(failure) => {
                             return Effect::from_error(failure.into())
@@ -161,7 +193,12 @@ macro_rules! impl_method_handler {
                     };
                 )*
 
-                let res = (self)($($ty,)*);
+                // Call the handler with the extractors borrowing from the system, this
+                // disambiguates between the two `Fn` bounds of `F`
+                fn call_inner<Res, $($ty,)*>(f: impl Fn($($ty,)*) -> Res, ($($ty,)*): ($($ty,)*)) -> Res {
+                    f($($ty,)*)
+                }
+                let res = call_inner(self, ($($ty,)*));
 
                 // Convert to effect
                 res.into()
@@ -169,7 +206,7 @@ macro_rules! impl_method_handler {
 
             fn is_scoped(&self) -> bool {
                 // The handler is scoped if all of its arguments are scoped
-                true $(&& $ty::is_scoped())*
+                true $(&& <$ty as FromSystemRef>::is_scoped())*
             }
 
             fn into_task(self) -> Task {
@@ -1,10 +1,31 @@
 use json_patch::Patch;
+use serde::Serialize;
+use serde_json::Value;
+use std::error::Error as StdError;
 
-use super::Task;
+use super::{Context, Task};
 
-use super::effect::Effect;
+use super::effect::{Assign, Effect};
 use super::errors::Error;
 use crate::errors::{IOError, MethodError};
+use crate::extract::Pointer;
+use crate::system::System;
+
+/// Convert an error returned by a handler into a task error
+///
+/// Handlers may return [`Error::Unreachable`] to stop planning, any other error
+/// is converted using the given function
+fn handler_error<E>(e: E, wrap: impl FnOnce(Box<dyn StdError + Send + Sync>) -> Error) -> Error
+where
+    E: StdError + Send + Sync + 'static,
+{
+    let err: Box<dyn StdError + Send + Sync> = Box::new(e);
+    match err.downcast::<Error>() {
+        Ok(err) if matches!(*err, Error::Unreachable(_)) => *err,
+        Ok(err) => wrap(err),
+        Err(err) => wrap(err),
+    }
+}
 
 pub trait IntoResult<O> {
     fn into_result(self) -> Result<O, Error>;
@@ -21,6 +42,36 @@ where
     }
 }
 
+macro_rules! impl_into_result_for_tuple {
+    ($($ty:ident),+) => {
+        /// Allow tasks to return a tuple of values implementing `IntoResult<Patch>`,
+        /// the resulting patch is the concatenation of the individual patches
+        #[allow(non_snake_case)]
+        impl<$($ty,)+> IntoResult<Patch> for ($($ty,)+)
+        where
+            $($ty: IntoResult<Patch>,)+
+        {
+            fn into_result(self) -> Result<Patch, Error> {
+                let ($($ty,)+) = self;
+                let mut changes = Vec::new();
+                $(
+                    let Patch(ops) = $ty.into_result()?;
+                    changes.extend(ops);
+                )+
+                Ok(Patch(changes))
+            }
+        }
+    };
+}
+
+impl_into_result_for_tuple!(T1, T2);
+impl_into_result_for_tuple!(T1, T2, T3);
+impl_into_result_for_tuple!(T1, T2, T3, T4);
+impl_into_result_for_tuple!(T1, T2, T3, T4, T5);
+impl_into_result_for_tuple!(T1, T2, T3, T4, T5, T6);
+impl_into_result_for_tuple!(T1, T2, T3, T4, T5, T6, T7);
+impl_into_result_for_tuple!(T1, T2, T3, T4, T5, T6, T7, T8);
+
 impl From<()> for Effect<Patch, Error, ()> {
     fn from(value: ()) -> Effect<Patch, Error, ()> {
         Effect::of(value)
@@ -29,6 +80,18 @@ impl From<()> for Effect<Patch, Error, ()> {
     }
 }
 
+/// Allow tasks to return an effect that performs IO but
+/// produces no changes, e.g. [`Effect::side_effect`]
+impl<E> From<Effect<(), E>> for Effect<Patch, Error, ()>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    fn from(eff: Effect<(), E>) -> Effect<Patch, Error, ()> {
+        eff.map_err(|e| handler_error(e, |e| IOError::from(e).into()))
+            .map(|_| Patch(vec![]))
+    }
+}
+
 /// Allow tasks to return a value that implements
 /// `IntoResult<Patch>`, e.g. View
 impl<I> From<I> for Effect<Patch, Error, I>
@@ -49,11 +112,70 @@ where
     E: std::error::Error + Send + Sync + 'static,
 {
     fn from(eff: Effect<I, E>) -> Effect<Patch, Error, I> {
-        eff.map_err(|e| IOError::new(e).into())
+        eff.map_err(|e| handler_error(e, |e| IOError::from(e).into()))
             .and_then(move |o| o.into_result())
     }
 }
 
+/// Allow actions to return a `Result` of a value that can be converted into an effect. The
+/// error is converted into an [`IOError`], allowing synchronous actions to fail without
+/// having to construct an effect
+impl<R, E, I> From<Result<R, E>> for Effect<Patch, Error, I>
+where
+    R: Into<Effect<Patch, Error, I>>,
+    E: std::error::Error + Send + Sync + 'static,
+    I: Send + 'static,
+{
+    fn from(res: Result<R, E>) -> Effect<Patch, Error, I> {
+        res.map(|r| r.into())
+            .unwrap_or_else(|e| Effect::from_error(handler_error(e, |e| IOError::from(e).into())))
+    }
+}
+
+/// Trait for action return values that can be converted into an effect on the system
+///
+/// Differently from `Into<Effect>`, the conversion has access to the system and the task
+/// context, which is needed to know where the changes should be written.
+pub trait IntoEffect<I> {
+    fn into_effect(self, system: &System, context: &Context) -> Effect<Patch, Error, I>;
+}
+
+impl<T, I> IntoEffect<I> for T
+where
+    T: Into<Effect<Patch, Error, I>>,
+{
+    fn into_effect(self, _: &System, _: &Context) -> Effect<Patch, Error, I> {
+        self.into()
+    }
+}
+
+/// Allow tasks to return a patch. The changes are applied to the system as-is
+impl IntoEffect<Patch> for Patch {
+    fn into_effect(self, _: &System, _: &Context) -> Effect<Patch, Error, Patch> {
+        Effect::of(self)
+    }
+}
+
+/// Allow tasks to return an effect created with [`Effect::from_value`]. The value
+/// is written to the task path
+impl<T, E> IntoEffect<Assign<T>> for Effect<Assign<T>, E>
+where
+    T: Serialize + Send + 'static,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    fn into_effect(self, system: &System, context: &Context) -> Effect<Patch, Error, Assign<T>> {
+        let initial = context
+            .path
+            .as_ref()
+            .resolve(system.root())
+            .cloned()
+            .unwrap_or(Value::Null);
+        let path = context.path.clone();
+        self.map_err(|e| handler_error(e, |e| IOError::from(e).into()))
+            .and_then(move |Assign(value)| Pointer::new(initial, Some(value), path).into_result())
+    }
+}
+
 // Allow tasks to return a pure `Vec<Task>`
 // and this will convert them into an effect
 impl From<Vec<Task>> for Effect<Vec<Task>, Error> {
@@ -93,7 +215,8 @@ where
     E: std::error::Error + Send + Sync + 'static,
 {
     fn from(res: Result<T, E>) -> Effect<Vec<Task>, Error> {
-        res.map(|t| t.into())
-            .unwrap_or_else(|e| Effect::from_error(MethodError::new(e).into()))
+        res.map(|t| t.into()).unwrap_or_else(|e| {
+            Effect::from_error(handler_error(e, |e| MethodError::from(e).into()))
+        })
     }
 }
@@ -1,15 +1,24 @@
 use super::context::Context;
-use super::description::Description;
+use super::description::{Description, SystemDescription};
 use super::handler::Handler;
 use super::Task;
+use crate::system::System;
+use serde_json::Value;
 use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+type Predicate = Arc<dyn Fn(&Value, &Value) -> bool + Send + Sync>;
 
 #[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone)]
 /// The operation a Job is applicable to
 pub enum Operation {
     /// Tells the `Worker` the job is not to be automatically selected for any operation
     ///
-    /// `None` jobs can still be used as part of compound tasks
+    /// `None` jobs are never selected by the planner to reduce the distance to the target, even
+    /// if the job route matches a changed path. They can still be used as part of compound tasks,
+    /// i.e. returned by a method.
     None,
     /// Tells the `Worker` the job is assignable to any operation
     Any,
@@ -30,11 +39,26 @@ pub enum Operation {
 /// may define a specific priority. During planning, the operation is used to determine the
 /// applicability of a job to a state change, and the priority it is used (amongst other factors) to
 /// decide what jobs will be tried first.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Job {
     operation: Operation,
     task: Task,
     priority: u8,
+    group: Option<&'static str>,
+    fallback: bool,
+    predicate: Option<Predicate>,
+}
+
+impl fmt::Debug for Job {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Job")
+            .field("operation", &self.operation)
+            .field("task", &self.task)
+            .field("priority", &self.priority)
+            .field("group", &self.group)
+            .field("fallback", &self.fallback)
+            .finish()
+    }
 }
 
 impl Job {
@@ -44,12 +68,16 @@ impl Job {
             task,
             // all tasks have the lowest priority
             priority: 0,
+            group: None,
+            fallback: false,
+            predicate: None,
         }
     }
 
     /// Get the unique identifier for the job
     ///
-    /// The id is determined from the [`Handler`] type name
+    /// The id is determined from the [`Handler`] type name, unless
+    /// overridden with [`Job::with_id`]
     pub fn id(&self) -> &str {
         self.task.id()
     }
@@ -64,6 +92,11 @@ impl Job {
         self.priority
     }
 
+    /// Return true if the job handler is a method
+    pub(crate) fn is_method(&self) -> bool {
+        matches!(self.task, Task::Method(_))
+    }
+
     /// Set job priority.
     ///
     /// This defines search priority when looking for jobs
@@ -73,6 +106,246 @@ impl Job {
         self
     }
 
+    /// Return true if the job is only tried when no other job is applicable
+    pub fn is_fallback(&self) -> bool {
+        self.fallback
+    }
+
+    /// Mark the job as a fallback
+    ///
+    /// During planning, a fallback job is only tried for a path if no other job with a matching
+    /// operation is applicable to it, independently of the job priority. This allows to define a
+    /// catch-all job for a route.
+    ///
+    /// ```rust
+    /// use mahler::task::{Job, update};
+    ///
+    /// fn update_in_place() {}
+    /// fn reinstall() {}
+    ///
+    /// let jobs = [update(update_in_place), update(reinstall).fallback()];
+    /// assert!(jobs[1].is_fallback());
+    /// ```
+    pub fn fallback(mut self) -> Self {
+        self.fallback = true;
+        self
+    }
+
+    /// Get the group the job belongs to, if any
+    pub fn group(&self) -> Option<&'static str> {
+        self.group
+    }
+
+    /// Assign the job to a group of mutually exclusive jobs
+    ///
+    /// Jobs in the same group are alternatives for the same operation. When searching for a
+    /// workflow, once a job of the group has been selected, the planner will not select a
+    /// different job from the group within that workflow.
+    ///
+    /// ```rust
+    /// use mahler::task::{Job, create};
+    ///
+    /// fn install_from_registry() {}
+    /// fn install_from_cache() {}
+    ///
+    /// let jobs = [
+    ///     create(install_from_registry).with_group("install"),
+    ///     create(install_from_cache).with_group("install"),
+    /// ];
+    /// assert_eq!(jobs[0].group(), Some("install"));
+    /// ```
+    pub fn with_group(mut self, group: &'static str) -> Self {
+        self.group = Some(group);
+        self
+    }
+
+    /// Override the job id
+    ///
+    /// By default, the job id is the [`Handler`] type name, which means a handler can only be
+    /// registered once in a [Worker](`crate::worker::Worker`). Overriding the id allows to
+    /// register the same handler under multiple routes.
+    ///
+    /// Note that the id is not carried to tasks created from the handler. A task returned by a
+    /// method (e.g. `foo.with_target(1)`) keeps using the handler type name as id, so it will
+    /// not be matched to this job unless the method sets the same id with [`Task::with_id`].
+    ///
+    /// ```rust
+    /// use mahler::task::{Job, update};
+    ///
+    /// fn foo() {}
+    ///
+    /// let job = update(foo).with_id("foo_a");
+    /// assert_eq!(job.id(), "foo_a");
+    /// ```
+    ///
+    /// # Closures
+    ///
+    /// The type name of a closure is not unique (e.g. `my_crate::my_fn::{{closure}}`) and it
+    /// may change between compiler versions, so using closures as handlers without an explicit
+    /// id is discouraged. Job ids are used to find the route of a task and to detect loops
+    /// during planning, so closures sharing the same id may lead to unexpected plans.
+    ///
+    /// ```rust
+    /// use mahler::extract::View;
+    /// use mahler::task::{Job, update};
+    ///
+    /// let job = update(|counter: View<i32>| counter).with_id("counter_noop");
+    /// assert_eq!(job.id(), "counter_noop");
+    /// ```
+    pub fn with_id(mut self, id: &'static str) -> Self {
+        self.task = self.task.with_id(id);
+        self
+    }
+
+    /// Allow tasks created from the job to produce no changes to the system state
+    ///
+    /// By default, the planner discards tasks that produce no changes, as it means the task is
+    /// not applicable to the current state. Use this for jobs that only perform side effects (e.g.
+    /// sending a notification) and that are meant to be called as part of a method.
+    ///
+    /// ```rust
+    /// use mahler::task::{Effect, Job, none};
+    ///
+    /// fn notify() -> Effect<()> {
+    ///     Effect::side_effect(|| async {
+    ///         // send the notification
+    ///         Ok(())
+    ///     })
+    /// }
+    ///
+    /// let job = none(notify).allow_empty_changes();
+    /// ```
+    pub fn allow_empty_changes(mut self) -> Self {
+        self.task = self.task.allow_empty_changes();
+        self
+    }
+
+    /// Set a deadline after which the job is no longer applicable
+    ///
+    /// Tasks created from the job are treated as if their condition failed once the deadline
+    /// passes, meaning the planner will not select the job and, if the deadline passes after
+    /// planning, the Worker will not execute the task, triggering a re-plan instead.
+    ///
+    /// ```rust
+    /// use std::time::{Duration, Instant};
+    /// use mahler::task::{Job, update};
+    ///
+    /// fn foo() {}
+    ///
+    /// // The job can only be used in the next 10 minutes
+    /// let job = update(foo).with_deadline(Instant::now() + Duration::from_secs(600));
+    /// ```
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.task = self.task.with_deadline(deadline);
+        self
+    }
+
+    /// Set a precondition that must hold for the job to be applicable
+    ///
+    /// The condition is evaluated on the system state and the task context before the handler
+    /// is called. If the condition does not hold, tasks created from the job fail with
+    /// [`Error::ConditionFailed`](`super::Error::ConditionFailed`), meaning the planner will not
+    /// select the job and, if the condition stops holding after planning, the Worker will not
+    /// execute the task, triggering a re-plan instead.
+    ///
+    /// ```rust
+    /// use mahler::task::{Job, update};
+    ///
+    /// fn foo() {}
+    ///
+    /// // The job is only applicable if the system is not in maintenance mode
+    /// let job = update(foo).require(|system, _| system.root()["maintenance"] != true);
+    /// ```
+    pub fn require<F>(mut self, condition: F) -> Self
+    where
+        F: Fn(&System, &Context) -> bool + Send + Sync + 'static,
+    {
+        self.task = self.task.with_condition(condition);
+        self
+    }
+
+    /// Only consider the job during planning if the predicate holds
+    ///
+    /// The predicate receives the current and target values at the path matched by the job,
+    /// with `null` used for a value that does not exist. Unlike [`require`](Self::require),
+    /// the predicate is evaluated by the planner before creating a task from the job, so no
+    /// arguments are extracted and the handler is never called if the predicate does not
+    /// hold. The predicate is not evaluated at runtime.
+    ///
+    /// ```rust
+    /// use mahler::task::{Job, update};
+    ///
+    /// fn scale_up() {}
+    ///
+    /// // Only try scaling up if the target has more replicas
+    /// let job = update(scale_up).when(|cur, tgt| tgt.as_u64() > cur.as_u64());
+    /// ```
+    pub fn when<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Value, &Value) -> bool + Send + Sync + 'static,
+    {
+        self.predicate = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Declare the paths of the system state read by the job handler
+    ///
+    /// By default, a handler using the [System](`crate::extract::System`) extractor may read any
+    /// part of the state, so tasks created from the job are never run in parallel with other
+    /// tasks. Declaring the paths the handler depends on allows the planner to run the tasks in
+    /// parallel with tasks that do not write to those paths. Paths may use the placeholders of
+    /// the job route, which are replaced by the task arguments.
+    ///
+    /// The declaration is not enforced, reading other parts of the state may lead to race
+    /// conditions at runtime.
+    ///
+    /// ```rust
+    /// use serde_json::Value;
+    /// use mahler::extract::{System, View};
+    /// use mahler::task::{Job, update};
+    ///
+    /// fn configure(item: View<String>, System(state): System<Value>) -> View<String> {
+    ///     // read the global configuration
+    ///     item
+    /// }
+    ///
+    /// let job = update(configure).reads(&["/config"]).writes(&["/items/{id}"]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any of the paths is not a valid route
+    pub fn reads(mut self, paths: &[&'static str]) -> Self {
+        paths.iter().for_each(|path| check_route(path));
+        self.task = self.task.with_reads(paths);
+        self
+    }
+
+    /// Declare the paths of the system state written by the job handler
+    ///
+    /// By default, tasks created from the job write to the path assigned to the task. See
+    /// [`reads`](Self::reads) for more information.
+    ///
+    /// Differently from reads, the declaration is enforced. A task returning or reporting
+    /// changes outside of the declared paths fails with
+    /// [`Error::OutOfScope`](`super::Error::OutOfScope`).
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any of the paths is not a valid route
+    pub fn writes(mut self, paths: &[&'static str]) -> Self {
+        paths.iter().for_each(|path| check_route(path));
+        self.task = self.task.with_writes(paths);
+        self
+    }
+
+    /// Return true if the job should be considered for the given current and target values
+    pub(crate) fn applies_to(&self, current: &Value, target: &Value) -> bool {
+        self.predicate
+            .as_ref()
+            .is_none_or(|predicate| predicate(current, target))
+    }
+
     /// Set the job operation
     ///
     /// This is for internal use only. Users can set the operation by using the constructor
@@ -108,6 +381,31 @@ impl Job {
         self
     }
 
+    /// Set a human readable description for the Job that can read the system state
+    ///
+    /// A [system description](`SystemDescription`) is any function that accepts zero or more
+    /// [extractors](`crate::extract`) and returns a String. Unlike
+    /// [`with_description`](Self::with_description), the description is rendered against
+    /// the system state using [`Task::describe_with`], so it can refer to the current
+    /// value at the task path. When the task is executed by the Worker, the description
+    /// is rendered against the system state at the time the task runs.
+    ///
+    /// ```rust
+    /// use mahler::task::{Job, update};
+    /// use mahler::extract::View;
+    ///
+    /// fn foo() {}
+    ///
+    /// let job = update(foo).with_system_description(|counter: View<i32>| format!("counter at {}", *counter));
+    /// ```
+    pub fn with_system_description<D, T>(mut self, description: D) -> Self
+    where
+        D: SystemDescription<T>,
+    {
+        self.task = self.task.with_system_description(description);
+        self
+    }
+
     /// Create a new task from the Job and the given Context
     pub(crate) fn new_task(&self, context: Context) -> Task {
         self.task.clone().with_context(context)
@@ -115,8 +413,9 @@ impl Job {
 }
 
 macro_rules! define_job {
-    ($func_name:ident, $operation:expr) => {
+    ($func_name:ident, $operation:expr $(, $doc:literal)*) => {
         #[doc = concat!("Create a new `Job` for the [`", stringify!($operation), "`] operation.")]
+        $(#[doc = $doc])*
         pub fn $func_name<H, T, O, I>(handler: H) -> Job
         where
             H: Handler<T, O, I>,
@@ -131,7 +430,106 @@ define_job!(create, Operation::Create);
 define_job!(update, Operation::Update);
 define_job!(delete, Operation::Delete);
 define_job!(any, Operation::Any);
-define_job!(none, Operation::None);
+define_job!(
+    none,
+    Operation::None,
+    "",
+    "The job will not be selected by the planner on its own, but it can be used as part of",
+    "a method."
+);
+
+/// Check that a route is well formed, panicking otherwise
+///
+/// A route is well formed if it is empty or starts with `/` and every
+/// parameter is enclosed in balanced, non empty braces. Literal braces may be
+/// escaped as `{{` and `}}`.
+///
+/// This is used by the [`job!`](`crate::job`) macro to validate routes at compile time
+#[doc(hidden)]
+pub const fn check_route(route: &str) {
+    let bytes = route.as_bytes();
+    if !bytes.is_empty() && bytes[0] != b'/' {
+        panic!("route must be empty or start with '/'");
+    }
+
+    let mut in_param = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = i + 1 < bytes.len() && bytes[i + 1] == bytes[i];
+        match bytes[i] {
+            b'{' if !in_param && escaped => i += 1,
+            b'}' if !in_param && escaped => i += 1,
+            b'{' if in_param => panic!("route parameters cannot be nested"),
+            b'{' if i + 1 < bytes.len() && bytes[i + 1] == b'}' => {
+                panic!("route parameters cannot be empty")
+            }
+            b'{' => in_param = true,
+            b'}' if !in_param => panic!("unbalanced '}}' in route"),
+            b'}' => in_param = false,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    if in_param {
+        panic!("unbalanced '{{' in route");
+    }
+}
+
+/// Pair a route with a [Job](`crate::task::Job`), validating the route at compile time
+///
+/// The macro returns a `(route, job)` tuple that can be added to a worker via
+/// [`Worker::register_job`](`crate::worker::Worker::register_job`). While the macro
+/// cannot check that the route matches the state model, it rejects routes that do not
+/// start with `/` or have unbalanced braces.
+///
+/// ```rust
+/// use mahler::job;
+/// use mahler::task::prelude::*;
+/// use mahler::worker::{Worker, Uninitialized};
+///
+/// fn plus_one() {}
+///
+/// let worker: Worker<i32, Uninitialized> =
+///     Worker::new().register_job(job!("/counters/{counter}", update(plus_one)));
+/// ```
+///
+/// Malformed routes fail to compile
+///
+/// ```rust,compile_fail,E0080
+/// use mahler::job;
+/// use mahler::task::prelude::*;
+///
+/// fn plus_one() {}
+///
+/// let (route, job) = job!("/counters/{counter", update(plus_one));
+/// ```
+///
+/// ```rust,compile_fail,E0080
+/// use mahler::job;
+/// use mahler::task::prelude::*;
+///
+/// fn plus_one() {}
+///
+/// let (route, job) = job!("counters/{counter}", update(plus_one));
+/// ```
+#[macro_export]
+macro_rules! job {
+    ($route:literal, $job:expr) => {{
+        const _: () = $crate::task::check_route($route);
+        ($route, $job)
+    }};
+}
+
+/// Create a job from a task
+///
+/// The job is applicable to the [`Operation::Update`] operation. This is mostly useful to
+/// register built-in tasks like [`noop`](`super::noop`) for a route.
+impl From<Task> for Job {
+    fn from(task: Task) -> Self {
+        Job::new(task)
+    }
+}
 
 impl PartialEq for Job {
     fn eq(&self, other: &Self) -> bool {
@@ -153,3 +551,41 @@ impl Ord for Job {
         self.task.id().cmp(other.task.id())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_accepts_well_formed_routes() {
+        check_route("");
+        check_route("/");
+        check_route("/counters/{counter}");
+        check_route("/a/{b}/c/{*rest}");
+        check_route("/a/{{literal}}");
+    }
+
+    #[test]
+    #[should_panic(expected = "route must be empty or start with '/'")]
+    fn it_rejects_routes_without_leading_slash() {
+        check_route("counters/{counter}");
+    }
+
+    #[test]
+    #[should_panic(expected = "unbalanced '{' in route")]
+    fn it_rejects_routes_with_unclosed_braces() {
+        check_route("/counters/{counter");
+    }
+
+    #[test]
+    #[should_panic(expected = "unbalanced '}' in route")]
+    fn it_rejects_routes_with_unopened_braces() {
+        check_route("/counters/counter}");
+    }
+
+    #[test]
+    #[should_panic(expected = "route parameters cannot be nested")]
+    fn it_rejects_routes_with_nested_braces() {
+        check_route("/counters/{a{b}}");
+    }
+}
@@ -1,4 +1,5 @@
 //! Types and traits for declaring and operating with Jobs and Tasks
+mod builtin;
 mod context;
 mod description;
 mod effect;
@@ -9,22 +10,28 @@ mod into_result;
 mod job;
 mod with_io;
 
-use json_patch::Patch;
+use futures::FutureExt;
+use json_patch::{CopyOperation, MoveOperation, Patch, PatchOperation};
+use jsonptr::Pointer;
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::fmt::{self, Display};
 use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tracing::warn;
 
 use crate::errors::SerializationError;
 use crate::path::Path;
 use crate::system::System;
 
-pub(crate) use context::*;
+pub(crate) use builtin::builtin;
 pub(crate) use into_result::*;
 
-pub use context::FromContext;
+pub use builtin::{noop, unreachable};
+pub use context::{Context, FromContext};
 pub use description::*;
 pub use effect::*;
 pub use errors::*;
@@ -43,19 +50,57 @@ pub mod prelude {
 
 type ActionOutput = Pin<Box<dyn Future<Output = Result<Patch, Error>> + Send>>;
 type DryRun = Arc<dyn Fn(&System, &Context) -> Result<Patch, Error> + Send + Sync>;
-type Run = Arc<dyn Fn(&System, &Context) -> ActionOutput + Send + Sync>;
+type Run = Arc<dyn Fn(&System, &Context, ReportChanges) -> ActionOutput + Send + Sync>;
 type Expand = Arc<dyn Fn(&System, &Context) -> Result<Vec<Task>, Error> + Send + Sync>;
 type Describe = Arc<dyn Fn(&Context) -> Result<String, Error> + Send + Sync>;
+type DescribeWith = Arc<dyn Fn(&System, &Context) -> Result<String, Error> + Send + Sync>;
+type Condition = Arc<dyn Fn(&System, &Context) -> bool + Send + Sync>;
+pub(crate) type ReportChanges = Box<dyn FnMut(Patch) + Send>;
+
+// Paths read and written by a task, declared as route templates
+// using the placeholders of the job route
+#[derive(Clone, Default)]
+struct Access {
+    reads: Option<Vec<&'static str>>,
+    writes: Option<Vec<&'static str>>,
+}
+
+// A task is scoped if its extractors are scoped or it declares the paths it reads
+fn is_scoped(scoped: bool, access: &Access) -> bool {
+    scoped || access.reads.is_some()
+}
+
+// Expand the route templates using the task arguments. Templates that cannot be
+// expanded into a valid path are replaced by the root path, which conflicts with any
+// other path
+fn expand_paths(templates: &[&'static str], context: &Context) -> Vec<Path> {
+    templates
+        .iter()
+        .map(|template| {
+            context
+                .args
+                .expand(template)
+                .ok()
+                .and_then(|(path, _)| Pointer::parse(&path).ok().map(Path::new))
+                .unwrap_or_default()
+        })
+        .collect()
+}
 
 #[derive(Clone)]
 /// An atomic task
 pub struct Action {
     id: &'static str,
     scoped: bool,
+    access: Access,
+    allow_empty: bool,
+    deadline: Option<Instant>,
+    condition: Option<Condition>,
     context: Context,
     dry_run: DryRun,
     run: Run,
-    describe: Describe,
+    describe: Option<Describe>,
+    describe_with: Option<DescribeWith>,
 }
 
 impl PartialEq for Action {
@@ -76,8 +121,66 @@ impl fmt::Debug for Action {
     }
 }
 
+/// Default description of a task, including the task target, if any
 fn default_description(id: &'static str, ctx: &Context) -> String {
-    format!("{}({})", id, ctx.path)
+    if ctx.target.is_null() {
+        return format!("{}({})", id, ctx.path);
+    }
+    format!("{}({}) -> {}", id, ctx.path, ctx.target)
+}
+
+/// Check that all changes in the patch are within the given scope
+///
+/// Every path modified by the patch, including the source of `move` operations, must be
+/// under one of the `writes` paths, and the source of `copy` operations must be under one
+/// of the `reads` paths
+fn check_scope(Patch(changes): &Patch, writes: &[Path], reads: &[Path]) -> Result<(), Error> {
+    let within = |paths: &[Path], path: &Pointer| {
+        paths
+            .iter()
+            .any(|allowed| path.starts_with(allowed.as_ref()))
+    };
+    for op in changes {
+        let from = match op {
+            PatchOperation::Move(MoveOperation { from, .. }) if !within(writes, from) => Some(from),
+            PatchOperation::Copy(CopyOperation { from, .. }) if !within(reads, from) => Some(from),
+            _ => None,
+        };
+        if let Some(from) = from {
+            return Err(Error::OutOfScope(from.to_string()));
+        }
+        if !within(writes, op.path()) {
+            return Err(Error::OutOfScope(op.path().to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Return true if the deadline has passed
+fn is_expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// Return true if the task condition does not hold on the system
+fn is_unmet(condition: &Option<Condition>, system: &System, context: &Context) -> bool {
+    condition
+        .as_ref()
+        .is_some_and(|condition| !condition(system, context))
+}
+
+/// Convert a panic payload into a task error
+fn panicked(id: &str, payload: Box<dyn std::any::Any + Send>) -> Error {
+    let msg = payload
+        .downcast_ref::<&str>()
+        .map(|msg| msg.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("unknown panic"));
+    Error::Panicked(format!("{id}: {msg}"))
+}
+
+/// Call the function, converting any panic into an error
+fn catch_panic<T>(id: &str, f: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| Err(panicked(id, payload)))
 }
 
 impl Action {
@@ -91,17 +194,36 @@ impl Action {
         Self {
             id,
             scoped: action.is_scoped(),
+            access: Access::default(),
+            allow_empty: false,
+            deadline: None,
+            condition: None,
             context,
             dry_run: Arc::new(move |system: &System, context: &Context| {
-                let effect = handler_clone.call(system, context);
-                effect.pure()
+                catch_panic(id, || handler_clone.call(system, context).pure())
             }),
-            run: Arc::new(move |system: &System, context: &Context| {
-                let effect = action.call(system, context);
-
-                Box::pin(async { effect.run().await })
-            }),
-            describe: Arc::new(move |context: &Context| Ok(default_description(id, context))),
+            run: Arc::new(
+                move |system: &System, context: &Context, report: ReportChanges| {
+                    let effect = match panic::catch_unwind(AssertUnwindSafe(|| {
+                        action.call(system, context)
+                    })) {
+                        Ok(effect) => effect,
+                        Err(payload) => {
+                            let err = panicked(id, payload);
+                            return Box::pin(async { Err(err) });
+                        }
+                    };
+
+                    Box::pin(async move {
+                        AssertUnwindSafe(effect.run_with_progress(report))
+                            .catch_unwind()
+                            .await
+                            .unwrap_or_else(|payload| Err(panicked(id, payload)))
+                    })
+                },
+            ),
+            describe: None,
+            describe_with: None,
         }
     }
 
@@ -117,27 +239,150 @@ impl Action {
         self.id
     }
 
+    /// Return true if the action is allowed to produce no changes
+    ///
+    /// This is the case for actions that only perform side effects
+    pub(crate) fn allows_empty(&self) -> bool {
+        self.allow_empty
+    }
+
+    /// Get the paths the action writes to
+    ///
+    /// These are the paths declared with [`Job::writes`], or the action path otherwise
+    pub(crate) fn writes(&self) -> Vec<Path> {
+        match &self.access.writes {
+            Some(writes) => expand_paths(writes, &self.context),
+            None => vec![self.context.path.clone()],
+        }
+    }
+
+    /// Get the paths the action is allowed to modify and read from
+    ///
+    /// Scoped actions, including actions declaring the paths they read, and actions declaring
+    /// the paths they write to may only modify the state under the paths returned by
+    /// [`Action::writes`], and only copy values from the paths returned by [`Action::reads`].
+    /// Extractors already guarantee this for scoped actions, but handlers may also return a raw
+    /// patch. Returns `None` if the action may modify any part of the state.
+    fn scope(&self) -> Option<(Vec<Path>, Vec<Path>)> {
+        if is_scoped(self.scoped, &self.access) || self.access.writes.is_some() {
+            return Some((self.writes(), self.reads()));
+        }
+        None
+    }
+
+    /// Get the paths the action reads from, including the paths it writes to
+    ///
+    /// These are the paths declared with [`Job::reads`]. If no paths were declared, a scoped
+    /// action only reads its own path, while any other action may read the whole system
+    pub(crate) fn reads(&self) -> Vec<Path> {
+        let mut reads = match &self.access.reads {
+            Some(reads) => expand_paths(reads, &self.context),
+            None if self.scoped => vec![self.context.path.clone()],
+            None => vec![Path::default()],
+        };
+        reads.extend(self.writes());
+        reads
+    }
+
+    #[cfg(test)]
     /// Run the task on the system and return a list of changes
     pub(crate) async fn run(&self, system: &System) -> Result<Patch, Error> {
-        let Action { context, run, .. } = self;
-        (run)(system, context).await
+        self.run_with_progress(system, Box::new(|_| {})).await
+    }
+
+    /// Run the task on the system, passing the changes for intermediate results
+    /// to the given callback
+    ///
+    /// Reported changes are relative to the given system
+    pub(crate) async fn run_with_progress(
+        &self,
+        system: &System,
+        report: ReportChanges,
+    ) -> Result<Patch, Error> {
+        let Action {
+            context,
+            run,
+            deadline,
+            condition,
+            ..
+        } = self;
+        if is_expired(*deadline) || is_unmet(condition, system, context) {
+            return Err(Error::ConditionFailed);
+        }
+
+        let Some((writes, reads)) = self.scope() else {
+            return (run)(system, context, report).await;
+        };
+
+        // Intermediate changes outside of the scope are not reported, the
+        // action fails with the first violation once it terminates
+        let violation = Arc::new(Mutex::new(None));
+        let checked_report: ReportChanges = {
+            let mut report = report;
+            let (writes, reads) = (writes.clone(), reads.clone());
+            let violation = Arc::clone(&violation);
+            Box::new(
+                move |changes| match check_scope(&changes, &writes, &reads) {
+                    Ok(()) => report(changes),
+                    Err(err) => {
+                        violation.lock().unwrap().get_or_insert(err);
+                    }
+                },
+            )
+        };
+
+        let patch = (run)(system, context, checked_report).await?;
+        if let Some(err) = violation.lock().unwrap().take() {
+            return Err(err);
+        }
+        check_scope(&patch, &writes, &reads)?;
+        Ok(patch)
+    }
+
+    /// Get the description of the task for the given system
+    ///
+    /// Falls back to the [`Display`] implementation if no system description
+    /// was set or it cannot be rendered
+    pub(crate) fn describe_on(&self, system: &System) -> String {
+        match &self.describe_with {
+            Some(describe) => describe(system, self.context()).unwrap_or_else(|e| {
+                warn!("failed to expand description for task {}: {}", self.id, e);
+                self.to_string()
+            }),
+            None => self.to_string(),
+        }
     }
 
     /// Simulate the effect of the task on the system
     pub(crate) fn dry_run(&self, system: &System) -> Result<Patch, Error> {
         let Action {
-            context, dry_run, ..
+            context,
+            dry_run,
+            deadline,
+            condition,
+            ..
         } = self;
-        (dry_run)(system, context)
+        if is_expired(*deadline) || is_unmet(condition, system, context) {
+            return Err(Error::ConditionFailed);
+        }
+
+        let patch = (dry_run)(system, context)?;
+        if let Some((writes, reads)) = self.scope() {
+            check_scope(&patch, &writes, &reads)?;
+        }
+        Ok(patch)
     }
 }
 
 impl Display for Action {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let description = (self.describe)(self.context()).unwrap_or_else(|e| {
-            warn!("failed to expand description for task {}: {}", self.id, e);
-            default_description(self.id, self.context())
-        });
+        let description = match &self.describe {
+            Some(describe) => describe(self.context()).unwrap_or_else(|e| {
+                warn!("failed to expand description for task {}: {}", self.id, e);
+                default_description(self.id, self.context())
+            }),
+            None => default_description(self.id, self.context()),
+        };
         write!(f, "{}", description)
     }
 }
@@ -147,9 +392,13 @@ impl Display for Action {
 pub struct Method {
     id: &'static str,
     scoped: bool,
+    access: Access,
+    deadline: Option<Instant>,
+    condition: Option<Condition>,
     context: Context,
     expand: Expand,
-    describe: Describe,
+    describe: Option<Describe>,
+    describe_with: Option<DescribeWith>,
 }
 
 impl fmt::Debug for Method {
@@ -171,11 +420,15 @@ impl Method {
         Method {
             id,
             scoped: method.is_scoped(),
+            access: Access::default(),
+            deadline: None,
+            condition: None,
             context,
             expand: Arc::new(move |system: &System, context: &Context| {
-                method.call(system, context).pure()
+                catch_panic(id, || method.call(system, context).pure())
             }),
-            describe: Arc::new(move |context: &Context| Ok(default_description(id, context))),
+            describe: None,
+            describe_with: None,
         }
     }
 
@@ -194,18 +447,28 @@ impl Method {
     /// Expand the method into its component tasks
     pub(crate) fn expand(&self, system: &System) -> Result<Vec<Task>, Error> {
         let Method {
-            context, expand, ..
+            context,
+            expand,
+            deadline,
+            condition,
+            ..
         } = self;
+        if is_expired(*deadline) || is_unmet(condition, system, context) {
+            return Err(Error::ConditionFailed);
+        }
         (expand)(system, context)
     }
 }
 
 impl Display for Method {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let description = (self.describe)(self.context()).unwrap_or_else(|e| {
-            warn!("failed to expand description for task {}: {}", self.id, e);
-            default_description(self.id, self.context())
-        });
+        let description = match &self.describe {
+            Some(describe) => describe(self.context()).unwrap_or_else(|e| {
+                warn!("failed to expand description for task {}: {}", self.id, e);
+                default_description(self.id, self.context())
+            }),
+            None => default_description(self.id, self.context()),
+        };
         write!(f, "{}", description)
     }
 }
@@ -264,20 +527,67 @@ impl Task {
         }
     }
 
-    pub(crate) fn context(&mut self) -> &Context {
+    pub(crate) fn context(&self) -> &Context {
         match self {
             Self::Action(Action { context, .. }) => context,
             Self::Method(Method { context, .. }) => context,
         }
     }
 
+    /// Get the target assigned to the task
+    ///
+    /// Returns `None` if no target has been assigned, or a [`SerializationError`] if the target
+    /// cannot be deserialized into the type `T`, the same error returned by
+    /// [`Task::try_target`] when the target cannot be serialized.
+    ///
+    /// Targets are stored as JSON, where a missing target is represented as `null`. This means
+    /// that a task assigned an explicit `null` target, e.g. `None::<i32>`, cannot be told apart
+    /// from a task without a target, and this method returns `None` for both.
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    ///
+    /// fn foo() {}
+    ///
+    /// let task = foo.into_task().with_target(10);
+    /// assert_eq!(task.target::<i32>().unwrap().unwrap(), 10);
+    ///
+    /// // a null target is the same as no target
+    /// let task = foo.into_task().with_target(None::<i32>);
+    /// assert!(task.target::<Option<i32>>().is_none());
+    /// ```
+    pub fn target<T: DeserializeOwned>(&self) -> Option<Result<T, SerializationError>> {
+        let target = &self.context().target;
+        if target.is_null() {
+            return None;
+        }
+
+        Some(serde_json::from_value(target.clone()).map_err(SerializationError::from))
+    }
+
+    /// Get the path arguments assigned to the task
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    ///
+    /// fn foo() {}
+    ///
+    /// let task = foo.into_task().with_arg("foo", "123");
+    /// assert_eq!(task.args()[0].1, "123");
+    /// ```
+    pub fn args(&self) -> &[(Arc<str>, String)] {
+        &self.context().args.0
+    }
+
     /// Return true if the task only operates within its assigned path
     ///
     /// A scoped task is parallelizable
+    ///
+    /// A task is also scoped if the paths it reads have been declared with [`Job::reads`]
     pub fn is_scoped(&self) -> bool {
         match self {
-            Self::Action(Action { scoped, .. }) => *scoped,
-            Self::Method(Method { scoped, .. }) => *scoped,
+            Self::Action(Action { scoped, access, .. })
+            | Self::Method(Method { scoped, access, .. }) => is_scoped(*scoped, access),
         }
     }
 
@@ -316,6 +626,9 @@ impl Task {
 
     /// Set an argument for the task
     ///
+    /// When used on a method sub-task, the value replaces the matching `{param}` placeholder in
+    /// the job route verbatim, so it can be any string computed during the method expansion.
+    ///
     /// ```rust
     /// use mahler::task::prelude::*;
     ///
@@ -337,6 +650,40 @@ impl Task {
         }
     }
 
+    /// Set an argument for the task, replacing the argument with the same name on the parent task
+    ///
+    /// When a method returns a task, the planner copies the arguments of the method to the
+    /// child task, so the child task cannot address paths outside of the method context.
+    /// This allows a method to intentionally address a different path, for instance, a sibling
+    /// of the method path.
+    ///
+    /// Note that the planner determines whether a method can run concurrently with other tasks
+    /// from the method extractors, which means a scoped method (e.g. one that only uses [View](`crate::extract::View`))
+    /// overriding arguments may be executed concurrently with tasks operating on the overridden path.
+    /// Use the [System](`crate::extract::System`) extractor on the method to prevent this.
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    ///
+    /// fn foo() {}
+    ///
+    /// // Assign the value of the `foo` path argument to the task, even if
+    /// // the parent defines a value for `foo`
+    /// let task = foo.into_task().with_arg_override("foo", "123");
+    /// ```
+    pub fn with_arg_override(self, key: impl AsRef<str>, value: impl Into<String>) -> Self {
+        match self {
+            Self::Action(mut action) => {
+                action.context = action.context.with_arg_override(key, value);
+                Self::Action(action)
+            }
+            Self::Method(mut method) => {
+                method.context = method.context.with_arg_override(key, value);
+                Self::Method(method)
+            }
+        }
+    }
+
     /// Set a path for the task
     ///
     /// This is called by the planner, the path is obtained by finding the task by id on the
@@ -362,6 +709,168 @@ impl Task {
         }
     }
 
+    /// Get the human readable description of the task
+    ///
+    /// Unlike the [`Display`] implementation, this returns an error if the
+    /// [`Description`] handler fails, for instance, if the extractors cannot be initialized with
+    /// the task context, instead of falling back to the default description.
+    ///
+    /// If no description was set, the default `<task.id>(<task.path>) -> <task.target>`
+    /// description is returned.
+    pub fn try_describe(&self) -> Result<String, Error> {
+        let (id, context, describe) = match self {
+            Self::Action(Action {
+                id,
+                context,
+                describe,
+                ..
+            }) => (id, context, describe),
+            Self::Method(Method {
+                id,
+                context,
+                describe,
+                ..
+            }) => (id, context, describe),
+        };
+
+        match describe {
+            Some(describe) => describe(context),
+            None => Ok(default_description(id, context)),
+        }
+    }
+
+    /// Get the human readable description of the task for the given system
+    ///
+    /// This uses the [`SystemDescription`] handler set by calling
+    /// [`Job::with_system_description`], allowing the description to include the current system
+    /// state. If no system description was set, this returns the same value as
+    /// [`try_describe`](Self::try_describe).
+    pub fn describe_with(&self, system: &System) -> Result<String, Error> {
+        let (context, describe_with) = match self {
+            Self::Action(Action {
+                context,
+                describe_with,
+                ..
+            }) => (context, describe_with),
+            Self::Method(Method {
+                context,
+                describe_with,
+                ..
+            }) => (context, describe_with),
+        };
+
+        match describe_with {
+            Some(describe) => describe(system, context),
+            None => self.try_describe(),
+        }
+    }
+
+    /// Override the task id
+    ///
+    /// Tasks created from a handler use the handler type name as id. If the job for the
+    /// handler was registered with a custom id using [`Job::with_id`], methods need to set the
+    /// same id on the tasks they return so the planner can find the job.
+    ///
+    /// ```rust
+    /// use mahler::extract::{Target, View};
+    /// use mahler::task::prelude::*;
+    ///
+    /// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///     if *counter < tgt {
+    ///         *counter += 1;
+    ///     }
+    ///     counter
+    /// }
+    ///
+    /// fn plus_two(counter: View<i32>, Target(tgt): Target<i32>) -> Vec<Task> {
+    ///     vec![
+    ///         plus_one.with_target(tgt).with_id("plus_one_a"),
+    ///         plus_one.with_target(tgt).with_id("plus_one_a"),
+    ///     ]
+    /// }
+    ///
+    /// let jobs = [update(plus_one).with_id("plus_one_a"), update(plus_two)];
+    /// ```
+    pub fn with_id(self, id: &'static str) -> Self {
+        match self {
+            Self::Action(task) => Self::Action(Action { id, ..task }),
+            Self::Method(task) => Self::Method(Method { id, ..task }),
+        }
+    }
+
+    /// Allow the task to produce no changes to the system
+    ///
+    /// This is for internal use only, use [`Job::allow_empty_changes`] instead. This has no effect
+    /// on methods
+    pub(crate) fn allow_empty_changes(self) -> Self {
+        match self {
+            Self::Action(task) => Self::Action(Action {
+                allow_empty: true,
+                ..task
+            }),
+            method => method,
+        }
+    }
+
+    /// Declare the paths read by the task
+    ///
+    /// This is for internal use only, use [`Job::reads`] instead.
+    pub(crate) fn with_reads(self, paths: &[&'static str]) -> Self {
+        let reads = Some(paths.to_vec());
+        match self {
+            Self::Action(mut task) => {
+                task.access.reads = reads;
+                Self::Action(task)
+            }
+            Self::Method(mut task) => {
+                task.access.reads = reads;
+                Self::Method(task)
+            }
+        }
+    }
+
+    /// Declare the paths written by the task
+    ///
+    /// This is for internal use only, use [`Job::writes`] instead.
+    pub(crate) fn with_writes(self, paths: &[&'static str]) -> Self {
+        let writes = Some(paths.to_vec());
+        match self {
+            Self::Action(mut task) => {
+                task.access.writes = writes;
+                Self::Action(task)
+            }
+            Self::Method(mut task) => {
+                task.access.writes = writes;
+                Self::Method(task)
+            }
+        }
+    }
+
+    /// Set a deadline after which the task is no longer applicable
+    ///
+    /// This is for internal use only, use [`Job::with_deadline`] instead.
+    pub(crate) fn with_deadline(self, deadline: Instant) -> Self {
+        let deadline = Some(deadline);
+        match self {
+            Self::Action(task) => Self::Action(Action { deadline, ..task }),
+            Self::Method(task) => Self::Method(Method { deadline, ..task }),
+        }
+    }
+
+    /// Set a condition that must hold on the system for the task to be applicable
+    ///
+    /// This is for internal use only, use [`Job::require`] instead.
+    pub(crate) fn with_condition<F>(self, condition: F) -> Self
+    where
+        F: Fn(&System, &Context) -> bool + Send + Sync + 'static,
+    {
+        let condition: Option<Condition> = Some(Arc::new(condition));
+        match self {
+            Self::Action(task) => Self::Action(Action { condition, ..task }),
+            Self::Method(task) => Self::Method(Method { condition, ..task }),
+        }
+    }
+
     /// Set a description for the task
     ///
     /// This is for internal use only, task descriptions must be defined using
@@ -370,19 +879,43 @@ impl Task {
     where
         D: Description<T>,
     {
-        let describe: Describe = Arc::new(move |ctx| description.call(ctx));
+        let describe: Option<Describe> = Some(Arc::new(move |ctx| description.call(ctx)));
         match self {
             Self::Action(task) => Self::Action(Action { describe, ..task }),
             Self::Method(task) => Self::Method(Method { describe, ..task }),
         }
     }
+
+    /// Set a description for the task that is rendered from the system state
+    ///
+    /// This is for internal use only, task descriptions must be defined using
+    /// [`Job::with_system_description`]
+    pub(crate) fn with_system_description<D, T>(self, description: D) -> Self
+    where
+        D: SystemDescription<T>,
+    {
+        let describe_with: Option<DescribeWith> =
+            Some(Arc::new(move |system, ctx| description.call(system, ctx)));
+        match self {
+            Self::Action(task) => Self::Action(Action {
+                describe_with,
+                ..task
+            }),
+            Self::Method(task) => Self::Method(Method {
+                describe_with,
+                ..task
+            }),
+        }
+    }
 }
 
 impl Display for Task {
     /// Human readable description for the Task
     ///
     /// The description will be obtained from the [`Description`] handler set by calling
-    /// [`Job::with_description`]. If no description is set it defaults to `<task.id>(<task.path>)`
+    /// [`Job::with_description`]. If no description is set it defaults to
+    /// `<task.id>(<task.path>) -> <task.target>`, with the target rendered as compact JSON, or to
+    /// `<task.id>(<task.path>)` if the task has no target.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Action(action) => action.fmt(f),
@@ -394,7 +927,7 @@ impl Display for Task {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::extract::{System as Sys, Target, View};
+    use crate::extract::{Args, System as Sys, Target, View};
     use crate::system::System;
     use pretty_assertions::assert_eq;
     use serde::{Deserialize, Serialize};
@@ -444,6 +977,31 @@ mod tests {
         })
     }
 
+    #[test]
+    fn it_fails_to_describe_a_task_if_the_description_cannot_be_expanded() {
+        let task = plus_one
+            .into_task()
+            .with_description(|Target(tgt): Target<i32>| format!("+1 until {tgt}"));
+
+        // The target has not been assigned
+        assert!(task.try_describe().is_err());
+
+        let task = task.with_target(2);
+        assert_eq!(task.try_describe().unwrap(), "+1 until 2");
+    }
+
+    #[test]
+    fn it_reads_the_task_target_and_args() {
+        let task = plus_one.into_task();
+        assert!(task.target::<i32>().is_none());
+        assert!(task.args().is_empty());
+
+        let task = task.with_target(2).with_arg("counter", "one");
+        assert_eq!(task.target::<i32>().unwrap().unwrap(), 2);
+        assert!(task.target::<String>().unwrap().is_err());
+        assert_eq!(task.args(), &[(Arc::from("counter"), String::from("one"))]);
+    }
+
     #[test]
     fn it_gets_metadata_from_function() {
         assert_eq!(plus_one.id(), "mahler::task::tests::plus_one");
@@ -466,6 +1024,91 @@ mod tests {
         assert_eq!(task.to_string(), "+1 until 2");
     }
 
+    #[test]
+    fn it_includes_the_target_in_the_default_description() {
+        let task = plus_one.into_task();
+
+        // Without a target, only the id and path are rendered
+        assert_eq!(task.to_string(), "mahler::task::tests::plus_one()");
+
+        let task = task.with_path("/counters/one").with_target(2);
+        assert_eq!(
+            task.to_string(),
+            "mahler::task::tests::plus_one(/counters/one) -> 2"
+        );
+        assert_eq!(
+            task.try_describe().unwrap(),
+            "mahler::task::tests::plus_one(/counters/one) -> 2"
+        );
+
+        // Targets are rendered as compact JSON
+        let task = plus_one.with_target(json!({"a": [1, 2]}));
+        assert_eq!(
+            task.to_string(),
+            r#"mahler::task::tests::plus_one() -> {"a":[1,2]}"#
+        );
+
+        // A description takes precedence
+        let task = task.with_description(|| "+1");
+        assert_eq!(task.to_string(), "+1");
+    }
+
+    #[test]
+    fn it_allows_to_describe_a_task_using_path_args() {
+        let task = plus_one
+            .into_task()
+            .with_description(|Args(counter): Args<String>| format!("increment counter {counter}"));
+
+        // The arg has not been assigned so the default description is returned
+        assert_eq!(task.to_string(), "mahler::task::tests::plus_one()");
+
+        let task = task.with_arg("counter", "one");
+        assert_eq!(task.to_string(), "increment counter one");
+    }
+
+    #[test]
+    fn it_allows_to_describe_a_task_using_args_and_the_system() {
+        let task = plus_one
+            .into_task()
+            .with_arg("counter", "one")
+            .with_system_description(
+                |Args(counter): Args<String>, Sys(state): Sys<serde_json::Value>| {
+                    format!(
+                        "increment counter {counter} from {}",
+                        state["counters"][&counter]
+                    )
+                },
+            );
+
+        let system = System::try_from(json!({ "counters": { "one": 1 } })).unwrap();
+        assert_eq!(
+            task.describe_with(&system).unwrap(),
+            "increment counter one from 1"
+        );
+    }
+
+    #[test]
+    fn it_allows_to_describe_a_task_using_the_system_state() {
+        let task = plus_one
+            .with_target(3)
+            .with_description(|Target(tgt): Target<i32>| format!("+1 until {tgt}"));
+
+        // Without a system description the context description is used
+        let system = System::try_from(1).unwrap();
+        assert_eq!(task.describe_with(&system).unwrap(), "+1 until 3");
+
+        let task = task.with_system_description(|counter: View<i32>, Target(tgt): Target<i32>| {
+            format!("+1 from {} until {tgt}", *counter)
+        });
+        assert_eq!(task.describe_with(&system).unwrap(), "+1 from 1 until 3");
+
+        let system = System::try_from(2).unwrap();
+        assert_eq!(task.describe_with(&system).unwrap(), "+1 from 2 until 3");
+
+        // The context description is still used for display
+        assert_eq!(task.to_string(), "+1 until 3");
+    }
+
     #[test]
     fn it_identifies_task_scoping_based_on_args() {
         let task = plus_one.with_target(1);
@@ -539,6 +1182,36 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn it_converts_panics_in_actions_into_errors() {
+        fn out_of_bounds(counter: View<Vec<i32>>) -> View<Vec<i32>> {
+            let _ = counter[3];
+            counter
+        }
+
+        fn panic_on_io(counter: View<i32>) -> Effect<View<i32>> {
+            Effect::of(counter).with_io(|_| async { panic!("io failed") })
+        }
+
+        let system = System::try_from(json!([1, 2])).unwrap();
+        let Task::Action(action) = out_of_bounds.into_task() else {
+            panic!("Expected an Action task");
+        };
+        assert!(matches!(action.dry_run(&system), Err(Error::Panicked(_))));
+        assert!(matches!(action.run(&system).await, Err(Error::Panicked(_))));
+
+        let system = System::try_from(0).unwrap();
+        let Task::Action(action) = panic_on_io.into_task() else {
+            panic!("Expected an Action task");
+        };
+        // The panic only happens at runtime
+        assert!(action.dry_run(&system).is_ok());
+        let Err(Error::Panicked(msg)) = action.run(&system).await else {
+            panic!("Expected a panic error");
+        };
+        assert!(msg.ends_with("panic_on_io: io failed"));
+    }
+
     #[tokio::test]
     async fn it_allows_actions_returning_runtime_errors() {
         let system = System::try_from(0).unwrap();
@@ -553,6 +1226,114 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn it_allows_recovering_the_runtime_error_source() {
+        let system = System::try_from(0).unwrap();
+        let task = plus_one_async_with_error.with_target(1);
+
+        if let Task::Action(action) = task {
+            let err = action.run(&system).await.unwrap_err();
+            assert!(err.runtime_source::<SomeError>().is_some());
+            assert!(err.runtime_source::<std::fmt::Error>().is_none());
+        } else {
+            panic!("Expected an Action task");
+        }
+    }
+
+    #[tokio::test]
+    async fn it_chains_the_runtime_error_source() {
+        use std::error::Error as _;
+
+        let system = System::try_from(0).unwrap();
+        let Task::Action(action) = plus_one_async_with_error.with_target(1) else {
+            panic!("Expected an Action task");
+        };
+
+        let err = action.run(&system).await.unwrap_err();
+        assert_eq!(err.to_string(), "some error happened");
+
+        let source = err.source().expect("the error should have a source");
+        assert!(source.downcast_ref::<SomeError>().is_some());
+    }
+
+    #[tokio::test]
+    async fn it_allows_effect_handlers_using_the_question_mark_operator() {
+        fn set_from_arg(
+            mut counter: View<i32>,
+            Args(value): Args<String>,
+        ) -> Effect<View<i32>, std::num::ParseIntError> {
+            try_effect(|| {
+                *counter = value.parse()?;
+                Ok(with_io(counter, |counter| async {
+                    sleep(Duration::from_millis(10)).await;
+                    Ok(counter)
+                }))
+            })
+        }
+
+        let system = System::try_from(0).unwrap();
+
+        let Task::Action(action) = set_from_arg.with_arg("value", "2") else {
+            panic!("Expected an Action task");
+        };
+        assert_eq!(
+            action.run(&system).await.unwrap(),
+            from_value::<Patch>(json!([
+              { "op": "replace", "path": "", "value": 2 },
+            ]))
+            .unwrap()
+        );
+
+        let Task::Action(action) = set_from_arg.with_arg("value", "two") else {
+            panic!("Expected an Action task");
+        };
+        let err = action.dry_run(&system).unwrap_err();
+        assert!(err.runtime_source::<std::num::ParseIntError>().is_some());
+    }
+
+    fn plus_one_or_fail(
+        mut counter: View<i32>,
+        Target(tgt): Target<i32>,
+    ) -> Result<View<i32>, SomeError> {
+        if tgt < 0 {
+            return Err(SomeError);
+        }
+
+        if *counter < tgt {
+            *counter += 1;
+        }
+
+        Ok(counter)
+    }
+
+    #[tokio::test]
+    async fn it_allows_sync_actions_returning_result() {
+        let system = System::try_from(0).unwrap();
+
+        let task = plus_one_or_fail.with_target(1);
+        if let Task::Action(action) = task {
+            let changes = action.run(&system).await.unwrap();
+            assert_eq!(
+                changes,
+                from_value::<Patch>(json!([
+                  { "op": "replace", "path": "", "value": 1 },
+                ]))
+                .unwrap()
+            );
+        } else {
+            panic!("Expected an Action task");
+        }
+
+        let task = plus_one_or_fail.with_target(-1);
+        if let Task::Action(action) = task {
+            let err = action.run(&system).await.unwrap_err();
+            assert_eq!(err.to_string(), "some error happened");
+            assert!(err.runtime_source::<SomeError>().is_some());
+        } else {
+            panic!("Expected an Action task");
+        }
+    }
+
     #[test]
     fn it_allows_to_dry_run_actions_returning_error() {
         let system = System::try_from(1).unwrap();
@@ -651,6 +1432,303 @@ mod tests {
         }
     }
 
+    fn reset_counter(Target(tgt): Target<i32>) -> Set<i32> {
+        Effect::from_value(tgt)
+    }
+
+    #[test]
+    fn it_writes_values_from_effects_to_the_task_path() {
+        let state = State {
+            counters: [("a".to_string(), 0)].into(),
+        };
+        let system = System::try_from(state).unwrap();
+
+        // An existing value is replaced
+        let task = reset_counter.with_target(2).with_path("/counters/a");
+        if let Task::Action(action) = task {
+            let changes = action.dry_run(&system).unwrap();
+            assert_eq!(
+                changes,
+                from_value::<Patch>(json!([
+                  { "op": "replace", "path": "/counters/a", "value": 2 },
+                ]))
+                .unwrap()
+            );
+        } else {
+            panic!("Expected an Action Task");
+        }
+
+        // A missing value is created
+        let task = reset_counter.with_target(3).with_path("/counters/b");
+        if let Task::Action(action) = task {
+            let changes = action.dry_run(&system).unwrap();
+            assert_eq!(
+                changes,
+                from_value::<Patch>(json!([
+                  { "op": "add", "path": "/counters/b", "value": 3 },
+                ]))
+                .unwrap()
+            );
+        } else {
+            panic!("Expected an Action Task");
+        }
+    }
+
+    fn replace_counter_a(Target(tgt): Target<i32>) -> Patch {
+        from_value(json!([
+          { "op": "replace", "path": "/counters/a", "value": tgt },
+        ]))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_applies_patches_returned_by_the_handler() {
+        let state = State {
+            counters: [("a".to_string(), 0), ("b".to_string(), 0)].into(),
+        };
+        let system = System::try_from(state).unwrap();
+        let expected = from_value::<Patch>(json!([
+          { "op": "replace", "path": "/counters/a", "value": 2 },
+        ]))
+        .unwrap();
+
+        let task = replace_counter_a.with_target(2).with_path("/counters/a");
+        if let Task::Action(action) = task {
+            assert_eq!(action.dry_run(&system).unwrap(), expected);
+            assert_eq!(action.run(&system).await.unwrap(), expected);
+        } else {
+            panic!("Expected an Action Task");
+        }
+
+        // The patch modifies a path outside the task scope
+        let task = replace_counter_a.with_target(2).with_path("/counters/b");
+        if let Task::Action(action) = task {
+            assert!(matches!(
+                action.dry_run(&system),
+                Err(Error::OutOfScope(path)) if path == "/counters/a"
+            ));
+        } else {
+            panic!("Expected an Action Task");
+        }
+    }
+
+    #[test]
+    fn it_checks_patches_against_the_declared_writes() {
+        let state = State {
+            counters: [("a".to_string(), 0), ("b".to_string(), 0)].into(),
+        };
+        let system = System::try_from(state).unwrap();
+        let context = Context::new()
+            .with_path("/counters/b")
+            .with_target(json!(2));
+
+        // The patch is outside of the task path but within the declared writes
+        let job = update(replace_counter_a).writes(&["/counters/a"]);
+        if let Task::Action(action) = job.new_task(context.clone()) {
+            assert_eq!(
+                action.dry_run(&system).unwrap(),
+                from_value::<Patch>(json!([
+                  { "op": "replace", "path": "/counters/a", "value": 2 },
+                ]))
+                .unwrap()
+            );
+        } else {
+            panic!("Expected an Action Task");
+        }
+
+        // The declared writes replace the task path as the scope of the task
+        let job = update(replace_counter_a).writes(&["/counters/c"]);
+        if let Task::Action(action) = job.new_task(context) {
+            assert!(matches!(
+                action.dry_run(&system),
+                Err(Error::OutOfScope(path)) if path == "/counters/a"
+            ));
+        } else {
+            panic!("Expected an Action Task");
+        }
+    }
+
+    fn replace_counter_a_sys(_: Sys<State>, tgt: Target<i32>) -> Patch {
+        replace_counter_a(tgt)
+    }
+
+    #[test]
+    fn it_checks_patches_of_tasks_declaring_reads() {
+        let state = State {
+            counters: [("a".to_string(), 0), ("b".to_string(), 0)].into(),
+        };
+        let system = System::try_from(state).unwrap();
+        let context = Context::new()
+            .with_path("/counters/b")
+            .with_target(json!(2));
+
+        // Declaring the reads makes the task scoped, so it may only modify its own path
+        let job = update(replace_counter_a_sys).reads(&["/counters"]);
+        let task = job.new_task(context);
+        assert!(task.is_scoped());
+        if let Task::Action(action) = task {
+            assert!(matches!(
+                action.dry_run(&system),
+                Err(Error::OutOfScope(path)) if path == "/counters/a"
+            ));
+        } else {
+            panic!("Expected an Action Task");
+        }
+    }
+
+    // Changes returned as is by an effect
+    #[derive(Clone)]
+    struct Changes(Patch);
+
+    impl IntoResult<Patch> for Changes {
+        fn into_result(self) -> Result<Patch, Error> {
+            Ok(self.0)
+        }
+    }
+
+    fn replace_counter(counter: &str, value: i32) -> Changes {
+        Changes(
+            from_value(json!([
+              { "op": "replace", "path": format!("/counters/{counter}"), "value": value },
+            ]))
+            .unwrap(),
+        )
+    }
+
+    // Reports a change to a different counter before the final result
+    fn replace_counters_with_progress(Target(tgt): Target<i32>) -> Effect<Changes> {
+        Effect::of(replace_counter("a", tgt)).with_io_stream(move |changes| {
+            futures::stream::iter([Ok(replace_counter("b", tgt)), Ok(changes)])
+        })
+    }
+
+    #[tokio::test]
+    async fn it_rejects_intermediate_changes_outside_the_task_scope() {
+        use std::sync::Mutex;
+
+        let state = State {
+            counters: [("a".to_string(), 0), ("b".to_string(), 0)].into(),
+        };
+        let system = System::try_from(state).unwrap();
+
+        let task = replace_counters_with_progress
+            .with_target(2)
+            .with_path("/counters/a");
+        let Task::Action(action) = task else {
+            panic!("Expected an Action Task");
+        };
+
+        // The planned changes are within the task scope
+        assert_eq!(action.dry_run(&system).unwrap(), replace_counter("a", 2).0);
+
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let res = {
+            let reported = Arc::clone(&reported);
+            action
+                .run_with_progress(
+                    &system,
+                    Box::new(move |changes| reported.lock().unwrap().push(changes)),
+                )
+                .await
+        };
+
+        assert!(matches!(res, Err(Error::OutOfScope(path)) if path == "/counters/b"));
+
+        // Changes outside of the scope are never reported
+        assert_eq!(*reported.lock().unwrap(), vec![replace_counter("a", 2).0]);
+    }
+
+    fn move_counter_b() -> Patch {
+        from_value(json!([
+          { "op": "move", "from": "/counters/b", "path": "/counters/a" },
+        ]))
+        .unwrap()
+    }
+
+    fn copy_counter_b() -> Patch {
+        from_value(json!([
+          { "op": "copy", "from": "/counters/b", "path": "/counters/a" },
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn it_checks_the_source_of_move_and_copy_operations() {
+        let state = State {
+            counters: [("a".to_string(), 0), ("b".to_string(), 0)].into(),
+        };
+        let system = System::try_from(state).unwrap();
+        let context = Context::new().with_path("/counters/a");
+
+        // Moving a value removes the source, which is outside of the task scope
+        let job = update(move_counter_b).writes(&["/counters/a"]);
+        if let Task::Action(action) = job.new_task(context.clone()) {
+            assert!(matches!(
+                action.dry_run(&system),
+                Err(Error::OutOfScope(path)) if path == "/counters/b"
+            ));
+        } else {
+            panic!("Expected an Action Task");
+        }
+
+        // Copying a value reads the source, which is not within the declared reads
+        let job = update(copy_counter_b).writes(&["/counters/a"]);
+        if let Task::Action(action) = job.new_task(context.clone()) {
+            assert!(matches!(
+                action.dry_run(&system),
+                Err(Error::OutOfScope(path)) if path == "/counters/b"
+            ));
+        } else {
+            panic!("Expected an Action Task");
+        }
+
+        // The source of the copy is within the declared reads
+        let job = update(copy_counter_b)
+            .reads(&["/counters"])
+            .writes(&["/counters/a"]);
+        if let Task::Action(action) = job.new_task(context) {
+            assert_eq!(action.dry_run(&system).unwrap(), copy_counter_b());
+        } else {
+            panic!("Expected an Action Task");
+        }
+    }
+
+    // Reports a move from a different counter before the final result
+    fn move_counter_with_progress(Target(tgt): Target<i32>) -> Effect<Changes> {
+        Effect::of(replace_counter("a", tgt)).with_io_stream(move |changes| {
+            futures::stream::iter([Ok(Changes(move_counter_b())), Ok(changes)])
+        })
+    }
+
+    #[tokio::test]
+    async fn it_rejects_intermediate_moves_from_outside_the_task_scope() {
+        let state = State {
+            counters: [("a".to_string(), 0), ("b".to_string(), 0)].into(),
+        };
+        let system = System::try_from(state).unwrap();
+
+        let task = move_counter_with_progress
+            .with_target(2)
+            .with_path("/counters/a");
+        let Task::Action(action) = task else {
+            panic!("Expected an Action Task");
+        };
+
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let res = {
+            let reported = Arc::clone(&reported);
+            action
+                .run_with_progress(
+                    &system,
+                    Box::new(move |changes| reported.lock().unwrap().push(changes)),
+                )
+                .await
+        };
+
+        assert!(matches!(res, Err(Error::OutOfScope(path)) if path == "/counters/b"));
+        assert_eq!(*reported.lock().unwrap(), vec![replace_counter("a", 2).0]);
+    }
+
     fn plus_two_with_error(
         counter: View<i32>,
         Target(tgt): Target<i32>,
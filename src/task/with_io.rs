@@ -1,6 +1,6 @@
 use std::{convert::Infallible, future::Future};
 
-use super::effect::Effect;
+use super::effect::{Assign, Effect};
 use crate::extract::{Pointer, View};
 
 /// Creates an [`Effect`] from a pure value and an I/O function
@@ -16,6 +16,36 @@ where
     Effect::of(pure).with_io(io)
 }
 
+/// Creates an [`Effect`] from a function returning a `Result` of an effect
+///
+/// Handlers returning an effect cannot use the `?` operator directly. Wrapping the handler body
+/// in `try_effect` allows to perform fallible work before building the effect. If the function
+/// returns an error, the resulting effect fails with the error without running any I/O.
+///
+/// ```rust
+/// use std::num::ParseIntError;
+/// use mahler::extract::{Args, View};
+/// use mahler::task::prelude::*;
+///
+/// fn set_counter(mut counter: View<i32>, Args(value): Args<String>) -> IO<i32, ParseIntError> {
+///     try_effect(|| {
+///         *counter = value.parse()?;
+///         Ok(with_io(counter, |counter| async {
+///             // write the value to the device
+///             Ok(counter)
+///         }))
+///     })
+/// }
+/// ```
+pub fn try_effect<T, E, F>(f: F) -> Effect<T, E>
+where
+    T: Send + 'static,
+    E: 'static,
+    F: FnOnce() -> Result<Effect<T, E>, E>,
+{
+    f().unwrap_or_else(Effect::from_error)
+}
+
 /// Convenience alias for `Job` return type
 pub type IO<T, E = Infallible> = Effect<View<T>, E>;
 
@@ -30,3 +60,6 @@ pub type Create<T, E = Infallible> = Effect<Pointer<T>, E>;
 
 /// Convenience alias for `Job` return type
 pub type Any<T, E = Infallible> = Effect<Pointer<T>, E>;
+
+/// Convenience alias for `Job` return type
+pub type Set<T, E = Infallible> = Effect<Assign<T>, E>;
@@ -5,15 +5,18 @@ use async_trait::async_trait;
 use json_patch::Patch;
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::sync::{broadcast, Notify};
 use tokio::task::JoinHandle;
 use tokio::{select, sync::RwLock};
 use tokio_stream::wrappers::BroadcastStream;
 use tokio_stream::{Stream, StreamExt};
-use tracing::{debug, error, field, instrument, span, Instrument, Level, Span};
+use tracing::{debug, error, field, instrument, span, warn, Instrument, Level, Span};
 
 #[cfg(feature = "logging")]
 mod logging;
@@ -21,18 +24,24 @@ mod logging;
 #[cfg(debug_assertions)]
 mod testing;
 
-#[cfg(debug_assertions)]
-pub use testing::*;
-
 #[cfg(feature = "logging")]
 #[cfg_attr(docsrs, doc(cfg(feature = "logging")))]
 pub use logging::init as init_logging;
 
-use crate::errors::{IOError, InternalError, SerializationError};
-use crate::planner::{Domain, Error as PlannerError, Planner};
+use crate::errors::{IOError, InternalError, MethodError, SerializationError};
+use crate::planner::{
+    merge_target, Distance, Error as PlannerError, PathComparators, Planner, PlannerConfig,
+};
+pub use crate::planner::{
+    Coverage, Domain, Heuristic, JobIssue, JobStats, NumberPolicy, PlanStats, RouteMatcher,
+    SearchStrategy,
+};
 use crate::system::{Resources, System};
-use crate::task::{Error as TaskError, Job};
-use crate::workflow::{channel, AggregateError, Interrupt, Sender, WorkflowStatus};
+use crate::task::{Context, Error as TaskError, Job, Task};
+use crate::workflow::{
+    channel, AggregateError, Divergence, Interrupt, PlanResult, RehydrateError, Sender, Workflow,
+    WorkflowStatus,
+};
 
 pub mod prelude {
     //! Types and traits for setting up a Worker
@@ -72,17 +81,65 @@ pub enum FatalError {
     Internal(#[from] InternalError),
 }
 
+/// The initial worker state could not be migrated
+///
+/// See [`Worker::initial_state_with_migration`]
+#[derive(Debug, Error)]
+pub enum MigrationError<E> {
+    #[error("state migration failed: {0}")]
+    /// The migration function returned an error
+    Migration(#[source] E),
+
+    #[error(transparent)]
+    /// The state could not be converted to or from the state model
+    Serialization(#[from] SerializationError),
+}
+
+#[derive(Debug, Error)]
+#[error("workflow not found{}", .reason.as_ref().map(|r| format!(": {r}")).unwrap_or_default())]
+/// No workflow was found for the given target
+///
+/// Includes statistics about the search space explored by the planner before
+/// giving up, which helps to tune the worker domain.
+pub struct NotFound {
+    stats: Box<PlanStats>,
+    reason: Option<String>,
+}
+
+impl NotFound {
+    pub(crate) fn new(stats: Box<PlanStats>, reason: Option<String>) -> Self {
+        Self { stats, reason }
+    }
+
+    /// Return the statistics of the failed search
+    pub fn stats(&self) -> &PlanStats {
+        &self.stats
+    }
+
+    /// Return the reason given by a task that declared the target
+    /// [unreachable](`crate::task::Error::Unreachable`), if any
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+}
+
 #[derive(Debug)]
 /// Exit status from [`Worker::seek_target`]
 pub enum SeekStatus {
     /// The worker has reached the target state
     Success,
     /// No workflow was found for the given target
-    NotFound,
+    NotFound(NotFound),
     /// Worker interrupted by user request
     Interrupted,
     /// An error happened while executing the workflow.
+    ///
+    /// Besides IO errors, this includes tasks that panicked or modified paths outside of their
+    /// scope at runtime, in which case the source of the error is the
+    /// [task error](`crate::task::Error`).
     Aborted(Vec<IOError>),
+    /// The target was not reached before exhausting the [re-plan limit](`ReplanPolicy::Limit`)
+    ReplanLimitReached,
 }
 
 impl PartialEq for SeekStatus {
@@ -90,12 +147,213 @@ impl PartialEq for SeekStatus {
         matches!(
             (self, other),
             (SeekStatus::Success, SeekStatus::Success)
-                | (SeekStatus::NotFound, SeekStatus::NotFound)
+                | (SeekStatus::NotFound(_), SeekStatus::NotFound(_))
                 | (SeekStatus::Interrupted, SeekStatus::Interrupted)
+                | (
+                    SeekStatus::ReplanLimitReached,
+                    SeekStatus::ReplanLimitReached
+                )
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Policy for re-planning when a workflow terminates without reaching the target
+///
+/// After a workflow completes, or if a task condition fails at runtime, the worker looks for
+/// a new workflow from the current state, as the system may have changed underneath it.
+pub enum ReplanPolicy {
+    /// Re-plan until the target is reached. This is the default
+    #[default]
+    Always,
+    /// Re-plan at most the given number of times per seek
+    ///
+    /// If the target is not reached once the limit is exhausted, the seek terminates with
+    /// [`SeekStatus::ReplanLimitReached`]. A limit of `0` executes a single workflow.
+    Limit(u32),
+}
+
+impl ReplanPolicy {
+    /// Return `true` if the worker may re-plan after the given number of re-plans
+    fn allows(&self, replans: u32) -> bool {
+        match self {
+            ReplanPolicy::Always => true,
+            ReplanPolicy::Limit(limit) => replans < *limit,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Outcome of a single planning and execution cycle
+///
+/// See [`Worker::run_once`]
+pub enum StepOutcome {
+    /// The system is already in the target state, no workflow was executed
+    Reached,
+    /// A workflow was executed. The target may not have been reached yet, and
+    /// another cycle is needed to confirm it
+    Progressed,
+    /// No workflow was found for the given target
+    NotFound(NotFound),
+    /// Workflow execution was interrupted
+    Interrupted,
+    /// An error happened while executing the workflow
+    Aborted(Vec<IOError>),
+}
+
+impl PartialEq for StepOutcome {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (StepOutcome::Reached, StepOutcome::Reached)
+                | (StepOutcome::Progressed, StepOutcome::Progressed)
+                | (StepOutcome::NotFound(_), StepOutcome::NotFound(_))
+                | (StepOutcome::Interrupted, StepOutcome::Interrupted)
         )
     }
 }
 
+impl Eq for StepOutcome {}
+
+#[derive(Debug, Error)]
+/// Error returned when searching for a workflow without executing it
+///
+/// See [`Worker::find_plan`]
+pub enum PlanError {
+    #[error(transparent)]
+    /// No workflow was found for the given target
+    NotFound(#[from] NotFound),
+
+    #[error(transparent)]
+    /// An unrecoverable error happened during planning
+    Fatal(#[from] FatalError),
+}
+
+impl From<SerializationError> for PlanError {
+    fn from(err: SerializationError) -> Self {
+        PlanError::Fatal(err.into())
+    }
+}
+
+/// Convert an error from the planner into the error reported to the caller
+fn plan_error(err: PlannerError) -> PlanError {
+    match err {
+        PlannerError::NotFound(stats, reason) => NotFound::new(stats, reason).into(),
+        PlannerError::Serialization(e) => FatalError::from(e).into(),
+        PlannerError::Internal(e) => FatalError::from(e).into(),
+        PlannerError::Task(e) => FatalError::from(e).into(),
+        e @ PlannerError::ChildJobNotRegistered { .. } => {
+            FatalError::from(TaskError::from(MethodError::new(e))).into()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A node in the expansion tree of a task
+///
+/// See [`Worker::expand_tree`]
+pub struct ExpansionNode {
+    /// The task id
+    pub id: String,
+    /// The path the task applies to
+    pub path: String,
+    /// The nodes for the tasks returned by a method expansion, in order.
+    /// This is empty for atomic tasks
+    pub children: Vec<ExpansionNode>,
+}
+
+/// Split runtime errors from a workflow execution, returning the list of errors aborting the
+/// workflow
+///
+/// Panics and out of scope changes are returned along with the IO errors, wrapping the
+/// [task error](`TaskError`). Any other error is probably a bug and is returned as an
+/// [`InternalError`]
+fn io_errors(err: AggregateError<TaskError>) -> Result<Vec<IOError>, InternalError> {
+    let mut io = Vec::new();
+    let mut other = Vec::new();
+    let AggregateError(all) = err;
+    for e in all.into_iter() {
+        match e {
+            TaskError::IO(re) => io.push(re),
+            // A panicking handler or a task modifying paths outside of its scope at
+            // runtime only affects that task, so the workflow is aborted like with an
+            // IO error and the worker remains usable
+            TaskError::Panicked(_) | TaskError::OutOfScope(_) => io.push(IOError::new(e)),
+            // Timed out tasks did not modify the system, the worker re-plans
+            // like with a condition failure. If the target is unreachable,
+            // re-planning will report that the workflow was not found
+            TaskError::ConditionFailed | TaskError::Timeout(_) | TaskError::Unreachable(_) => {}
+            _ => other.push(e),
+        }
+    }
+
+    // If there are non-IO errors, there is
+    // probably a bug somewhere
+    if !other.is_empty() {
+        return Err(InternalError::from(anyhow!(AggregateError::from(other))));
+    }
+
+    Ok(io)
+}
+
+/// Read the current state of the system from the real world
+///
+/// When a sensor is configured on the [`Worker`], the system state is refreshed from the sensor
+/// before each planning cycle, so the Worker never plans from a stale in-memory state.
+///
+/// ```rust
+/// use serde_json::{json, Value};
+/// use mahler::errors::IOError;
+/// use mahler::worker::Sensor;
+///
+/// struct MySensor;
+///
+/// impl Sensor for MySensor {
+///     fn sense(&self) -> Result<Value, IOError> {
+///         // read the state of the system
+///         Ok(json!({"one": 0}))
+///     }
+/// }
+/// ```
+pub trait Sensor: Send + Sync + 'static {
+    /// Read the current system state
+    ///
+    /// The returned value must be compatible with the Worker internal state type
+    fn sense(&self) -> Result<Value, IOError>;
+}
+
+/// Error reading the system state from a [`Sensor`]
+enum SenseError {
+    Sensor(IOError),
+    Internal(InternalError),
+}
+
+/// Refresh the system state from the sensor
+///
+/// Changes are sent through the patch channel so the system writer
+/// can notify followers of the update
+async fn sense_system(
+    sensor: &dyn Sensor,
+    sys: &Arc<RwLock<System>>,
+    channel: &Sender<Patch>,
+) -> Result<(), SenseError> {
+    let state = sensor.sense().map_err(SenseError::Sensor)?;
+    let changes = {
+        let system = sys.read().await;
+        json_patch::diff(system.root(), &state)
+    };
+
+    if !changes.0.is_empty() {
+        channel.send(changes).await.map_err(|e| {
+            SenseError::Internal(InternalError::from(anyhow!(
+                "failed to update state from sensor: {e}"
+            )))
+        })?;
+    }
+
+    Ok(())
+}
+
 #[async_trait]
 /// Helper trait to chain worker calls
 pub trait SeekTarget<O, I = O> {
@@ -120,6 +378,134 @@ impl Drop for AutoInterrupt {
 /// Helper type to indicate that a state change happened on the worker
 struct UpdateEvent;
 
+/// Function used to read the system state as the worker input type
+type Normalizer = fn(&System) -> Option<Value>;
+
+fn normalize<I: Serialize + DeserializeOwned>(system: &System) -> Option<Value> {
+    system
+        .state::<I>()
+        .ok()
+        .and_then(|state| serde_json::to_value(state).ok())
+}
+
+/// Read the system state without conversion, including any internal fields
+fn raw_state(system: &System) -> Option<Value> {
+    Some(system.root().clone())
+}
+
+/// Target of the progress calculation and how the state is compared to it
+struct ProgressTarget {
+    target: Value,
+    normalizer: Normalizer,
+    number_policy: NumberPolicy,
+    path_comparators: PathComparators,
+}
+
+impl ProgressTarget {
+    /// Count the differences between the system state and the target, using the same
+    /// comparison rules as the planner
+    fn distance(&self, system: &System) -> Option<usize> {
+        let cur = (self.normalizer)(system)?;
+        let distance = Distance::with_comparators(
+            &cur,
+            &self.target,
+            self.number_policy,
+            &self.path_comparators,
+        );
+        Some(distance.count())
+    }
+}
+
+struct ProgressState {
+    target: Option<ProgressTarget>,
+    initial: usize,
+    value: f32,
+}
+
+// There is nothing to do until a target is given
+impl Default for ProgressState {
+    fn default() -> Self {
+        Self {
+            target: None,
+            initial: 0,
+            value: 100.0,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+/// Helper type to track the progress of the worker towards the last target
+///
+/// Progress is calculated from the distance to the target when the search started and the
+/// remaining distance after every system change. The value never decreases while seeking a
+/// target, even if the distance temporarily grows, e.g. after a re-plan.
+struct Progress(Arc<std::sync::RwLock<ProgressState>>);
+
+impl Progress {
+    /// Start tracking the progress towards a new target
+    fn start(&self, system: &System, tgt: &Value, normalizer: Normalizer, config: &PlannerConfig) {
+        let target = ProgressTarget {
+            target: tgt.clone(),
+            normalizer,
+            number_policy: config.number_policy,
+            path_comparators: config.path_comparators.clone(),
+        };
+        let initial = target.distance(system).unwrap_or_default();
+
+        let mut state = self.0.write().unwrap();
+        *state = ProgressState {
+            target: Some(target),
+            initial,
+            value: if initial == 0 { 100.0 } else { 0.0 },
+        };
+    }
+
+    /// Start tracking the progress towards the target, unless the target is already tracked
+    fn track(&self, system: &System, tgt: &Value, normalizer: Normalizer, config: &PlannerConfig) {
+        let tracked = self
+            .0
+            .read()
+            .unwrap()
+            .target
+            .as_ref()
+            .is_some_and(|current| &current.target == tgt);
+        if !tracked {
+            self.start(system, tgt, normalizer, config);
+        }
+    }
+
+    /// Update the progress from the current system state
+    fn update(&self, system: &System) {
+        let mut state = self.0.write().unwrap();
+        let Some(remaining) = state.target.as_ref().and_then(|tgt| tgt.distance(system)) else {
+            return;
+        };
+
+        let progress = if state.initial == 0 {
+            100.0
+        } else {
+            100.0 * (1.0 - remaining as f32 / state.initial as f32)
+        };
+        state.value = state.value.max(progress.clamp(0.0, 100.0));
+    }
+
+    /// Mark the target as reached, if it is the tracked target
+    fn complete(&self, tgt: &Value) {
+        let mut state = self.0.write().unwrap();
+        if state
+            .target
+            .as_ref()
+            .is_some_and(|current| &current.target == tgt)
+        {
+            state.value = 100.0;
+        }
+    }
+
+    fn get(&self) -> f32 {
+        self.0.read().unwrap().value
+    }
+}
+
 /// Helper trait to implement the Typestate pattern for Worker
 pub trait WorkerState {}
 
@@ -130,6 +516,10 @@ pub trait WorkerState {}
 pub struct Uninitialized {
     domain: Domain,
     resources: Resources,
+    sensor: Option<Arc<dyn Sensor>>,
+    config: PlannerConfig,
+    action_timeout: Option<Duration>,
+    replan_policy: ReplanPolicy,
 }
 
 /// Initialized worker state
@@ -141,10 +531,15 @@ pub struct Ready {
     planner: Planner,
     system: Arc<RwLock<System>>,
     updates: broadcast::Sender<UpdateEvent>,
+    divergences: broadcast::Sender<Divergence>,
     patches: Sender<Patch>,
     writer_closed: Arc<Notify>,
     interrupt: AutoInterrupt,
     status: SeekStatus,
+    sensor: Option<Arc<dyn Sensor>>,
+    action_timeout: Option<Duration>,
+    replan_policy: ReplanPolicy,
+    progress: Progress,
 }
 
 /// Final state of a Worker
@@ -296,25 +691,153 @@ impl<O> Default for Worker<O, Uninitialized> {
 
 impl<O> Worker<O, Uninitialized> {
     /// Create a new uninitialized Worker instance
+    ///
+    /// The uninitialized worker works as a builder, jobs, resources and planner options can be
+    /// configured before providing an initial state.
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    /// use mahler::worker::{Ready, SearchStrategy, Worker};
+    ///
+    /// fn foo() {}
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker: Worker<i32, Ready> = Worker::new()
+    ///     .job("", update(foo))
+    ///     .search_strategy(SearchStrategy::a_star())
+    ///     .path_visit_limit(10)
+    ///     .max_depth(64)
+    ///     .initial_state(0)
+    ///     .unwrap();
+    /// # })
+    /// ```
     pub fn new() -> Self {
         Worker::from_inner(Uninitialized {
             domain: Domain::new(),
             resources: Resources::new(),
+            sensor: None,
+            config: PlannerConfig::default(),
+            action_timeout: None,
+            replan_policy: ReplanPolicy::default(),
         })
     }
+
+    /// Create a [`WorkerBuilder`] for the jobs in the given domain
+    ///
+    /// The builder groups the planning and execution options of the worker in a single
+    /// entrypoint. The worker returned by [`WorkerBuilder::build`] is uninitialized, so further
+    /// jobs and resources may be configured before providing an initial state.
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    /// use mahler::worker::{Domain, Ready, ReplanPolicy, SearchStrategy, Worker};
+    ///
+    /// fn foo() {}
+    ///
+    /// # tokio_test::block_on(async {
+    /// let domain = Domain::new().job("", update(foo));
+    /// let worker: Worker<i32, Ready> = Worker::builder(domain)
+    ///     .max_depth(64)
+    ///     .search_strategy(SearchStrategy::a_star())
+    ///     .replan_policy(ReplanPolicy::Limit(3))
+    ///     .build()
+    ///     .initial_state(0)
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub fn builder(domain: Domain) -> WorkerBuilder<O> {
+        let mut worker = Worker::new();
+        worker.inner.domain = domain;
+        WorkerBuilder { worker }
+    }
 }
 
-impl<O> Worker<O, Uninitialized> {
-    /// Add a [Job](`crate::task::Job`) to the worker domain
-    pub fn job(mut self, route: &'static str, job: Job) -> Self {
-        self.inner.domain = self.inner.domain.job(route, job);
+/// Builder for an uninitialized [`Worker`]
+///
+/// See [`Worker::builder`]
+pub struct WorkerBuilder<O> {
+    worker: Worker<O, Uninitialized>,
+}
+
+impl<O> WorkerBuilder<O> {
+    /// Set the maximum depth of the planner search
+    ///
+    /// See [`Worker::max_depth`]
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.worker = self.worker.max_depth(depth);
         self
     }
 
-    /// Add a list if jobs linked to the same route on the worker domain
+    /// Set the strategy used by the planner to explore the search space
     ///
-    /// This is a convenience method to simplify the configuration of multiple jobs
-    /// for the same domain
+    /// See [`Worker::search_strategy`]
+    pub fn search_strategy(mut self, strategy: SearchStrategy) -> Self {
+        self.worker = self.worker.search_strategy(strategy);
+        self
+    }
+
+    /// Set the policy for re-planning when a workflow terminates without reaching the target
+    ///
+    /// See [`Worker::replan_policy`]
+    pub fn replan_policy(mut self, policy: ReplanPolicy) -> Self {
+        self.worker = self.worker.replan_policy(policy);
+        self
+    }
+
+    /// Discard tasks that do not take the system closer to the target during planning
+    ///
+    /// See [`Worker::prune_regressive_tasks`]
+    pub fn prune_regressive_tasks(mut self, enable: bool) -> Self {
+        self.worker = self.worker.prune_regressive_tasks(enable);
+        self
+    }
+
+    /// Limit the number of actions that can act on the same path within a single plan branch
+    ///
+    /// See [`Worker::path_visit_limit`]
+    pub fn path_visit_limit(mut self, limit: u32) -> Self {
+        self.worker = self.worker.path_visit_limit(limit);
+        self
+    }
+
+    /// Skip states that were already explored by a different branch of the planner search
+    ///
+    /// See [`Worker::skip_visited_states`]
+    pub fn skip_visited_states(mut self, enable: bool) -> Self {
+        self.worker = self.worker.skip_visited_states(enable);
+        self
+    }
+
+    /// Set the policy used when comparing numbers in the state with the target
+    ///
+    /// See [`Worker::number_policy`]
+    pub fn number_policy(mut self, policy: NumberPolicy) -> Self {
+        self.worker = self.worker.number_policy(policy);
+        self
+    }
+
+    /// Set the maximum time that any action is allowed to run for during workflow execution
+    ///
+    /// See [`Worker::action_timeout`]
+    pub fn action_timeout(mut self, timeout: Duration) -> Self {
+        self.worker = self.worker.action_timeout(timeout);
+        self
+    }
+
+    /// Return the configured worker
+    pub fn build(self) -> Worker<O, Uninitialized> {
+        self.worker
+    }
+}
+
+impl<O> Worker<O, Uninitialized> {
+    /// Normalize routes and paths before matching them to jobs
+    ///
+    /// The normalizer is applied to the static segments of the routes when registering jobs and
+    /// to each segment of a copy of the path when looking up jobs for a change in the system
+    /// state. This allows, for instance, to match routes ignoring case. Path arguments are read
+    /// from the original path, and the paths of the tasks returned by methods are built from the
+    /// routes as registered.
     ///
     /// ```rust
     /// use serde::{Deserialize, Serialize};
@@ -325,87 +848,558 @@ impl<O> Worker<O, Uninitialized> {
     /// struct StateModel;
     ///
     /// fn foo() {}
-    /// fn bar() {}
     ///
     /// let worker: Worker<StateModel, Uninitialized> = Worker::new()
-    ///         .jobs("/{foo}", [update(foo), update(bar)]);
+    ///         .route_normalizer(str::to_lowercase)
+    ///         .job("/counters/{counter}", update(foo));
     /// ```
-    pub fn jobs<const N: usize>(mut self, route: &'static str, list: [Job; N]) -> Self {
-        self.inner.domain = self.inner.domain.jobs(route, list);
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any jobs have already been added to the worker
+    pub fn route_normalizer(mut self, normalizer: fn(&str) -> String) -> Self {
+        self.inner.domain = self.inner.domain.with_normalizer(normalizer);
         self
     }
 
-    /// Add a shared resource to use within tasks
+    /// Use a custom [`RouteMatcher`] to find the jobs applicable to a path
     ///
-    /// Resources are stored by [TypeId](`std::any::TypeId`),
-    /// meaning only one resource of each type is allowed. If multiple resources of the same type
-    /// are configured, only the last one will be used.
+    /// By default, routes are matched using the [matchit](https://docs.rs/matchit) syntax.
     ///
     /// ```rust
     /// use serde::{Deserialize, Serialize};
-    /// use mahler::worker::{Worker, Uninitialized};
+    /// use mahler::worker::{Worker, Uninitialized, RouteMatcher};
+    /// use mahler::task::prelude::*;
     ///
     /// #[derive(Serialize, Deserialize)]
     /// struct StateModel;
     ///
-    /// // MyConnection represents a shared resource
-    /// struct MyConnection;
+    /// // Only match paths equal to the route
+    /// #[derive(Clone, Default)]
+    /// struct ExactMatcher(Vec<String>);
     ///
-    /// let conn = MyConnection {/* .. */};
-    /// let otherconn = MyConnection { /* .. */};
+    /// impl RouteMatcher for ExactMatcher {
+    ///     fn insert(&mut self, route: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///         self.0.push(route.to_string());
+    ///         Ok(())
+    ///     }
+    ///
+    ///     fn at<'r>(&'r self, path: &str) -> Option<(&'r str, Vec<(String, String)>)> {
+    ///         self.0.iter().find(|r| *r == path).map(|r| (r.as_str(), vec![]))
+    ///     }
+    /// }
+    ///
+    /// fn foo() {}
     ///
     /// let worker: Worker<StateModel, Uninitialized> = Worker::new()
-    ///         // add conn as resource
-    ///         .resource(conn)
-    ///         // only the last assignment of a resource of the same type
-    ///         // will be used
-    ///         .resource(otherconn);
+    ///         .route_matcher(ExactMatcher::default())
+    ///         .job("/counters/one", update(foo));
     /// ```
     ///
-    /// Resources can be accessed by jobs using the [Res extractor](`crate::extract::Res`).
+    /// # Panics
     ///
-    /// ```rust
+    /// This function will panic if any jobs have already been added to the worker
+    pub fn route_matcher<M: RouteMatcher + Clone>(mut self, matcher: M) -> Self {
+        self.inner.domain = self.inner.domain.with_matcher(matcher);
+        self
+    }
+
+    /// Reject jobs that would be tried by the planner in an ambiguous order
+    ///
+    /// Jobs on the same route with the same operation and priority are tried in order of their
+    /// id, which may be unexpected. When enabled, registering a job panics if another job on the
+    /// same route has the same operation, priority and kind (action or method), requiring an
+    /// explicit tiebreak via [`Job::with_priority`](`crate::task::Job::with_priority`).
+    ///
+    /// ```rust,should_panic
+    /// use mahler::worker::{Worker, Uninitialized};
     /// use mahler::task::prelude::*;
-    /// use mahler::extract::Res;
     ///
-    /// struct MyConnection;
+    /// fn foo() {}
+    /// fn bar() {}
     ///
-    /// fn job_with_resource(res: Res<MyConnection>) {
-    ///     // Access the worker MyConnection instance by dereferencing `res`.
-    ///     // Initializing the extractor will fail if no resource of type MyConnection
-    ///     // has been assigned to the worker.
-    /// }
+    /// let worker: Worker<i32, Uninitialized> = Worker::new()
+    ///         .strict_job_priorities(true)
+    ///         .job("/{foo}", update(foo))
+    ///         .job("/{foo}", update(bar));
     /// ```
-    pub fn resource<R>(mut self, res: R) -> Self
-    where
-        R: Send + Sync + 'static,
-    {
-        self.inner.resources = self.inner.resources.with_res(res);
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any jobs have already been added to the worker
+    pub fn strict_job_priorities(mut self, enable: bool) -> Self {
+        self.inner.domain = self.inner.domain.with_strict_priorities(enable);
         self
     }
 
-    /// Provide the initial worker state
+    /// Get the operations handled by the jobs registered on each route
     ///
-    /// This moves the state of the worker to `Ready`. No further jobs or resources may
-    /// be assigned after `initial_state` has been called.
+    /// ```rust
+    /// use mahler::task::{prelude::*, Operation};
+    /// use mahler::worker::{Worker, Uninitialized};
     ///
-    /// Note that an additional input type `<I>` may be defined at this point in case
-    /// a different model is needed for the target state. See [State Type Compatibility](#state-type-compatibility).
+    /// fn foo() {}
     ///
-    /// # Errors
-    /// The method will throw a [SerializationError](`crate::errors::SerializationError`) if the
-    /// provided state cannot be converted to the internal state representation.
-    pub fn initial_state<I>(self, state: O) -> Result<Worker<O, Ready, I>, SerializationError>
-    where
+    /// let worker: Worker<i32, Uninitialized> = Worker::new().job("/{foo}", update(foo));
+    ///
+    /// // Values under the root cannot be removed
+    /// assert!(!worker.coverage().covers("/{foo}", &Operation::Delete));
+    /// ```
+    pub fn coverage(&self) -> Coverage {
+        self.inner.domain.coverage()
+    }
+
+    /// Try every registered job against a representative state
+    ///
+    /// This allows to find mistakes in the job definitions, e.g. handlers expecting a view of a
+    /// different type than the value found at the job route, before the worker is used. Each job
+    /// matching a path of the sample is dry-run using the value at the path as target, and any
+    /// panics or errors are reported as a [`JobIssue`]. Failed conditions are not reported. Jobs
+    /// whose route does not match any path of the sample are also reported, as they could not
+    /// be tried.
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use mahler::extract::View;
+    /// use mahler::task::prelude::*;
+    /// use mahler::worker::{Worker, Uninitialized};
+    ///
+    /// fn plus_one(mut counter: View<i32>) -> View<i32> {
+    ///     *counter += 1;
+    ///     counter
+    /// }
+    ///
+    /// // The handler expects a counter, but the route points to the map of counters
+    /// let worker: Worker<serde_json::Value, Uninitialized> =
+    ///     Worker::new().job("/counters", update(plus_one));
+    ///
+    /// let issues = worker.validate(json!({"counters": {"one": 1}})).unwrap();
+    /// assert_eq!(issues.len(), 1);
+    /// assert_eq!(issues[0].path, "/counters");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The method will return a [`SerializationError`] if the sample cannot be serialized.
+    pub fn validate<S: Serialize>(&self, sample: S) -> Result<Vec<JobIssue>, SerializationError> {
+        Planner::new(self.inner.domain.clone())
+            .with_config(self.inner.config.clone())
+            .validate(sample)
+    }
+
+    /// Add a [Job](`crate::task::Job`) to the worker domain
+    pub fn job(mut self, route: &'static str, job: Job) -> Self {
+        self.inner.domain = self.inner.domain.job(route, job);
+        self
+    }
+
+    /// Add a `(route, job)` pair to the worker domain
+    ///
+    /// This is meant to be used with the [`job!`](`crate::job`) macro, which validates
+    /// the route at compile time.
+    pub fn register_job(self, (route, job): (&'static str, Job)) -> Self {
+        self.job(route, job)
+    }
+
+    /// Add a list of `(route, job)` pairs to the worker domain
+    ///
+    /// This allows to configure jobs generated at runtime.
+    ///
+    /// ```rust
+    /// use mahler::worker::{Worker, Uninitialized};
+    /// use mahler::task::prelude::*;
+    ///
+    /// fn foo() {}
+    ///
+    /// let jobs = ["a", "b", "c"].map(|name| ("/{name}", update(foo).with_id(name)));
+    /// let worker: Worker<(), Uninitialized> = Worker::new().register_jobs(jobs);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic under the same conditions as [`Worker::job`]
+    pub fn register_jobs<T>(self, jobs: T) -> Self
+    where
+        T: IntoIterator<Item = (&'static str, Job)>,
+    {
+        jobs.into_iter().fold(self, Self::register_job)
+    }
+
+    /// Add a list if jobs linked to the same route on the worker domain
+    ///
+    /// This is a convenience method to simplify the configuration of multiple jobs
+    /// for the same domain
+    ///
+    /// ```rust
+    /// use serde::{Deserialize, Serialize};
+    /// use mahler::worker::{Worker, Uninitialized};
+    /// use mahler::task::prelude::*;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct StateModel;
+    ///
+    /// fn foo() {}
+    /// fn bar() {}
+    ///
+    /// // `foo` will be tried before `bar` during planning
+    /// let worker: Worker<StateModel, Uninitialized> = Worker::new()
+    ///         .jobs("/{foo}", [update(foo).with_priority(1), update(bar)]);
+    /// ```
+    pub fn jobs<const N: usize>(mut self, route: &'static str, list: [Job; N]) -> Self {
+        self.inner.domain = self.inner.domain.jobs(route, list);
+        self
+    }
+
+    /// Add a [Job](`crate::task::Job`) to the worker domain, providing default values for
+    /// the route path arguments
+    ///
+    /// Defaults are used when a method returns a task for the job without providing all the
+    /// arguments required by the route.
+    ///
+    /// ```rust
+    /// use serde::{Deserialize, Serialize};
+    /// use mahler::worker::{Worker, Uninitialized};
+    /// use mahler::task::prelude::*;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct StateModel;
+    ///
+    /// fn foo() {}
+    ///
+    /// let worker: Worker<StateModel, Uninitialized> = Worker::new()
+    ///         .job_with_defaults("/x/{region}", update(foo), [("region", "us-east")]);
+    /// ```
+    pub fn job_with_defaults<const N: usize>(
+        mut self,
+        route: &'static str,
+        job: Job,
+        defaults: [(&str, &str); N],
+    ) -> Self {
+        self.inner.domain = self.inner.domain.job_with_defaults(route, job, defaults);
+        self
+    }
+
+    /// Add a shared resource to use within tasks
+    ///
+    /// Resources are stored by [TypeId](`std::any::TypeId`),
+    /// meaning only one resource of each type is allowed. If multiple resources of the same type
+    /// are configured, only the last one will be used.
+    ///
+    /// ```rust
+    /// use serde::{Deserialize, Serialize};
+    /// use mahler::worker::{Worker, Uninitialized};
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct StateModel;
+    ///
+    /// // MyConnection represents a shared resource
+    /// struct MyConnection;
+    ///
+    /// let conn = MyConnection {/* .. */};
+    /// let otherconn = MyConnection { /* .. */};
+    ///
+    /// let worker: Worker<StateModel, Uninitialized> = Worker::new()
+    ///         // add conn as resource
+    ///         .resource(conn)
+    ///         // only the last assignment of a resource of the same type
+    ///         // will be used
+    ///         .resource(otherconn);
+    /// ```
+    ///
+    /// Resources can be accessed by jobs using the [Res extractor](`crate::extract::Res`).
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    /// use mahler::extract::Res;
+    ///
+    /// struct MyConnection;
+    ///
+    /// fn job_with_resource(res: Res<MyConnection>) {
+    ///     // Access the worker MyConnection instance by dereferencing `res`.
+    ///     // Initializing the extractor will fail if no resource of type MyConnection
+    ///     // has been assigned to the worker.
+    /// }
+    /// ```
+    pub fn resource<R>(mut self, res: R) -> Self
+    where
+        R: Send + Sync + 'static,
+    {
+        self.inner.resources = self.inner.resources.with_res(res);
+        self
+    }
+
+    /// Discard tasks that do not take the system closer to the target during planning
+    ///
+    /// When enabled, the planner only selects candidate tasks for which the resulting state is
+    /// strictly closer to the target state. Numeric values are compared by their absolute
+    /// difference to the target, any other value is considered either equal or different.
+    ///
+    /// This avoids exploring search paths that move away from the target, but it also prevents
+    /// finding workflows that require intermediate steps that temporarily move away from the
+    /// target. This is disabled by default.
+    pub fn prune_regressive_tasks(mut self, enable: bool) -> Self {
+        self.inner.config.prune_regressive = enable;
+        self
+    }
+
+    /// Limit the number of actions that can act on the same path within a single plan branch
+    ///
+    /// Loop detection only prevents the planner from applying the same task to the same state
+    /// twice, but a task may legitimately modify a path multiple times (e.g. counting up). On a
+    /// misconfigured domain this can make the search grow indefinitely. When the limit is set,
+    /// plan branches reaching the limit are discarded, and the worker reports that no workflow
+    /// was found if no other branch reaches the target. There is no limit by default.
+    pub fn path_visit_limit(mut self, limit: u32) -> Self {
+        self.inner.config.path_visit_limit = Some(limit);
+        self
+    }
+
+    /// Skip states that were already explored by a different branch of the planner search
+    ///
+    /// Different orderings of tasks may lead to the same intermediate state, causing the planner
+    /// to explore the same state multiple times. When enabled, the planner only explores each
+    /// state once. This reduces the search space for some domains, but it may prevent finding a
+    /// workflow if the first branch reaching a state fails because of [path visit
+    /// limits](`Self::path_visit_limit`) or [job groups](`crate::task::Job::with_group`). This is
+    /// disabled by default.
+    pub fn skip_visited_states(mut self, enable: bool) -> Self {
+        self.inner.config.skip_visited = enable;
+        self
+    }
+
+    /// Set the maximum depth of the planner search
+    ///
+    /// The depth of the search is the number of planning steps in a plan branch, where each
+    /// step adds one or more concurrent tasks to the plan. If a branch reaches the limit, the
+    /// planner abandons the search and the worker reports that no workflow was found. The default
+    /// maximum depth is 256.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.inner.config.max_depth = Some(depth);
+        self
+    }
+
+    /// Set the tolerance used when comparing numbers in the state with the target
+    ///
+    /// Numbers that differ by at most `epsilon` are considered equal by the planner. This
+    /// allows planning for floating point values that may not match the target exactly
+    /// after arithmetic operations or serialization. By default numbers are compared
+    /// exactly.
+    ///
+    /// This is a shorthand for setting a [`NumberPolicy::Epsilon`] policy.
+    pub fn float_epsilon(self, epsilon: f64) -> Self {
+        self.number_policy(NumberPolicy::Epsilon(epsilon))
+    }
+
+    /// Set the policy used when comparing numbers in the state with the target
+    ///
+    /// By default numbers are compared [exactly](`NumberPolicy::Exact`).
+    ///
+    /// ```rust
+    /// use mahler::worker::{Worker, Uninitialized, NumberPolicy};
+    ///
+    /// let worker: Worker<f64, Uninitialized> = Worker::new().number_policy(NumberPolicy::RoundToInt);
+    /// ```
+    pub fn number_policy(mut self, policy: NumberPolicy) -> Self {
+        self.inner.config.number_policy = policy;
+        self
+    }
+
+    /// Use a custom comparator for values at the paths matching the given pattern
+    ///
+    /// By default, the planner considers the state to be at the target when every value is
+    /// equal to the value at the same path in the target. The comparator receives the state and
+    /// target values at matching paths and returns `true` if they should be considered equal,
+    /// e.g. for timestamps that are close enough, or lists where the order does not matter.
+    ///
+    /// Patterns are [JSON pointers](https://datatracker.ietf.org/doc/html/rfc6901), where a `*`
+    /// token matches any single token of the path.
+    ///
+    /// ```rust
+    /// use serde_json::Value;
+    /// use mahler::worker::{Worker, Uninitialized};
+    ///
+    /// // Compare the service tags ignoring their order
+    /// let worker: Worker<Value, Uninitialized> = Worker::new()
+    ///     .path_comparator("/services/*/tags", |cur, tgt| {
+    ///         let (Some(cur), Some(tgt)) = (cur.as_array(), tgt.as_array()) else {
+    ///             return cur == tgt;
+    ///         };
+    ///         cur.len() == tgt.len() && cur.iter().all(|tag| tgt.contains(tag))
+    ///     });
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the pattern is not a valid JSON pointer
+    pub fn path_comparator<F>(mut self, pattern: &str, comparator: F) -> Self
+    where
+        F: Fn(&Value, &Value) -> bool + Send + Sync + 'static,
+    {
+        self.inner
+            .config
+            .path_comparators
+            .insert(pattern, Arc::new(comparator));
+        self
+    }
+
+    /// Set the strategy used by the planner to explore the search space
+    ///
+    /// By default the planner uses a [depth first](`SearchStrategy::DepthFirst`) search,
+    /// trying jobs in order of path, operation and priority.
+    ///
+    /// ```rust
+    /// use serde::{Deserialize, Serialize};
+    /// use mahler::worker::{Worker, Uninitialized, SearchStrategy};
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct StateModel;
+    ///
+    /// let worker: Worker<StateModel, Uninitialized> = Worker::new()
+    ///         .search_strategy(SearchStrategy::a_star());
+    /// ```
+    pub fn search_strategy(mut self, strategy: SearchStrategy) -> Self {
+        self.inner.config.strategy = strategy;
+        self
+    }
+
+    /// Set a [`Sensor`] to read the system state from
+    ///
+    /// The system state is refreshed from the sensor before each planning cycle. The
+    /// initial state provided via [`Worker::initial_state`] will be replaced by the sensor
+    /// state on the first cycle.
+    pub fn sensor<S: Sensor>(mut self, sensor: S) -> Self {
+        self.inner.sensor = Some(Arc::new(sensor));
+        self
+    }
+
+    /// Set the maximum time that any action is allowed to run for during workflow execution
+    ///
+    /// The limit applies to every action, independently of any timeouts set by the action
+    /// effects. If an action does not terminate in time, it is cancelled and fails with
+    /// [`Error::Timeout`](`crate::task::Error::Timeout`). The final changes of a timed out action
+    /// are not applied, but intermediate changes already reported by a
+    /// [streaming](`crate::task::Effect::with_io_stream`) effect are kept, as when the stream
+    /// yields an error. As with a condition failure, the worker then re-plans from the current
+    /// state. There is no timeout by default.
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use mahler::worker::{Worker, Uninitialized};
+    ///
+    /// let worker: Worker<i32, Uninitialized> =
+    ///     Worker::new().action_timeout(Duration::from_secs(30));
+    /// ```
+    pub fn action_timeout(mut self, timeout: Duration) -> Self {
+        self.inner.action_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the policy for re-planning when a workflow terminates without reaching the target
+    ///
+    /// By default the worker re-plans until the target is [reached](`ReplanPolicy::Always`).
+    ///
+    /// ```rust
+    /// use mahler::worker::{Worker, Uninitialized, ReplanPolicy};
+    ///
+    /// let worker: Worker<i32, Uninitialized> = Worker::new().replan_policy(ReplanPolicy::Limit(3));
+    /// ```
+    pub fn replan_policy(mut self, policy: ReplanPolicy) -> Self {
+        self.inner.replan_policy = policy;
+        self
+    }
+
+    /// Provide the initial worker state
+    ///
+    /// This moves the state of the worker to `Ready`. No further jobs or resources may
+    /// be assigned after `initial_state` has been called.
+    ///
+    /// Note that an additional input type `<I>` may be defined at this point in case
+    /// a different model is needed for the target state. See [State Type Compatibility](#state-type-compatibility).
+    ///
+    /// # Errors
+    /// The method will throw a [SerializationError](`crate::errors::SerializationError`) if the
+    /// provided state cannot be converted to the internal state representation.
+    pub fn initial_state<I>(self, state: O) -> Result<Worker<O, Ready, I>, SerializationError>
+    where
         O: Serialize,
     {
+        let system = System::try_from(state)?;
+        Ok(self.with_system(system))
+    }
+
+    /// Provide the initial worker state from a serialized value, transforming it first
+    /// with the given migration function
+    ///
+    /// This allows to initialize the worker from a state persisted with a previous version
+    /// of the state model.
+    ///
+    /// ```rust
+    /// use serde::{Deserialize, Serialize};
+    /// use serde_json::{json, Value};
+    /// use mahler::worker::{Worker, Ready};
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct StateModel {
+    ///     counter: i32,
+    /// }
+    ///
+    /// #[derive(Debug, thiserror::Error)]
+    /// #[error("the persisted state has no counter")]
+    /// struct MissingCounter;
+    ///
+    /// # tokio_test::block_on(async {
+    /// // The field was previously called `count`
+    /// let persisted = json!({"count": 1});
+    ///
+    /// let worker: Worker<StateModel, Ready> = Worker::new()
+    ///     .initial_state_with_migration(persisted, |mut state| -> Result<Value, MissingCounter> {
+    ///         let count = state
+    ///             .as_object_mut()
+    ///             .and_then(|obj| obj.remove("count"))
+    ///             .ok_or(MissingCounter)?;
+    ///         state["counter"] = count;
+    ///         Ok(state)
+    ///     })
+    ///     .unwrap();
+    ///
+    /// let state = worker.state().await.unwrap();
+    /// assert_eq!(state.counter, 1);
+    /// # })
+    /// ```
+    ///
+    /// # Errors
+    /// The method will throw a [`MigrationError::Migration`] with the error returned by the
+    /// migration function if the migration fails, or a [`MigrationError::Serialization`] if the
+    /// migrated state cannot be deserialized into the state model.
+    pub fn initial_state_with_migration<I, F, E>(
+        self,
+        state: Value,
+        migrate: F,
+    ) -> Result<Worker<O, Ready, I>, MigrationError<E>>
+    where
+        O: DeserializeOwned,
+        F: FnOnce(Value) -> Result<Value, E>,
+    {
+        let mut system = System::try_from(state).map_err(SerializationError::from)?;
+        system.migrate(migrate).map_err(MigrationError::Migration)?;
+
+        // Make sure the migrated state is valid for the model
+        system.state::<O>().map_err(SerializationError::from)?;
+
+        Ok(self.with_system(system))
+    }
+
+    fn with_system<I>(self, system: System) -> Worker<O, Ready, I> {
         let Uninitialized {
             domain,
             resources: env,
-            ..
+            sensor,
+            config,
+            action_timeout,
+            replan_policy,
         } = self.inner;
 
-        let system = System::try_from(state).map(|s| s.with_resources(env))?;
+        let system = system.with_resources(env);
 
         // Shared system protected by RwLock
         let system = Arc::new(RwLock::new(system));
@@ -420,10 +1414,17 @@ impl<O> Worker<O, Uninitialized> {
         // Broadcast channel for state updates
         let (updates, _) = broadcast::channel(1);
 
+        // Broadcast channel for changes diverging from the plan
+        let (divergences, _) = broadcast::channel(16);
+
+        // Progress towards the target, updated on every change
+        let progress = Progress::default();
+
         // Spawn system writer task
         {
             let sys_writer = Arc::clone(&system);
             let broadcast = updates.clone();
+            let progress = progress.clone();
             tokio::spawn(
                 async move {
                     while let Some(mut msg) = rx.recv().await {
@@ -439,6 +1440,7 @@ impl<O> Worker<O, Uninitialized> {
                             notify.notify_one();
                             break;
                         }
+                        progress.update(&system);
 
                         // Notify the change over the broadcast channel
                         let _ = broadcast.send(UpdateEvent);
@@ -451,15 +1453,20 @@ impl<O> Worker<O, Uninitialized> {
             );
         }
 
-        Ok(Worker::from_inner(Ready {
-            planner: Planner::new(domain),
+        Worker::from_inner(Ready {
+            planner: Planner::new(domain).with_config(config),
             system,
             updates,
+            divergences,
             patches: tx,
             writer_closed,
             interrupt: AutoInterrupt::default(),
             status: SeekStatus::Success,
-        }))
+            sensor,
+            action_timeout,
+            replan_policy,
+            progress,
+        })
     }
 }
 
@@ -537,8 +1544,39 @@ impl<O, I> Worker<O, Ready, I> {
     where
         O: DeserializeOwned,
     {
-        let system = self.inner.system.read().await;
-        let state = system.state()?;
+        self.current_state().await
+    }
+
+    /// Read the current system state from the worker as any type `<S>`
+    ///
+    /// This allows to read the state, or a compatible view of it, using a different type than
+    /// the worker output or input types.
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use serde_json::Value;
+    /// use mahler::worker::{Worker, Ready};
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker: Worker<HashMap<String, i32>, Ready> = Worker::new()
+    ///     .initial_state(HashMap::from([("one".to_string(), 1)]))
+    ///     .unwrap();
+    ///
+    /// let state: Value = worker.current_state().await.unwrap();
+    /// assert_eq!(state, serde_json::json!({"one": 1}));
+    /// # })
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The method will throw a [SerializationError](`crate::errors::SerializationError`) if the
+    /// internal state cannot be deserialized into the type `<S>`
+    pub async fn current_state<S>(&self) -> Result<S, SerializationError>
+    where
+        S: DeserializeOwned,
+    {
+        let system = self.inner.system.read().await;
+        let state = system.state()?;
         Ok(state)
     }
 
@@ -584,9 +1622,7 @@ impl<O, I> Worker<O, Ready, I> {
     where
         I: DeserializeOwned,
     {
-        let system = self.inner.system.read().await;
-        let state = system.state()?;
-        Ok(state)
+        self.current_state().await
     }
 
     /// Returns a stream of updated states after each system change
@@ -629,6 +1665,67 @@ impl<O, I> Worker<O, Ready, I> {
         follow_worker(self.inner.updates.clone(), Arc::clone(&self.inner.system))
     }
 
+    /// Returns a stream of the changes applied by tasks that diverge from the planned changes
+    ///
+    /// A [`Divergence`] is reported every time the effectful part of a task returns
+    /// different changes than the ones predicted during planning. The worker applies the
+    /// actual changes to the system. Like [`follow`](Self::follow), the stream is best effort,
+    /// meaning events may be missed if the receiver lags behind.
+    pub fn follow_divergences(&self) -> impl Stream<Item = Divergence> {
+        FollowStream::new(
+            BroadcastStream::new(self.inner.divergences.subscribe()).filter_map(|res| res.ok()),
+        )
+    }
+
+    /// Returns a stream of the worker [progress](Self::progress) after each system change
+    ///
+    /// Like [`follow`](Self::follow), the stream is best effort, meaning updates may be missed if
+    /// the receiver lags behind.
+    pub fn follow_progress(&self) -> impl Stream<Item = f32> {
+        let progress = self.inner.progress.clone();
+        FollowStream::new(
+            BroadcastStream::new(self.inner.updates.subscribe())
+                .filter_map(move |res| res.ok().map(|_| progress.get())),
+        )
+    }
+
+    /// Return the progress towards the last target as a percentage between 0 and 100
+    ///
+    /// The progress is calculated from the number of differing values between the state
+    /// and the target when [`seek_target`](Self::seek_target) was called, and the number of
+    /// values that are still different after every change. The distance to the target may
+    /// temporarily grow during the search, e.g. after a re-plan, but the reported progress
+    /// never decreases.
+    ///
+    /// It will return `100.0` for a newly initialized `Worker`.
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    /// use mahler::extract::{View, Target};
+    /// use mahler::worker::{Worker, SeekTarget};
+    ///
+    /// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///    if *counter < tgt {
+    ///        *counter += 1;
+    ///    }
+    ///    counter
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker = Worker::new()
+    ///     .job("", update(plus_one))
+    ///     .initial_state(0)
+    ///     .seek_target(2)
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// assert_eq!(worker.progress(), 100.0);
+    /// # })
+    /// ```
+    pub fn progress(&self) -> f32 {
+        self.inner.progress.get()
+    }
+
     /// Return the result of the last worker run
     ///
     /// It will return `SeekStatus::Success` for a newly initialized `Worker`
@@ -636,7 +1733,54 @@ impl<O, I> Worker<O, Ready, I> {
         &self.inner.status
     }
 
-    #[instrument(name = "seek_target", skip_all, fields(return=field::Empty), err)]
+    /// Apply changes made to the system by an external actor
+    ///
+    /// Unlike a [`Sensor`], which the worker polls before each planning cycle, this allows
+    /// to push changes into the worker as they are observed. The patch is applied to the
+    /// internal system state and followers are notified, so the next plan will account for
+    /// the new state.
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use serde_json::json;
+    /// use mahler::worker::{Worker, Ready};
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker: Worker<i32, Ready> = Worker::new().initial_state(0).unwrap();
+    ///
+    /// // The counter was modified outside the worker
+    /// let changes: Patch =
+    ///     serde_json::from_value(json!([{ "op": "replace", "path": "", "value": 1 }])).unwrap();
+    /// worker.observe(changes).await.unwrap();
+    ///
+    /// assert_eq!(worker.state().await.unwrap(), 1);
+    /// # })
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The method will return a [`PatchError`](`json_patch::PatchError`) if the patch cannot be
+    /// applied to the current state. The system state is left unmodified in that case.
+    pub async fn observe(&self, patch: Patch) -> Result<(), json_patch::PatchError> {
+        let Ready {
+            system,
+            updates,
+            progress,
+            ..
+        } = &self.inner;
+
+        {
+            let mut system = system.write().await;
+            system.patch(patch)?;
+            progress.update(&system);
+        }
+
+        // Notify the change over the broadcast channel
+        let _ = updates.send(UpdateEvent);
+
+        Ok(())
+    }
+
     /// Trigger system changes by providing a new target state and interrupt signal
     ///
     /// When called, this method tells the worker to look for a plan for the given
@@ -665,16 +1809,36 @@ impl<O, I> Worker<O, Ready, I> {
     where
         I: Serialize + DeserializeOwned,
     {
-        let cur_span = Span::current();
         let tgt = serde_json::to_value(tgt).map_err(SerializationError::from)?;
+        {
+            let system = self.inner.system.read().await;
+            self.inner
+                .progress
+                .start(&system, &tgt, normalize::<I>, self.inner.planner.config());
+        }
+        self.seek(tgt, interrupt).await
+    }
+
+    /// Seek the serialized target without resetting the progress
+    #[instrument(name = "seek_target", skip_all, fields(return=field::Empty), err)]
+    async fn seek(self, tgt: Value, interrupt: Interrupt) -> Result<Worker<O, Ready, I>, FatalError>
+    where
+        I: Serialize + DeserializeOwned,
+    {
+        let cur_span = Span::current();
 
         let Ready {
             planner,
             system,
             updates,
+            divergences,
             writer_closed,
             patches,
             interrupt: drop_interrupt,
+            sensor,
+            action_timeout,
+            replan_policy,
+            progress,
             ..
         } = self.inner;
 
@@ -682,32 +1846,47 @@ impl<O, I> Worker<O, Ready, I> {
             TargetReached,
             WorkflowCompleted,
             Interrupted,
+            ReplanLimitReached,
         }
 
         enum SeekError {
             Runtime(AggregateError<TaskError>),
             Planning(PlannerError),
+            Sensing(SenseError),
         }
 
-        async fn find_and_run_workflow<I: Serialize + DeserializeOwned>(
+        async fn find_workflow<I: Serialize + DeserializeOwned>(
             planner: &Planner,
             sys: &Arc<RwLock<System>>,
             tgt: &Value,
             channel: &Sender<Patch>,
-            sigint: &Interrupt,
-        ) -> Result<SeekResult, SeekError> {
-            let workflow = {
-                let system = sys.read().await;
-                planner
-                    .find_workflow::<I>(&system, tgt)
-                    .map_err(SeekError::Planning)?
-            };
-
-            if workflow.is_empty() {
-                return Ok(SeekResult::TargetReached);
+            sensor: Option<&dyn Sensor>,
+        ) -> Result<Workflow, SeekError> {
+            // Refresh the state before planning
+            if let Some(sensor) = sensor {
+                sense_system(sensor, sys, channel)
+                    .await
+                    .map_err(SeekError::Sensing)?;
             }
 
+            // Store the state before planning as the baseline for the workflow
+            let mut system = sys.write().await;
+            system.set_baseline();
+            let system = system.downgrade();
+            planner
+                .find_workflow::<I>(&system, tgt)
+                .map_err(SeekError::Planning)
+        }
+
+        async fn run_workflow(
+            workflow: Workflow,
+            sys: &Arc<RwLock<System>>,
+            channel: &Sender<Patch>,
+            sigint: &Interrupt,
+            action_timeout: Option<Duration>,
+        ) -> Result<SeekResult, SeekError> {
             let status = workflow
+                .with_action_timeout(action_timeout)
                 .execute(sys, channel.clone(), sigint.clone())
                 .await
                 .map_err(SeekError::Runtime)?;
@@ -727,8 +1906,15 @@ impl<O, I> Worker<O, Ready, I> {
             let workflow_interrupt = interrupt.clone();
             let sys_reader = Arc::clone(&system);
             let changes = patches.clone();
+            let sensor = sensor.clone();
+            let progress = progress.clone();
+            let divergence_sender = divergences.clone();
             tokio::spawn(async move {
+                let mut replans = 0;
                 loop {
+                    // Every workflow after the first one is the result of a re-plan
+                    let can_run = replans == 0 || replan_policy.allows(replans - 1);
+                    replans += 1;
                     select! {
                         biased;
 
@@ -748,9 +1934,23 @@ impl<O, I> Worker<O, Ready, I> {
                             return Ok((planner, SeekStatus::Interrupted));
                         }
 
-                        res = find_and_run_workflow::<I>(&planner, &sys_reader, &tgt, &changes, &workflow_interrupt) => {
+                        res = async {
+                            let workflow = find_workflow::<I>(&planner, &sys_reader, &tgt, &changes, sensor.as_deref()).await?;
+                            if workflow.is_empty() {
+                                return Ok(SeekResult::TargetReached);
+                            }
+
+                            // The planner still runs once the re-plan limit is exhausted to
+                            // confirm whether the last workflow reached the target
+                            if !can_run {
+                                return Ok(SeekResult::ReplanLimitReached);
+                            }
+
+                            run_workflow(workflow.with_divergences(divergence_sender.clone()), &sys_reader, &changes, &workflow_interrupt, action_timeout).await
+                        } => {
                             match res {
                                 Ok(SeekResult::TargetReached) => {
+                                    progress.complete(&tgt);
                                     cur_span.record("return", "success");
                                     return Ok((planner, SeekStatus::Success));
                                 }
@@ -759,28 +1959,21 @@ impl<O, I> Worker<O, Ready, I> {
                                     cur_span.record("return", "interrupted");
                                     return Ok((planner, SeekStatus::Interrupted));
                                 }
-                                Err(SeekError::Planning(PlannerError::NotFound)) =>  return Ok((planner, SeekStatus::NotFound)),
-                                Err(SeekError::Planning(PlannerError::Serialization(e))) =>  return Err(e)?,
-                                Err(SeekError::Planning(PlannerError::Internal(e))) =>  return Err(e)?,
-                                Err(SeekError::Planning(PlannerError::Task(e))) => return Err(e)?,
+                                Ok(SeekResult::ReplanLimitReached) => {
+                                    cur_span.record("return", "replan_limit_reached");
+                                    return Ok((planner, SeekStatus::ReplanLimitReached));
+                                }
+                                Err(SeekError::Planning(e)) => match plan_error(e) {
+                                    PlanError::NotFound(e) => return Ok((planner, SeekStatus::NotFound(e))),
+                                    PlanError::Fatal(e) => return Err(e),
+                                },
+                                Err(SeekError::Sensing(SenseError::Internal(e))) => return Err(e)?,
+                                Err(SeekError::Sensing(SenseError::Sensor(e))) => {
+                                    cur_span.record("return", "aborted");
+                                    return Ok((planner, SeekStatus::Aborted(vec![e])));
+                                }
                                 Err(SeekError::Runtime(err)) => {
-                                    let mut io = Vec::new();
-                                    let mut other = Vec::new();
-                                    let AggregateError (all) = err;
-                                    for e in all.into_iter() {
-                                        match e {
-                                            TaskError::IO(re) => io.push(re),
-                                            TaskError::ConditionFailed => {},
-                                            _ => other.push(e)
-                                        }
-
-                                    }
-
-                                    // If there are non-IO errors, there is
-                                    // probably a bug somewhere
-                                    if !other.is_empty() {
-                                        return Err(InternalError::from(anyhow!(AggregateError::from(other))))?;
-                                    }
+                                    let io = io_errors(err)?;
 
                                     // Abort if there are any runtime errors as those
                                     // should be recoverable
@@ -811,10 +2004,15 @@ impl<O, I> Worker<O, Ready, I> {
             planner,
             system,
             updates,
+            divergences,
             patches,
             writer_closed,
             interrupt: AutoInterrupt::default(),
             status,
+            sensor,
+            action_timeout,
+            replan_policy,
+            progress,
         }))
     }
 
@@ -840,100 +2038,1351 @@ impl<O, I> Worker<O, Ready, I> {
     {
         self.seek_with_interrupt(tgt, Interrupt::new()).await
     }
-}
 
-#[async_trait]
-impl<O: Send, I: Serialize + DeserializeOwned + Send> SeekTarget<O, I>
-    for Result<Worker<O, Ready, I>, SerializationError>
-{
-    async fn seek_target(self, tgt: I) -> Result<Worker<O, Ready, I>, FatalError> {
-        let worker = self?;
-        worker.seek_target(tgt).await
+    /// Continuously reconcile the system towards the targets received from a stream
+    ///
+    /// For every target received, the worker looks for a plan from the current state and
+    /// executes it, as with [`seek_target`](Self::seek_target). If a new target is received
+    /// while a workflow is still running, the running workflow is interrupted and the worker
+    /// re-plans towards the new target. Once a target is reached (or the search terminates
+    /// for any other reason), the worker waits for the next target on the stream.
+    ///
+    /// The method returns when the stream terminates and the seek for the last target
+    /// finishes. The returned worker [status](Self::status) is the result of that last seek.
+    ///
+    /// ```rust,no_run
+    /// use serde::{Deserialize, Serialize};
+    /// use mahler::worker::Worker;
+    ///
+    /// #[derive(Debug, Serialize, Deserialize)]
+    /// struct SystemState;
+    ///
+    /// # tokio_test::block_on(async move {
+    /// let worker = Worker::new()
+    ///     // todo: configure jobs
+    ///     .initial_state(SystemState {/* .. */})
+    ///     .unwrap();
+    ///
+    /// // receive targets from some external source
+    /// let targets = tokio_stream::iter(vec![SystemState {/* .. */}]);
+    ///
+    /// let worker = worker.reconcile(targets).await.unwrap();
+    /// # })
+    /// ```
+    ///
+    /// # Errors
+    /// The method will result in a [`FatalError`] if any of the seek operations fail with an
+    /// error. See [`seek_with_interrupt`](Self::seek_with_interrupt).
+    pub async fn reconcile<S>(self, targets: S) -> Result<Worker<O, Ready, I>, FatalError>
+    where
+        I: Serialize + DeserializeOwned,
+        S: Stream<Item = I>,
+    {
+        tokio::pin!(targets);
+
+        let mut worker = self;
+        let Some(mut tgt) = targets.next().await else {
+            return Ok(worker);
+        };
+
+        loop {
+            let interrupt = Interrupt::new();
+            let seek = worker.seek_with_interrupt(tgt, interrupt.clone());
+            tokio::pin!(seek);
+
+            let (res, next) = select! {
+                res = &mut seek => (res, targets.next().await),
+                next = targets.next() => {
+                    // Preempt the running workflow if the target changed,
+                    // otherwise let the seek finish
+                    if next.is_some() {
+                        debug!("target changed, interrupting the running workflow");
+                        interrupt.trigger();
+                    }
+                    (seek.await, next)
+                }
+            };
+
+            worker = res?;
+            match next {
+                Some(next) => tgt = next,
+                None => return Ok(worker),
+            }
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
-    use std::time::Duration;
+    /// Reconcile the system towards multiple targets in order of priority
+    ///
+    /// Targets are partial states, applied to the current state as a [JSON Merge
+    /// Patch](https://datatracker.ietf.org/doc/html/rfc7396). Targets with higher priority are
+    /// sought first. Each target is merged with the targets already reached, so reaching a lower
+    /// priority target never undoes the changes made for a more important one. Targets for
+    /// which no workflow is found, or that conflict with a more important target, are skipped with
+    /// a warning, allowing the worker to make progress on the remaining targets.
+    ///
+    /// The method returns after all targets have been tried, or if a seek is interrupted or
+    /// aborted. The returned worker [status](Self::status) is the result of the last seek.
+    ///
+    /// ```rust,no_run
+    /// use serde_json::{json, Value};
+    /// use mahler::worker::{Worker, Ready};
+    ///
+    /// # tokio_test::block_on(async move {
+    /// let worker: Worker<Value, Ready> = Worker::new()
+    ///     // todo: configure jobs
+    ///     .initial_state(json!({"a": 0, "b": 0}))
+    ///     .unwrap();
+    ///
+    /// // `a` is reconciled before `b`
+    /// let worker = worker
+    ///     .reconcile_all(vec![(json!({"b": 1}), 1), (json!({"a": 1}), 10)])
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    ///
+    /// # Errors
+    /// The method will result in a [`FatalError`] if a target cannot be converted to the target
+    /// type or if any of the seek operations fail with an error. See
+    /// [`seek_with_interrupt`](Self::seek_with_interrupt).
+    pub async fn reconcile_all(
+        self,
+        targets: Vec<(Value, u8)>,
+    ) -> Result<Worker<O, Ready, I>, FatalError>
+    where
+        I: Serialize + DeserializeOwned,
+    {
+        // The sort is stable, so targets with the same priority
+        // are sought in the order given
+        let mut targets = targets;
+        targets.sort_by_key(|(_, priority)| Reverse(*priority));
+
+        // Track the progress towards all the targets that do not conflict
+        // with a more important one
+        let mut combined: Option<Value> = None;
+        for (tgt, _) in targets.iter() {
+            let mut merged = combined.clone().unwrap_or_else(|| tgt.clone());
+            if merge_target(&mut merged, tgt) {
+                combined = Some(merged);
+            }
+        }
+        if let Some(combined) = combined {
+            let system = self.inner.system.read().await;
+            let mut final_state = system.clone();
+            final_state.patch_merge(&combined);
+            if let Some(tgt) = normalize::<I>(&final_state) {
+                self.inner.progress.start(
+                    &system,
+                    &tgt,
+                    normalize::<I>,
+                    self.inner.planner.config(),
+                );
+            }
+        }
+
+        let mut worker = self;
+        let mut reached: Option<Value> = None;
+        for (tgt, priority) in targets {
+            let mut merged = reached.clone().unwrap_or_else(|| tgt.clone());
+            if !merge_target(&mut merged, &tgt) {
+                warn!(
+                    priority,
+                    "target conflicts with a higher priority target, skipping"
+                );
+                continue;
+            }
+
+            // Apply the targets to the current state, so values not
+            // referenced by any of the targets are left unchanged
+            let next = {
+                let mut system = worker.inner.system.read().await.clone();
+                system.patch_merge(&merged);
+                system.state::<I>().map_err(SerializationError::from)?
+            };
+            let next = serde_json::to_value(next).map_err(SerializationError::from)?;
+            worker = worker.seek(next, Interrupt::new()).await?;
+            match worker.status() {
+                SeekStatus::Success => reached = Some(merged),
+                SeekStatus::NotFound(_) => {
+                    warn!(priority, "target unreachable, skipping");
+                }
+                SeekStatus::ReplanLimitReached => {
+                    warn!(
+                        priority,
+                        "target not reached within the re-plan limit, skipping"
+                    );
+                }
+                SeekStatus::Interrupted | SeekStatus::Aborted(_) => break,
+            }
+        }
+
+        Ok(worker)
+    }
+
+    /// Perform a single planning and execution cycle towards the given target
+    ///
+    /// Unlike [`seek_target`](Self::seek_target), which loops until the target is reached,
+    /// this method looks for a workflow once, executes it and returns. This allows to integrate
+    /// the Worker with an external scheduler that controls the timing between cycles.
+    ///
+    /// The method returns [`StepOutcome::Reached`] if no changes are needed to reach the target,
+    /// and [`StepOutcome::Progressed`] after a workflow is executed, as conditions may have
+    /// changed during execution. Callers should keep calling `run_once` until the target is
+    /// reached or another outcome is returned.
+    ///
+    /// ```rust,no_run
+    /// use serde::{Deserialize, Serialize};
+    /// use mahler::worker::{Worker, StepOutcome};
+    ///
+    /// #[derive(Debug, Serialize, Deserialize)]
+    /// struct SystemState;
+    ///
+    /// # tokio_test::block_on(async move {
+    /// let mut worker = Worker::new()
+    ///     // todo: configure jobs
+    ///     .initial_state(SystemState {/* .. */})
+    ///     .unwrap();
+    ///
+    /// while worker.run_once(SystemState {/* .. */}).await.unwrap() == StepOutcome::Progressed {
+    ///     // wait before the next cycle
+    /// }
+    /// # })
+    /// ```
+    ///
+    /// # Errors
+    /// The method will result in a [`FatalError`] if a serialization issue occurs while converting
+    /// between state types, or there is an unexpected error during planning or execution.
+    pub async fn run_once(&mut self, tgt: I) -> Result<StepOutcome, FatalError>
+    where
+        I: Serialize + DeserializeOwned,
+    {
+        let tgt = serde_json::to_value(tgt).map_err(SerializationError::from)?;
+
+        let Ready {
+            planner,
+            system,
+            patches,
+            sensor,
+            progress,
+            ..
+        } = &self.inner;
+
+        // Repeated calls for the same target continue tracking the progress
+        {
+            let system = system.read().await;
+            progress.track(&system, &tgt, normalize::<I>, planner.config());
+        }
+
+        // Refresh the state before planning
+        if let Some(sensor) = sensor {
+            match sense_system(sensor.as_ref(), system, patches).await {
+                Ok(()) => {}
+                Err(SenseError::Sensor(e)) => return Ok(StepOutcome::Aborted(vec![e])),
+                Err(SenseError::Internal(e)) => return Err(e)?,
+            }
+        }
+
+        let workflow = {
+            // Store the state before planning as the baseline for the workflow
+            let mut system = system.write().await;
+            system.set_baseline();
+            let system = system.downgrade();
+            match planner
+                .find_workflow::<I>(&system, &tgt)
+                .map_err(plan_error)
+            {
+                Ok(workflow) => workflow,
+                Err(PlanError::NotFound(e)) => return Ok(StepOutcome::NotFound(e)),
+                Err(PlanError::Fatal(e)) => return Err(e),
+            }
+        };
+
+        if workflow.is_empty() {
+            progress.complete(&tgt);
+        }
+
+        self.execute_workflow(workflow).await
+    }
+
+    /// Run a planner search on a copy of the current worker state
+    async fn with_current_state<'a, T, F>(&'a self, search: F) -> T
+    where
+        F: FnOnce(&'a Planner, &System) -> T,
+    {
+        let system = self.inner.system.read().await.clone();
+        search(&self.inner.planner, &system)
+    }
+
+    /// Find a workflow to the target from the current worker state, without executing it
+    ///
+    /// Unlike [`seek_target`](Self::seek_target), which executes the workflow found by the
+    /// planner, this allows to inspect the workflow before running it, e.g. with
+    /// [`run_workflow`](Self::run_workflow). If no work is needed to reach the target, this
+    /// returns [`PlanResult::Reached`]. See [`PlanResult`] for an example.
+    ///
+    /// # Errors
+    ///
+    /// The method will return [`PlanError::NotFound`] if no workflow to the target can be found,
+    /// or a [`PlanError::Fatal`] if an unexpected error happens during planning.
+    pub async fn find_plan(&self, tgt: I) -> Result<PlanResult, PlanError>
+    where
+        I: Serialize + DeserializeOwned,
+    {
+        let tgt = serde_json::to_value(tgt).map_err(SerializationError::from)?;
+        self.with_current_state(|planner, system| planner.find_plan::<I>(system, &tgt))
+            .await
+            .map_err(plan_error)
+    }
+
+    /// Find a workflow along with the predicted system states after each task
+    ///
+    /// This allows to step through the states the planner expects the system to go through
+    /// when executing the workflow. States are listed in the order tasks are visited by
+    /// [`Dag::values`](`crate::Dag::values`).
+    ///
+    /// # Example
+    /// ```rust
+    /// use serde_json::json;
+    /// use mahler::task::prelude::*;
+    /// use mahler::extract::{View, Target};
+    /// use mahler::worker::Worker;
+    ///
+    /// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///    if *counter < tgt {
+    ///        *counter += 1;
+    ///    }
+    ///    counter
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker = Worker::new()
+    ///                 .job("", update(plus_one))
+    ///                 .initial_state(0)
+    ///                 .unwrap();
+    /// let (_, states) = worker.find_workflow_with_states(2).await.unwrap();
+    ///
+    /// assert_eq!(states, vec![json!(1), json!(2)]);
+    /// # })
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The method will return [`PlanError::NotFound`] if no workflow to the target can be found,
+    /// or a [`PlanError::Fatal`] if an unexpected error happens during planning.
+    pub async fn find_workflow_with_states(
+        &self,
+        tgt: I,
+    ) -> Result<(Workflow, Vec<Value>), PlanError>
+    where
+        I: Serialize + DeserializeOwned,
+    {
+        let tgt = serde_json::to_value(tgt).map_err(SerializationError::from)?;
+        self.with_current_state(|planner, system| {
+            planner.find_workflow_with_states::<I>(system, &tgt)
+        })
+        .await
+        .map_err(plan_error)
+    }
+
+    /// Find a workflow to the target, along with statistics on how each job was used
+    /// during the search
+    ///
+    /// Statistics are returned even if no workflow is found, and are indexed by job id. This
+    /// allows to identify jobs that are matched by changes to the target but never selected.
+    ///
+    /// # Example
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    /// use mahler::extract::{View, Target};
+    /// use mahler::worker::Worker;
+    ///
+    /// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///    if *counter < tgt {
+    ///        *counter += 1;
+    ///    }
+    ///    counter
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker = Worker::new()
+    ///                 .job("", update(plus_one).with_id("plus_one"))
+    ///                 .initial_state(0)
+    ///                 .unwrap();
+    /// let (res, stats) = worker.find_plan_with_job_stats(2).await;
+    ///
+    /// assert!(res.is_ok());
+    /// assert_eq!(stats["plus_one"].selected, 2);
+    /// # })
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The search result is the same as returned by [`find_plan`](Self::find_plan). No
+    /// statistics are collected if the target cannot be serialized.
+    pub async fn find_plan_with_job_stats(
+        &self,
+        tgt: I,
+    ) -> (Result<PlanResult, PlanError>, BTreeMap<String, JobStats>)
+    where
+        I: Serialize + DeserializeOwned,
+    {
+        let tgt = match serde_json::to_value(tgt) {
+            Ok(tgt) => tgt,
+            Err(e) => return (Err(SerializationError::from(e).into()), BTreeMap::new()),
+        };
+        let (res, stats) = self
+            .with_current_state(|planner, system| {
+                planner.find_plan_with_job_stats::<I>(system, &tgt)
+            })
+            .await;
+        (res.map_err(plan_error), stats)
+    }
+
+    /// Iterate over the workflows to the target, in the order they are found by the planner
+    ///
+    /// The search is resumed every time a new workflow is requested, so callers can stop
+    /// early or apply custom selection between alternative workflows. The iterator ends when
+    /// there are no more workflows to the target, or after yielding an error.
+    ///
+    /// # Example
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    /// use mahler::extract::{View, Target};
+    /// use mahler::worker::Worker;
+    ///
+    /// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///    if *counter < tgt {
+    ///        *counter += 1;
+    ///    }
+    ///    counter
+    /// }
+    ///
+    /// fn plus_two(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///    if tgt - *counter > 1 {
+    ///        *counter += 2;
+    ///    }
+    ///    counter
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker = Worker::new()
+    ///                 .job("", update(plus_one).with_description(|| "+1"))
+    ///                 .job("", update(plus_two).with_description(|| "+2"))
+    ///                 .initial_state(0)
+    ///                 .unwrap();
+    ///
+    /// let workflows = worker
+    ///     .plan_iter(2)
+    ///     .await
+    ///     .unwrap()
+    ///     .take(2)
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    /// assert_eq!(workflows.len(), 2);
+    /// # })
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The method will return a [`SerializationError`] if the target cannot be serialized.
+    /// Planning errors are yielded by the iterator as a [`PlanError`].
+    pub async fn plan_iter(
+        &self,
+        tgt: I,
+    ) -> Result<impl Iterator<Item = Result<Workflow, PlanError>> + '_, SerializationError>
+    where
+        I: Serialize + DeserializeOwned,
+    {
+        let tgt = serde_json::to_value(tgt)?;
+        let iter = self
+            .with_current_state(|planner, system| planner.plan_iter::<I>(system, &tgt))
+            .await;
+        Ok(iter.map(|res| res.map_err(plan_error)))
+    }
+
+    /// Find a single workflow reaching multiple targets from the current worker state, without
+    /// executing it
+    ///
+    /// Targets are merged before planning, so steps shared between targets are only
+    /// included once in the workflow. An empty workflow is returned if no work is needed to
+    /// reach the targets.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    ///
+    /// use mahler::task::{self, prelude::*};
+    /// use mahler::extract::{View, Target};
+    /// use mahler::worker::Worker;
+    /// use mahler::{Dag, par, seq};
+    ///
+    /// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///    if *counter < tgt {
+    ///        *counter += 1;
+    ///    }
+    ///    counter
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker = Worker::new()
+    ///                 .job("/{counter}", update(plus_one).with_description(|| "+1"))
+    ///                 .initial_state(HashMap::from([("a".to_string(), 0), ("b".to_string(), 0)]))
+    ///                 .unwrap();
+    ///
+    /// let workflow = worker
+    ///     .find_workflow_multi(&[
+    ///         HashMap::from([("a".to_string(), 1)]),
+    ///         HashMap::from([("b".to_string(), 1)]),
+    ///     ])
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// let expected: Dag<&str> = par!("+1", "+1");
+    /// assert_eq!(workflow.to_string(), expected.to_string());
+    /// # })
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The method will return [`PlanError::NotFound`] if no workflow to the targets can be
+    /// found, including when the targets require different values for the same path, or a
+    /// [`PlanError::Fatal`] if an unexpected error happens during planning.
+    pub async fn find_workflow_multi(&self, targets: &[I]) -> Result<Workflow, PlanError>
+    where
+        I: Serialize + DeserializeOwned,
+    {
+        let targets = targets
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(SerializationError::from)?;
+        self.with_current_state(|planner, system| {
+            planner.find_workflow_multi::<I>(system, &targets)
+        })
+        .await
+        .map_err(plan_error)
+    }
+
+    /// Expand the task on the system, applying the changes of every atomic task in the tree
+    fn expand_tree_with_system(
+        &self,
+        mut task: Task,
+        system: &mut System,
+    ) -> Result<ExpansionNode, TaskError> {
+        let task_id = task.id().to_string();
+        let Context { args, .. } = task.context_mut();
+        let path = self
+            .inner
+            .planner
+            .domain()
+            .find_path_for_job(task_id.as_str(), args)
+            .map_err(MethodError::new)?;
+
+        let task = task.with_path(path.as_str());
+        let mut children = Vec::new();
+        match &task {
+            Task::Action(action) => {
+                let changes = action.dry_run(system)?;
+                system
+                    .patch(changes)
+                    .expect("failed to patch the system state");
+            }
+            Task::Method(method) => {
+                let tasks = method.expand(system)?;
+                for mut task in tasks {
+                    // Propagate the parent args to the child task
+                    for (k, v) in method.context().args.iter() {
+                        if !task.context().is_override(k) {
+                            task = task.with_arg(k, v)
+                        }
+                    }
+                    children.push(self.expand_tree_with_system(task, system)?);
+                }
+            }
+        }
+
+        Ok(ExpansionNode {
+            id: task_id,
+            path,
+            children,
+        })
+    }
+
+    /// Get the expansion tree of a task within the context of the worker domain
+    ///
+    /// Methods are recursively expanded on a copy of the current worker state, but only the
+    /// planning part of atomic tasks is evaluated. Each node
+    /// of the tree lists the tasks a method expanded into, which helps to understand the
+    /// structure of the domain.
+    ///
+    /// # Example
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    /// use mahler::extract::{View, Target};
+    /// use mahler::worker::Worker;
+    ///
+    /// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///     if *counter < tgt {
+    ///         *counter += 1;
+    ///     }
+    ///     counter
+    /// }
+    ///
+    /// fn plus_two(counter: View<i32>, Target(tgt): Target<i32>) -> Vec<Task> {
+    ///     if tgt - *counter > 1 {
+    ///         return vec![plus_one.with_target(tgt), plus_one.with_target(tgt)];
+    ///     }
+    ///     vec![]
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker = Worker::new()
+    ///     .job("", update(plus_one))
+    ///     .job("", update(plus_two))
+    ///     .initial_state::<i32>(0)
+    ///     .unwrap();
+    ///
+    /// let tree = worker.expand_tree(plus_two.with_target(2)).await.unwrap();
+    /// assert_eq!(tree.children.len(), 2);
+    /// assert!(tree.children.iter().all(|child| child.id == plus_one.id()));
+    /// # })
+    /// ```
+    ///
+    /// Returns [`TaskError::CannotExpandMethod`] if the task, or any task returned by a method
+    /// expansion, is not registered with the worker.
+    ///
+    /// # Panics
+    /// This function will panic if a serialization or internal error happens during the expansion
+    pub async fn expand_tree(&self, task: Task) -> Result<ExpansionNode, TaskError> {
+        let mut system = self.inner.system.read().await.clone();
+        self.expand_tree_with_system(task, &mut system)
+    }
+
+    /// Check that the target can be used with the worker before seeking it
+    ///
+    /// The target is serialized when planning. Checking the target first allows to fail early
+    /// with a [`SerializationError`] instead of a [`FatalError`] from
+    /// [`seek_target`](Self::seek_target).
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use mahler::worker::{Worker, Ready};
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker: Worker<BTreeMap<String, i32>, Ready, BTreeMap<Vec<i32>, i32>> =
+    ///     Worker::new().initial_state(BTreeMap::new()).unwrap();
+    ///
+    /// // JSON object keys must be strings
+    /// let target = BTreeMap::from([(vec![1], 1)]);
+    /// assert!(worker.check_target(&target).is_err());
+    /// # })
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The method will return a [`SerializationError`] if the target cannot be serialized.
+    pub fn check_target(&self, tgt: &I) -> Result<(), SerializationError>
+    where
+        I: Serialize,
+    {
+        self.inner.planner.check_target(tgt)
+    }
+
+    /// Bind a serialized workflow to the jobs registered with the worker
+    ///
+    /// This allows to reuse a [`Workflow`] previously serialized, e.g. to cache the result of
+    /// planning for a given state and target.
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    /// use mahler::extract::{View, Target};
+    /// use mahler::worker::{Worker, StepOutcome};
+    ///
+    /// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///    if *counter < tgt {
+    ///        *counter += 1;
+    ///    }
+    ///    counter
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let mut worker = Worker::new()
+    ///                 .job("", update(plus_one))
+    ///                 .initial_state(0)
+    ///                 .unwrap();
+    /// let workflow = worker.find_workflow(2).await.unwrap();
+    ///
+    /// // Store the workflow
+    /// let cached = serde_json::to_value(&workflow).unwrap();
+    ///
+    /// // And reload it to skip planning
+    /// let workflow = worker.rehydrate(cached).unwrap();
+    /// assert_eq!(worker.run_workflow(workflow).await.unwrap(), StepOutcome::Progressed);
+    /// assert_eq!(worker.state().await.unwrap(), 2);
+    /// # })
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The method will return a [`RehydrateError`] if the value is not a valid serialized workflow
+    /// or if any of its tasks does not match an action registered with the worker.
+    pub fn rehydrate(&self, serialized: Value) -> Result<Workflow, RehydrateError> {
+        self.inner.planner.domain().rehydrate(serialized)
+    }
+
+    /// Execute a workflow without planning
+    ///
+    /// Tasks are tested against the current state before running, so the execution stops if
+    /// the workflow is no longer applicable, in which case [`StepOutcome::Progressed`] is returned
+    /// and the next cycle should plan again. See [`run_once`](Self::run_once) for other possible
+    /// outcomes.
+    ///
+    /// # Errors
+    /// The method will result in a [`FatalError`] if there is an unexpected error during
+    /// execution.
+    pub async fn run_workflow(&mut self, workflow: Workflow) -> Result<StepOutcome, FatalError> {
+        {
+            // Tasks may read the state before execution
+            let mut system = self.inner.system.write().await;
+            system.set_baseline();
+
+            // Track the progress towards the state predicted by the workflow. The prediction
+            // fails if the workflow does not apply to the current state, which is reported
+            // when the workflow is executed
+            if let Ok(predicted) = workflow.predicted_states(&system) {
+                let tgt = predicted.last().unwrap_or(system.root());
+                self.inner
+                    .progress
+                    .start(&system, tgt, raw_state, self.inner.planner.config());
+            }
+        }
+
+        self.execute_workflow(workflow).await
+    }
+
+    /// Execute the workflow and report the outcome
+    async fn execute_workflow(&self, workflow: Workflow) -> Result<StepOutcome, FatalError> {
+        let Ready {
+            system,
+            patches,
+            writer_closed,
+            interrupt,
+            action_timeout,
+            divergences,
+            ..
+        } = &self.inner;
+
+        if workflow.is_empty() {
+            return Ok(StepOutcome::Reached);
+        }
+
+        let res = select! {
+            biased;
+
+            _ = writer_closed.notified() => {
+                return Err(InternalError::from(anyhow!("state patch failed, worker state possibly tainted")))?;
+            }
+
+            res = workflow
+                .with_action_timeout(*action_timeout)
+                .with_divergences(divergences.clone())
+                .execute(system, patches.clone(), interrupt.0.clone()) => res
+        };
+
+        match res {
+            Ok(WorkflowStatus::Interrupted) => Ok(StepOutcome::Interrupted),
+            Ok(WorkflowStatus::Completed) => Ok(StepOutcome::Progressed),
+            Err(err) => {
+                let io = io_errors(err)?;
+                if !io.is_empty() {
+                    return Ok(StepOutcome::Aborted(io));
+                }
+
+                // All errors were condition failures, the next
+                // cycle will re-plan from the updated state
+                Ok(StepOutcome::Progressed)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<O: Send, I: Serialize + DeserializeOwned + Send> SeekTarget<O, I>
+    for Result<Worker<O, Ready, I>, SerializationError>
+{
+    async fn seek_target(self, tgt: I) -> Result<Worker<O, Ready, I>, FatalError> {
+        let worker = self?;
+        worker.seek_target(tgt).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::extract::{Target, View};
+    use crate::task::*;
+    use serde::Deserialize;
+    use serde_json::json;
+    use tokio::time::{sleep, timeout};
+    use tracing_subscriber::fmt::format::FmtSpan;
+    use tracing_subscriber::{prelude::*, EnvFilter};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Counters(HashMap<String, i32>);
+
+    fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> Effect<View<i32>> {
+        if *counter < tgt {
+            // Modify the counter if we are below target
+            *counter += 1;
+        }
+
+        // Return the updated counter. The I/O part of the
+        // effect will only be called if the job is chosen
+        // in the workflow which will only happens if there are
+        // changes
+        Effect::of(counter).with_io(|counter| async {
+            sleep(Duration::from_millis(10)).await;
+            Ok(counter)
+        })
+    }
+
+    fn buggy_plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+        if *counter < tgt {
+            // This is the wrong operation
+            *counter -= 1;
+        }
+
+        counter
+    }
+
+    fn init() {
+        tracing_subscriber::registry()
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .pretty()
+                    .with_target(false)
+                    .with_thread_names(true)
+                    .with_thread_ids(true)
+                    .with_line_number(true)
+                    .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE),
+            )
+            .with(EnvFilter::from_default_env())
+            .try_init()
+            .unwrap_or(());
+    }
+
+    #[tokio::test]
+    async fn test_worker_complex_state() {
+        init();
+        let worker = Worker::new()
+            .job("/{counter}", update(plus_one))
+            .initial_state(Counters(HashMap::from([
+                ("one".to_string(), 0),
+                ("two".to_string(), 0),
+            ])))
+            .seek_target(Counters(HashMap::from([
+                ("one".to_string(), 2),
+                ("two".to_string(), 0),
+            ])))
+            .await
+            .unwrap();
+
+        assert_eq!(worker.status(), &SeekStatus::Success);
+        let state = worker.state().await.unwrap();
+        assert_eq!(
+            state,
+            Counters(HashMap::from([
+                ("one".to_string(), 2),
+                ("two".to_string(), 0),
+            ]))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_worker_run_once() {
+        init();
+        let mut worker = Worker::new()
+            .job("/{counter}", update(plus_one))
+            .initial_state(Counters(HashMap::from([
+                ("one".to_string(), 0),
+                ("two".to_string(), 0),
+            ])))
+            .unwrap();
+
+        let target = || {
+            Counters(HashMap::from([
+                ("one".to_string(), 2),
+                ("two".to_string(), 1),
+            ]))
+        };
+
+        let mut cycles = 0;
+        loop {
+            let outcome = worker.run_once(target()).await.unwrap();
+            if outcome == StepOutcome::Reached {
+                break;
+            }
+            assert_eq!(outcome, StepOutcome::Progressed);
+            cycles += 1;
+        }
+
+        assert!(cycles > 0);
+        let state = worker.state().await.unwrap();
+        assert_eq!(state, target());
+    }
+
+    #[tokio::test]
+    async fn it_finds_a_plan_to_run_later() {
+        init();
+        let mut worker = Worker::new()
+            .job("", update(plus_one))
+            .initial_state(0)
+            .unwrap();
+
+        assert!(worker.find_plan(0).await.unwrap().is_reached());
+        assert!(matches!(
+            worker.find_plan(-1).await,
+            Err(PlanError::NotFound(_))
+        ));
+
+        let PlanResult::Plan(workflow) = worker.find_plan(2).await.unwrap() else {
+            panic!("expected a workflow to the target");
+        };
+        assert_eq!(
+            worker.run_workflow(workflow).await.unwrap(),
+            StepOutcome::Progressed
+        );
+        assert_eq!(worker.state().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_worker_applies_the_runtime_changes() {
+        init();
+
+        // The counter is predicted to increase by one but
+        // the IO operation jumps straight to the target
+        fn jump_to_target(mut counter: View<i32>, Target(tgt): Target<i32>) -> Effect<View<i32>> {
+            if *counter < tgt {
+                *counter += 1;
+            }
+
+            Effect::of(counter).with_io(move |mut counter| async move {
+                *counter = tgt;
+                Ok(counter)
+            })
+        }
+
+        let mut worker = Worker::new()
+            .job("/{counter}", update(jump_to_target))
+            .initial_state(Counters(HashMap::from([("one".to_string(), 0)])))
+            .unwrap();
+
+        let target = || Counters(HashMap::from([("one".to_string(), 3)]));
+
+        // A single workflow execution should be enough
+        let outcome = worker.run_once(target()).await.unwrap();
+        assert_eq!(outcome, StepOutcome::Progressed);
+        assert_eq!(worker.state().await.unwrap(), target());
+
+        let outcome = worker.run_once(target()).await.unwrap();
+        assert_eq!(outcome, StepOutcome::Reached);
+    }
+
+    #[tokio::test]
+    async fn it_reads_the_converged_state_as_any_type() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Target {
+            one: i32,
+        }
+
+        let worker = Worker::new()
+            .job("/{counter}", update(plus_one))
+            .initial_state(Counters(HashMap::from([
+                ("one".to_string(), 0),
+                ("two".to_string(), 0),
+            ])))
+            .unwrap()
+            .seek_target(Counters(HashMap::from([
+                ("one".to_string(), 2),
+                ("two".to_string(), 0),
+            ])))
+            .await
+            .unwrap();
+
+        assert_eq!(worker.status(), &SeekStatus::Success);
+
+        let state: Target = worker.current_state().await.unwrap();
+        assert_eq!(state, Target { one: 2 });
+
+        let state: HashMap<String, i32> = worker.current_state().await.unwrap();
+        assert_eq!(
+            state,
+            HashMap::from([("one".to_string(), 2), ("two".to_string(), 0)])
+        );
+
+        // The state cannot be read as an incompatible type
+        assert!(worker.current_state::<Vec<i32>>().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn it_reads_configuration_resources_from_handlers() {
+        use crate::extract::Res;
+
+        struct Config {
+            step: i32,
+        }
+
+        fn plus_step(
+            mut counter: View<i32>,
+            Target(tgt): Target<i32>,
+            config: Res<Config>,
+        ) -> View<i32> {
+            if *counter < tgt {
+                *counter = (*counter + config.step).min(tgt);
+            }
+
+            counter
+        }
+
+        // Resources do not affect the scope of the task
+        assert!(plus_step.into_task().is_scoped());
+
+        let target = || {
+            Counters(HashMap::from([
+                ("one".to_string(), 6),
+                ("two".to_string(), 1),
+            ]))
+        };
+
+        let worker = Worker::new()
+            .job("/{counter}", update(plus_step))
+            .resource(Config { step: 3 })
+            .initial_state(Counters(HashMap::from([
+                ("one".to_string(), 0),
+                ("two".to_string(), 0),
+            ])))
+            .unwrap();
+
+        // The planner uses the configured step, two tasks for "one"
+        // and a single task for "two"
+        let workflow = worker.find_plan(target()).await.unwrap().into_workflow();
+        assert_eq!(workflow.as_dag().values().count(), 3);
+
+        let worker = worker.seek_target(target()).await.unwrap();
+        assert_eq!(worker.status(), &SeekStatus::Success);
+
+        // The configuration is not part of the system state
+        assert_eq!(worker.state().await.unwrap(), target());
+    }
+
+    #[tokio::test]
+    async fn it_reports_the_migration_error() {
+        #[derive(Debug, Error)]
+        #[error("unsupported version")]
+        struct UnsupportedVersion;
+
+        let res: Result<Worker<i32, Ready>, _> = Worker::new()
+            .initial_state_with_migration(serde_json::json!({"version": 1}), |_| {
+                Err(UnsupportedVersion)
+            });
+        assert!(matches!(
+            res,
+            Err(MigrationError::Migration(UnsupportedVersion))
+        ));
+
+        // The migrated state must match the state model
+        let res: Result<Worker<i32, Ready>, _> = Worker::new()
+            .initial_state_with_migration(serde_json::json!({"version": 1}), |state| {
+                Ok::<_, UnsupportedVersion>(state)
+            });
+        assert!(matches!(res, Err(MigrationError::Serialization(_))));
+    }
+
+    #[tokio::test]
+    async fn it_checks_the_target_before_seeking() {
+        use std::collections::BTreeMap;
+
+        let worker: Worker<BTreeMap<String, i32>, Ready, BTreeMap<Vec<i32>, i32>> = Worker::new()
+            .initial_state(BTreeMap::from([("one".to_string(), 0)]))
+            .unwrap();
+
+        assert!(worker.check_target(&BTreeMap::new()).is_ok());
+
+        let err = worker
+            .check_target(&BTreeMap::from([(vec![1, 2], 1)]))
+            .unwrap_err();
+        assert!(err.to_string().contains("key must be a string"));
+    }
+
+    #[tokio::test]
+    async fn it_replans_when_an_action_exceeds_the_timeout() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        init();
+
+        static SLOW_ATTEMPTED: AtomicBool = AtomicBool::new(false);
+
+        fn slow_plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> Effect<View<i32>> {
+            if *counter < tgt {
+                *counter += 1;
+            }
+
+            Effect::of(counter).with_io(|counter| async {
+                SLOW_ATTEMPTED.store(true, Ordering::SeqCst);
+                sleep(Duration::from_secs(10)).await;
+                Ok(counter)
+            })
+        }
+
+        // The slow job is preferred but it is no longer applicable after
+        // it has been tried once
+        let worker = Worker::new()
+            .job(
+                "",
+                update(slow_plus_one)
+                    .with_priority(1)
+                    .require(|_, _| !SLOW_ATTEMPTED.load(Ordering::SeqCst)),
+            )
+            .job("", update(plus_one))
+            .action_timeout(Duration::from_millis(50))
+            .initial_state(0)
+            .unwrap();
+
+        let worker = timeout(Duration::from_secs(1), worker.seek_target(1))
+            .await
+            .expect("the worker should not wait for the slow action")
+            .unwrap();
+
+        assert!(SLOW_ATTEMPTED.load(Ordering::SeqCst));
+        assert_eq!(worker.status(), &SeekStatus::Success);
+        assert_eq!(worker.state().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn it_keeps_intermediate_changes_when_an_action_exceeds_the_timeout() {
+        use futures::stream;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        init();
+
+        static ATTEMPTED: AtomicBool = AtomicBool::new(false);
+
+        // The first step is reported immediately, the following ones
+        // take longer than the action timeout
+        fn count_to_target(mut counter: View<i32>, Target(tgt): Target<i32>) -> Effect<View<i32>> {
+            let start = *counter;
+            *counter = tgt;
+
+            Effect::of(counter).with_io_stream(move |counter| {
+                ATTEMPTED.store(true, Ordering::SeqCst);
+                stream::unfold(start, move |i| {
+                    let mut counter = counter.clone();
+                    async move {
+                        if i >= tgt {
+                            return None;
+                        }
+                        if i > start {
+                            sleep(Duration::from_secs(10)).await;
+                        }
+                        *counter = i + 1;
+                        Some((Ok(counter), i + 1))
+                    }
+                })
+            })
+        }
+
+        // The job is only applicable once, so re-planning after the
+        // timeout cannot find a workflow
+        let worker = Worker::new()
+            .job(
+                "",
+                update(count_to_target).require(|_, _| !ATTEMPTED.load(Ordering::SeqCst)),
+            )
+            .action_timeout(Duration::from_millis(50))
+            .initial_state(0)
+            .unwrap();
+
+        let worker = timeout(Duration::from_secs(1), worker.seek_target(3))
+            .await
+            .expect("the worker should not wait for the slow stream")
+            .unwrap();
+
+        assert!(matches!(worker.status(), SeekStatus::NotFound(_)));
 
-    use super::*;
-    use crate::extract::{Target, View};
-    use crate::task::*;
-    use serde::Deserialize;
-    use tokio::time::{sleep, timeout};
-    use tracing_subscriber::fmt::format::FmtSpan;
-    use tracing_subscriber::{prelude::*, EnvFilter};
+        // The change reported before the timeout is kept
+        assert_eq!(worker.state().await.unwrap(), 1);
+    }
 
-    #[derive(Debug, Serialize, Deserialize, PartialEq)]
-    struct Counters(HashMap<String, i32>);
+    #[tokio::test]
+    async fn test_worker_applies_intermediate_changes() {
+        use futures::stream;
 
-    fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> Effect<View<i32>> {
-        if *counter < tgt {
-            // Modify the counter if we are below target
-            *counter += 1;
+        init();
+
+        // The IO operation reports every step towards the target
+        fn count_to_target(mut counter: View<i32>, Target(tgt): Target<i32>) -> Effect<View<i32>> {
+            let start = *counter;
+            *counter = tgt;
+
+            Effect::of(counter).with_io_stream(move |counter| {
+                stream::unfold(start, move |i| {
+                    let mut counter = counter.clone();
+                    async move {
+                        if i >= tgt {
+                            return None;
+                        }
+                        sleep(Duration::from_millis(10)).await;
+                        *counter = i + 1;
+                        Some((Ok(counter), i + 1))
+                    }
+                })
+            })
         }
 
-        // Return the updated counter. The I/O part of the
-        // effect will only be called if the job is chosen
-        // in the workflow which will only happens if there are
-        // changes
-        Effect::of(counter).with_io(|counter| async {
-            sleep(Duration::from_millis(10)).await;
-            Ok(counter)
-        })
+        let mut worker = Worker::new()
+            .job("/{counter}", update(count_to_target))
+            .initial_state(Counters(HashMap::from([("one".to_string(), 0)])))
+            .unwrap();
+
+        let mut updates = worker.follow();
+        let follower = tokio::spawn(async move {
+            let mut values = Vec::new();
+            while let Some(Counters(state)) = updates.next().await {
+                let value = state["one"];
+                if values.last() != Some(&value) {
+                    values.push(value);
+                }
+            }
+            values
+        });
+
+        let target = || Counters(HashMap::from([("one".to_string(), 3)]));
+        let outcome = worker.run_once(target()).await.unwrap();
+        assert_eq!(outcome, StepOutcome::Progressed);
+        assert_eq!(worker.state().await.unwrap(), target());
+
+        // Dropping the worker closes the update stream
+        drop(worker);
+        assert_eq!(follower.await.unwrap(), vec![1, 2, 3]);
     }
 
-    fn buggy_plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
-        if *counter < tgt {
-            // This is the wrong operation
-            *counter -= 1;
+    #[tokio::test]
+    async fn test_worker_refreshes_state_from_sensor() {
+        use serde_json::json;
+        use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+
+        init();
+
+        // The counter in the real world
+        let world = Arc::new(AtomicI32::new(1));
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        struct WorldSensor(Arc<AtomicI32>);
+
+        impl Sensor for WorldSensor {
+            fn sense(&self) -> Result<Value, IOError> {
+                Ok(json!({ "one": self.0.load(Ordering::SeqCst) }))
+            }
         }
 
-        counter
-    }
+        let plus_one = {
+            let world = world.clone();
+            let runs = runs.clone();
+            move |mut counter: View<i32>, Target(tgt): Target<i32>| {
+                if *counter < tgt {
+                    *counter += 1;
+                }
 
-    fn init() {
-        tracing_subscriber::registry()
-            .with(
-                tracing_subscriber::fmt::layer()
-                    .pretty()
-                    .with_target(false)
-                    .with_thread_names(true)
-                    .with_thread_ids(true)
-                    .with_line_number(true)
-                    .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE),
-            )
-            .with(EnvFilter::from_default_env())
-            .try_init()
-            .unwrap_or(());
+                let world = world.clone();
+                let runs = runs.clone();
+                Effect::of(counter).with_io(move |counter| async move {
+                    world.store(*counter, Ordering::SeqCst);
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    Ok::<_, std::convert::Infallible>(counter)
+                })
+            }
+        };
+
+        // The worker believes the counter is 0, but it is
+        // already 1 in the real world
+        let worker = Worker::new()
+            .job("/{counter}", update(plus_one))
+            .sensor(WorldSensor(world.clone()))
+            .initial_state(Counters(HashMap::from([("one".to_string(), 0)])))
+            .seek_target(Counters(HashMap::from([("one".to_string(), 2)])))
+            .await
+            .unwrap();
+
+        assert_eq!(worker.status(), &SeekStatus::Success);
+        assert_eq!(
+            worker.state().await.unwrap(),
+            Counters(HashMap::from([("one".to_string(), 2)]))
+        );
+
+        // Only one increment should have been necessary
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(world.load(Ordering::SeqCst), 2);
     }
 
     #[tokio::test]
-    async fn test_worker_complex_state() {
+    async fn it_plans_from_externally_observed_changes() {
+        use serde_json::json;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
         init();
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let plus_one = {
+            let runs = runs.clone();
+            move |mut counter: View<i32>, Target(tgt): Target<i32>| {
+                if *counter < tgt {
+                    *counter += 1;
+                }
+
+                let runs = runs.clone();
+                Effect::of(counter).with_io(move |counter| async move {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    Ok::<_, std::convert::Infallible>(counter)
+                })
+            }
+        };
+
         let worker = Worker::new()
             .job("/{counter}", update(plus_one))
-            .initial_state(Counters(HashMap::from([
-                ("one".to_string(), 0),
-                ("two".to_string(), 0),
-            ])))
-            .seek_target(Counters(HashMap::from([
-                ("one".to_string(), 2),
-                ("two".to_string(), 0),
-            ])))
+            .initial_state(Counters(HashMap::from([("one".to_string(), 0)])))
+            .unwrap();
+
+        // An external actor moves the counter forward
+        let changes: Patch =
+            serde_json::from_value(json!([{ "op": "replace", "path": "/one", "value": 2 }]))
+                .unwrap();
+        worker.observe(changes).await.unwrap();
+        assert_eq!(
+            worker.state().await.unwrap(),
+            Counters(HashMap::from([("one".to_string(), 2)]))
+        );
+
+        // Invalid changes are rejected without modifying the state
+        let invalid: Patch =
+            serde_json::from_value(json!([{ "op": "remove", "path": "/two" }])).unwrap();
+        assert!(worker.observe(invalid).await.is_err());
+
+        let worker = worker
+            .seek_target(Counters(HashMap::from([("one".to_string(), 3)])))
             .await
             .unwrap();
 
         assert_eq!(worker.status(), &SeekStatus::Success);
-        let state = worker.state().await.unwrap();
         assert_eq!(
-            state,
-            Counters(HashMap::from([
-                ("one".to_string(), 2),
-                ("two".to_string(), 0),
-            ]))
+            worker.state().await.unwrap(),
+            Counters(HashMap::from([("one".to_string(), 3)]))
         );
+
+        // The plan only needed to account for the remaining increment
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
     }
 
     #[tokio::test]
@@ -946,7 +3395,7 @@ mod tests {
             .await
             .unwrap();
 
-        assert!(matches!(res.status(), &SeekStatus::NotFound))
+        assert!(matches!(res.status(), &SeekStatus::NotFound(_)))
     }
 
     #[tokio::test]
@@ -981,6 +3430,125 @@ mod tests {
         assert_eq!(*results, vec![Some(1), Some(2)]);
     }
 
+    #[tokio::test]
+    async fn it_reports_increasing_progress_towards_the_target() {
+        let worker = Worker::new()
+            .job("/{counter}", update(plus_one))
+            .initial_state(Counters(HashMap::from([
+                ("a".to_string(), 0),
+                ("b".to_string(), 0),
+            ])))
+            .unwrap();
+
+        // Collect progress updates until the target is reached
+        let mut updates = worker.follow_progress();
+        let collector = tokio::spawn(async move {
+            let mut values = Vec::new();
+            while let Some(progress) = updates.next().await {
+                values.push(progress);
+                if progress == 100.0 {
+                    break;
+                }
+            }
+            values
+        });
+
+        let worker = worker
+            .seek_target(Counters(HashMap::from([
+                ("a".to_string(), 2),
+                ("b".to_string(), 1),
+            ])))
+            .await
+            .unwrap();
+        assert_eq!(worker.status(), &SeekStatus::Success);
+        assert_eq!(worker.progress(), 100.0);
+
+        let values = timeout(Duration::from_secs(1), collector)
+            .await
+            .unwrap()
+            .unwrap();
+        // Updates may be skipped by a slow reader, but the ones received never decrease
+        assert!(values.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(values.last(), Some(&100.0));
+    }
+
+    // The action never changes the system at runtime
+    fn stuck_plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> Effect<View<i32>> {
+        if *counter < tgt {
+            *counter += 1;
+        }
+
+        Effect::of(counter).with_io(|mut counter| async {
+            *counter -= 1;
+            Ok(counter)
+        })
+    }
+
+    #[tokio::test]
+    async fn it_tracks_progress_when_running_single_cycles() {
+        let mut worker = Worker::new()
+            .job("", update(stuck_plus_one))
+            .initial_state(0)
+            .unwrap();
+        assert_eq!(worker.progress(), 100.0);
+
+        assert_eq!(worker.run_once(1).await.unwrap(), StepOutcome::Progressed);
+        assert_eq!(worker.progress(), 0.0);
+
+        assert_eq!(worker.run_once(0).await.unwrap(), StepOutcome::Reached);
+        assert_eq!(worker.progress(), 100.0);
+
+        let workflow = worker.find_plan(1).await.unwrap().into_workflow();
+        assert_eq!(
+            worker.run_workflow(workflow).await.unwrap(),
+            StepOutcome::Progressed
+        );
+        assert_eq!(worker.progress(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn it_tracks_progress_towards_all_reconciled_targets() {
+        // Only the first counter can be updated
+        let worker: Worker<Value, Ready> = Worker::new()
+            .job("/a", update(plus_one))
+            .initial_state(json!({"a": 0, "b": 0}))
+            .unwrap();
+
+        let worker = worker
+            .reconcile_all(vec![(json!({"a": 1}), 10), (json!({"b": 1}), 1)])
+            .await
+            .unwrap();
+        assert!(matches!(worker.status(), SeekStatus::NotFound(_)));
+        assert_eq!(worker.progress(), 50.0);
+    }
+
+    #[test]
+    fn it_calculates_progress_using_the_planner_comparison_rules() {
+        let system = System::try_from(0.99).unwrap();
+        let tgt = serde_json::json!(1.0);
+
+        // With the default config, the state is one change away from the target
+        let progress = Progress::default();
+        progress.start(&system, &tgt, normalize::<f64>, &PlannerConfig::default());
+        assert_eq!(progress.get(), 0.0);
+
+        // The number policy makes the state equal to the target
+        let config = PlannerConfig {
+            number_policy: NumberPolicy::Epsilon(0.05),
+            ..PlannerConfig::default()
+        };
+        progress.start(&system, &tgt, normalize::<f64>, &config);
+        assert_eq!(progress.get(), 100.0);
+
+        // Path comparators are also used to compare values
+        let mut config = PlannerConfig::default();
+        config
+            .path_comparators
+            .insert("", Arc::new(|_: &Value, _: &Value| true));
+        progress.start(&system, &tgt, normalize::<f64>, &config);
+        assert_eq!(progress.get(), 100.0);
+    }
+
     #[tokio::test]
     async fn test_worker_follow_best_effort_loss() {
         init();
@@ -1142,6 +3710,183 @@ mod tests {
         assert!(state < 10, "Expected state {} to be less than 10", state);
     }
 
+    #[tokio::test]
+    async fn it_reconciles_to_the_latest_target_from_a_stream() {
+        init();
+
+        fn sleepy_step(mut counter: View<i32>, Target(tgt): Target<i32>) -> Effect<View<i32>> {
+            if *counter < tgt {
+                *counter += 1;
+            } else if *counter > tgt {
+                *counter -= 1;
+            }
+
+            Effect::of(counter).with_io(|counter| async {
+                sleep(Duration::from_millis(20)).await;
+                Ok(counter)
+            })
+        }
+
+        let worker = Worker::new()
+            .job("", update(sleepy_step))
+            .initial_state(0)
+            .unwrap();
+
+        // The second target arrives before the first one can be reached
+        let targets = tokio_stream::iter(vec![(0, 10), (50, 2)]).then(|(delay, tgt)| async move {
+            sleep(Duration::from_millis(delay)).await;
+            tgt
+        });
+
+        let worker = worker.reconcile(targets).await.unwrap();
+        assert_eq!(worker.status(), &SeekStatus::Success);
+        assert_eq!(worker.state().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn it_reconciles_targets_in_order_of_priority() {
+        init();
+
+        // Counters can only go up, so lower targets are unreachable
+        let worker: Worker<HashMap<String, i32>, Ready> = Worker::new()
+            .job("/{counter}", update(plus_one))
+            .initial_state(HashMap::from([("a".to_string(), 0), ("b".to_string(), 0)]))
+            .unwrap();
+
+        let worker = worker
+            .reconcile_all(vec![
+                (json!({"b": -1}), 1),
+                (json!({"a": 2}), 10),
+                (json!({"a": 3}), 5),
+            ])
+            .await
+            .unwrap();
+
+        assert!(matches!(worker.status(), SeekStatus::NotFound(_)));
+        assert_eq!(
+            worker.state().await.unwrap(),
+            HashMap::from([("a".to_string(), 2), ("b".to_string(), 0)])
+        );
+    }
+
+    #[tokio::test]
+    async fn it_configures_the_planner_from_the_worker_builder() {
+        init();
+
+        let domain = Domain::new().job("", update(plus_one));
+        let worker = Worker::builder(domain)
+            .search_strategy(SearchStrategy::a_star())
+            .prune_regressive_tasks(true)
+            .path_visit_limit(10)
+            .number_policy(NumberPolicy::Epsilon(1e-9))
+            .max_depth(4)
+            .replan_policy(ReplanPolicy::Limit(1))
+            .build()
+            .initial_state(0)
+            .unwrap();
+
+        let worker = worker
+            .reconcile(tokio_stream::iter(vec![1, 3]))
+            .await
+            .unwrap();
+        assert_eq!(worker.status(), &SeekStatus::Success);
+        assert_eq!(worker.state().await.unwrap(), 3);
+
+        // Reaching the target requires more steps than allowed
+        let worker = worker.seek_target(7).await.unwrap();
+        let SeekStatus::NotFound(err) = worker.status() else {
+            panic!("expected the search to fail");
+        };
+        assert!(err.stats().nodes_expanded > 0);
+        assert_eq!(worker.state().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn it_reports_not_found_if_every_branch_reaches_the_path_visit_limit() {
+        init();
+
+        let worker = Worker::new()
+            .job("", update(buggy_plus_one))
+            .path_visit_limit(10)
+            .initial_state(0)
+            .unwrap();
+
+        let worker = worker.seek_target(2).await.unwrap();
+        let SeekStatus::NotFound(err) = worker.status() else {
+            panic!("expected the search to fail");
+        };
+        assert_eq!(err.stats().pruned_visit_limit, 1);
+        assert_eq!(worker.state().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn it_aborts_the_seek_if_an_action_panics_at_runtime() {
+        init();
+
+        fn panicking_plus_one(
+            mut counter: View<i32>,
+            Target(tgt): Target<i32>,
+        ) -> Effect<View<i32>> {
+            if *counter < tgt {
+                *counter += 1;
+            }
+
+            Effect::of(counter).with_io(|_| async { panic!("something went wrong") })
+        }
+
+        let worker = Worker::new()
+            .job("", update(panicking_plus_one))
+            .initial_state(0)
+            .unwrap();
+
+        let worker = worker.seek_target(1).await.unwrap();
+        let SeekStatus::Aborted(errors) = worker.status() else {
+            panic!("expected the seek to be aborted");
+        };
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0].downcast_ref::<TaskError>(),
+            Some(TaskError::Panicked(_))
+        ));
+
+        // The worker can still be used
+        assert_eq!(worker.state().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn it_stops_re_planning_after_the_replan_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        init();
+
+        static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+
+        // The action never changes the system at runtime
+        fn stuck_plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> Effect<View<i32>> {
+            if *counter < tgt {
+                *counter += 1;
+            }
+
+            Effect::of(counter).with_io(|mut counter| async {
+                ATTEMPTS.fetch_add(1, Ordering::SeqCst);
+                *counter -= 1;
+                Ok(counter)
+            })
+        }
+
+        let domain = Domain::new().job("", update(stuck_plus_one));
+        let worker = Worker::builder(domain)
+            .replan_policy(ReplanPolicy::Limit(2))
+            .build()
+            .initial_state(0)
+            .unwrap();
+
+        let worker = worker.seek_target(1).await.unwrap();
+        assert_eq!(worker.status(), &SeekStatus::ReplanLimitReached);
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 3);
+        assert_eq!(worker.state().await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn test_seek_with_interrupt_vs_seek_target() {
         init();
@@ -1,40 +1,17 @@
-use std::sync::Arc;
-
 use serde::{de::DeserializeOwned, Serialize};
-use thiserror::Error;
-use tokio::sync::RwLock;
 
-use super::{Ready, Worker};
-use crate::planner::{Error as PlannerError, Planner};
+use super::{NotFound, Ready, Worker};
+use crate::planner::Error as PlannerError;
 use crate::system::System;
+use crate::task::Task;
 use crate::task::{self, Context};
-use crate::{task::Task, workflow::Workflow};
-
-#[derive(Debug, Error)]
-#[error("workflow not found")]
-/// A workflow could not be found
-///
-/// This is returned by [`Worker::find_workflow`] when used on testing.
-pub struct NotFound;
-
-async fn find_workflow<I>(
-    system: &Arc<RwLock<System>>,
-    planner: &Planner,
-    tgt: I,
-) -> Result<Workflow, NotFound>
-where
-    I: Serialize + DeserializeOwned,
-{
-    let cur = {
-        let sys = system.read().await;
-        sys.clone()
-    };
-
-    let tgt = serde_json::to_value(tgt).expect("failed to serialize target state");
-
-    match planner.find_workflow::<I>(&cur, &tgt) {
-        Ok(workflow) => Ok(workflow),
-        Err(PlannerError::NotFound) => Err(NotFound),
+use crate::workflow::Workflow;
+
+/// Return the result of a search, panicking on any error other than [`NotFound`]
+fn expect_found<T>(res: Result<T, PlannerError>) -> Result<T, NotFound> {
+    match res {
+        Ok(res) => Ok(res),
+        Err(PlannerError::NotFound(stats, reason)) => Err(NotFound::new(stats, reason)),
         Err(e) => panic!("unexpected planning error: {e}"),
     }
 }
@@ -83,7 +60,11 @@ impl<O, I> Worker<O, Ready, I> {
     where
         I: Serialize + DeserializeOwned,
     {
-        find_workflow::<I>(&self.inner.system, &self.inner.planner, tgt).await
+        let tgt = serde_json::to_value(tgt).expect("failed to serialize target state");
+        let res = self
+            .with_current_state(|planner, system| planner.find_workflow::<I>(system, &tgt))
+            .await;
+        expect_found(res)
     }
 
     async fn run_task_with_system(
@@ -103,7 +84,7 @@ impl<O, I> Worker<O, Ready, I> {
         let task = task.with_path(path);
         match &task {
             Task::Action(action) => {
-                let changes = action.run(system).await?;
+                let changes = action.run_with_progress(system, Box::new(|_| {})).await?;
                 system
                     .patch(changes)
                     .expect("failed to patch the system state");
@@ -194,6 +175,8 @@ mod tests {
 
     use super::*;
     use crate::extract::{Target, View};
+    use crate::worker::ExpansionNode;
+    use crate::workflow::PlanResult;
     use crate::{par, seq, task::*, Dag};
 
     fn plus_one(mut counter: View<i32>, tgt: Target<i32>) -> View<i32> {
@@ -268,6 +251,30 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn it_reports_if_the_target_is_already_reached() {
+        let worker = Worker::new()
+            .job("", update(plus_one))
+            .initial_state(2)
+            .unwrap();
+
+        let res = worker.find_plan(2).await.unwrap();
+        assert!(matches!(res, PlanResult::Reached));
+
+        // The planner still returns an empty workflow
+        let workflow = worker.find_workflow(2).await.unwrap();
+        assert!(workflow.is_empty());
+
+        let PlanResult::Plan(workflow) = worker.find_plan(4).await.unwrap() else {
+            panic!("expected a workflow to the target");
+        };
+        let expected: Dag<&str> = seq!(
+            "mahler::worker::testing::tests::plus_one() -> 4",
+            "mahler::worker::testing::tests::plus_one() -> 4"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
     #[tokio::test]
     async fn it_allows_searching_for_workflow() {
         #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -292,10 +299,98 @@ mod tests {
 
         // We expect a linear DAG with three tasks
         let expected: Dag<&str> = par!(
-            "mahler::worker::testing::tests::plus_one(/one)",
-            "mahler::worker::testing::tests::plus_one(/two)"
-        ) + seq!("mahler::worker::testing::tests::plus_one(/one)",);
+            "mahler::worker::testing::tests::plus_one(/one) -> 2",
+            "mahler::worker::testing::tests::plus_one(/two) -> 1"
+        ) + seq!("mahler::worker::testing::tests::plus_one(/one) -> 2",);
 
         assert_eq!(workflow.to_string(), expected.to_string(),);
     }
+
+    #[tokio::test]
+    async fn it_reports_why_the_target_is_unreachable() {
+        fn max_ten(counter: View<i32>, tgt: Target<i32>) -> Result<View<i32>, task::Error> {
+            if *tgt > 10 {
+                return Err(task::Error::Unreachable(
+                    "counter cannot go above 10".to_string(),
+                ));
+            }
+            Ok(counter)
+        }
+
+        let worker = Worker::new()
+            .job("", update(plus_one))
+            .job("", update(max_ten))
+            .initial_state(0)
+            .unwrap();
+
+        let Err(err) = worker.find_workflow(11).await else {
+            panic!("expected the search to fail");
+        };
+        assert_eq!(err.reason(), Some("counter cannot go above 10"));
+        assert_eq!(
+            err.to_string(),
+            "workflow not found: counter cannot go above 10"
+        );
+    }
+
+    #[tokio::test]
+    async fn it_returns_the_expansion_tree_of_a_method() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Counters(HashMap<String, i32>);
+
+        fn plus_three(counter: View<i32>, tgt: Target<i32>) -> Vec<Task> {
+            if *tgt - *counter < 3 {
+                return vec![];
+            }
+
+            vec![plus_two.with_target(*tgt), plus_one.with_target(*tgt)]
+        }
+
+        let worker = Worker::new()
+            .job("/{counter}", update(plus_one))
+            .job("/{counter}", update(plus_two))
+            .job("/{counter}", update(plus_three))
+            .initial_state::<Counters>(Counters(HashMap::from([("one".to_string(), 0)])))
+            .unwrap();
+
+        let tree = worker
+            .expand_tree(plus_three.with_target(3).with_arg("counter", "one"))
+            .await
+            .unwrap();
+
+        let node = |id: &str, children| ExpansionNode {
+            id: id.to_string(),
+            path: String::from("/one"),
+            children,
+        };
+
+        assert_eq!(
+            tree,
+            node(
+                plus_three.id(),
+                vec![
+                    node(
+                        plus_two.id(),
+                        vec![node(plus_one.id(), vec![]), node(plus_one.id(), vec![])]
+                    ),
+                    node(plus_one.id(), vec![]),
+                ]
+            )
+        );
+
+        // The expansion does not modify the worker state
+        let state = worker.state().await.unwrap();
+        assert_eq!(state, Counters(HashMap::from([("one".to_string(), 0)])));
+    }
+
+    #[tokio::test]
+    async fn it_fails_to_expand_methods_with_unregistered_children() {
+        let worker = Worker::new()
+            .job("", update(plus_two))
+            .initial_state::<i32>(0)
+            .unwrap();
+
+        let res = worker.expand_tree(plus_two.with_target(2)).await;
+        assert!(matches!(res, Err(task::Error::CannotExpandMethod(_))));
+    }
 }
@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::ops::Add;
 use std::sync::{Arc, RwLock};
@@ -322,27 +323,140 @@ impl<T> Default for Dag<T> {
     }
 }
 
-impl<T: PartialEq> PartialEq for Dag<T> {
-    fn eq(&self, other: &Self) -> bool {
-        for (left, rght) in self.iter().zip(other.iter()) {
-            if let (
-                Node::Item {
-                    value: left_value, ..
-                },
-                Node::Item {
-                    value: rght_value, ..
+/// Element of the structural representation of a DAG used for comparisons
+enum Shape<T> {
+    Item(Arc<RwLock<Node<T>>>),
+    Fork(Vec<Vec<Shape<T>>>),
+}
+
+impl<T> Shape<T> {
+    /// Get the structure of the DAG starting at the given link and until
+    /// reaching the end of the DAG or the end of a branch.
+    ///
+    /// Returns the structure and the link following the end of the branch
+    fn from_link(link: &Link<T>) -> (Vec<Shape<T>>, Link<T>) {
+        let mut shape = Vec::new();
+        let mut current = link.clone();
+        while let Some(node_rc) = current {
+            current = match &*node_rc.read().unwrap() {
+                Node::Item { next, .. } => {
+                    shape.push(Shape::Item(node_rc.clone()));
+                    next.clone()
+                }
+                Node::Fork { next } => {
+                    let mut branches = Vec::new();
+                    let mut after_join = None;
+                    for branch in next.iter() {
+                        let (branch, next) = Shape::from_link(branch);
+                        branches.push(branch);
+                        after_join = next;
+                    }
+                    shape.push(Shape::Fork(branches));
+                    after_join
+                }
+                Node::Join { next } => return (shape, next.clone()),
+            };
+        }
+        (shape, None)
+    }
+}
+
+impl<T: Clone> Shape<T> {
+    /// Convert the structure into a list of serializable steps
+    fn into_steps(shape: Vec<Shape<T>>) -> Vec<Step<T>> {
+        shape
+            .into_iter()
+            .filter_map(|elem| match elem {
+                Shape::Item(node_rc) => match &*node_rc.read().unwrap() {
+                    Node::Item { value, .. } => Some(Step::Item(value.clone())),
+                    _ => None,
                 },
-            ) = (&*left.read().unwrap(), &*rght.read().unwrap())
-            {
-                if left_value != rght_value {
+                Shape::Fork(branches) => Some(Step::Fork(
+                    branches.into_iter().map(Shape::into_steps).collect(),
+                )),
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Serializable representation of the DAG structure
+///
+/// A DAG is represented as a list of steps, where each step is either a single value or a
+/// fork of sequential branches
+pub(crate) enum Step<T> {
+    Item(T),
+    Fork(Vec<Vec<Step<T>>>),
+}
+
+impl<T> Step<T> {
+    /// Convert the values of the step using the given function
+    pub fn try_map<U, E>(self, f: &mut impl FnMut(T) -> Result<U, E>) -> Result<Step<U>, E> {
+        match self {
+            Step::Item(value) => f(value).map(Step::Item),
+            Step::Fork(branches) => branches
+                .into_iter()
+                .map(|branch| branch.into_iter().map(|step| step.try_map(f)).collect())
+                .collect::<Result<_, _>>()
+                .map(Step::Fork),
+        }
+    }
+}
+
+impl<T: PartialEq> Shape<T> {
+    fn seq_eq(left: &[Shape<T>], rght: &[Shape<T>]) -> bool {
+        left.len() == rght.len() && left.iter().zip(rght.iter()).all(|(l, r)| l.eq(r))
+    }
+
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Shape::Item(left), Shape::Item(rght)) => {
+                match (&*left.read().unwrap(), &*rght.read().unwrap()) {
+                    (Node::Item { value: left, .. }, Node::Item { value: rght, .. }) => {
+                        left == rght
+                    }
+                    _ => false,
+                }
+            }
+            // Forks are equal if every branch can be matched to an equal
+            // branch on the other fork, independently of the branch order
+            (Shape::Fork(left), Shape::Fork(rght)) => {
+                if left.len() != rght.len() {
                     return false;
                 }
-            } else {
-                return false;
+                let mut matched = vec![false; rght.len()];
+                left.iter().all(|l| {
+                    let pos = rght
+                        .iter()
+                        .enumerate()
+                        .position(|(i, r)| !matched[i] && Shape::seq_eq(l, r));
+                    if let Some(i) = pos {
+                        matched[i] = true;
+                    }
+                    pos.is_some()
+                })
             }
+            _ => false,
         }
+    }
+}
 
-        true
+/// Two DAGs are equal if they have the same structure and values, independently
+/// of the ordering of parallel branches
+///
+/// ```rust
+/// use mahler::{Dag, dag, seq};
+///
+/// let left: Dag<i32> = seq!(1) + dag!(seq!(2, 3), seq!(4));
+/// let rght: Dag<i32> = seq!(1) + dag!(seq!(4), seq!(2, 3));
+/// assert!(left == rght);
+/// ```
+impl<T: PartialEq> PartialEq for Dag<T> {
+    fn eq(&self, other: &Self) -> bool {
+        let (left, _) = Shape::from_link(&self.head);
+        let (rght, _) = Shape::from_link(&other.head);
+        Shape::seq_eq(&left, &rght)
     }
 }
 
@@ -470,6 +584,21 @@ impl<T> Dag<T> {
         self.tail.is_none()
     }
 
+    /// Add a single element at the end of the DAG
+    ///
+    /// If the DAG ends in a fork, the element is added after the branches join.
+    ///
+    /// # Example
+    /// ```rust
+    /// use mahler::{Dag, seq};
+    ///
+    /// let dag: Dag<i32> = Dag::new([seq!(1), seq!(2)]).append(3);
+    /// assert_eq!(dag.to_string(), "+ ~ - 1\n  ~ - 2\n- 3");
+    /// ```
+    pub fn append(self, value: impl Into<T>) -> Self {
+        self.concat(Dag::seq([value]))
+    }
+
     /// Join two DAGs
     pub fn concat(self, other: impl Into<Dag<T>>) -> Self {
         let other = other.into();
@@ -505,6 +634,51 @@ impl<T> Dag<T> {
         }
     }
 
+    /// Return an iterator over the DAG values in topological order
+    ///
+    /// Branches of a fork are visited one after the other, in the order
+    /// they were given to [`Dag::new`]. Every value is visited after the values
+    /// that precede it in the DAG.
+    ///
+    /// # Example
+    /// ```rust
+    /// use mahler::{Dag, seq};
+    ///
+    /// let dag: Dag<i32> = seq!(1) + Dag::new([seq!(2, 3), seq!(4)]) + seq!(5);
+    /// let values: Vec<i32> = dag.values().collect();
+    /// assert_eq!(values, vec![1, 2, 3, 4, 5]);
+    /// ```
+    pub fn values(&self) -> impl Iterator<Item = T>
+    where
+        T: Clone,
+    {
+        self.iter().filter_map(|node| match &*node.read().unwrap() {
+            Node::Item { value, .. } => Some(value.clone()),
+            _ => None,
+        })
+    }
+
+    /// Return the serializable structure of the DAG
+    pub(crate) fn to_steps(&self) -> Vec<Step<T>>
+    where
+        T: Clone,
+    {
+        let (shape, _) = Shape::from_link(&self.head);
+        Shape::into_steps(shape)
+    }
+
+    /// Create a DAG from its serializable structure
+    pub(crate) fn from_steps(steps: Vec<Step<T>>) -> Dag<T> {
+        steps
+            .into_iter()
+            .fold(Dag::default(), |dag, step| match step {
+                Step::Item(value) => dag.append(value),
+                Step::Fork(branches) => {
+                    dag.concat(Dag::new(branches.into_iter().map(Dag::from_steps)))
+                }
+            })
+    }
+
     /// Return `true` if there is any node in the DAG that meets the given condition
     pub fn any(&self, condition: impl Fn(&T) -> bool) -> bool {
         for node in self.iter() {
@@ -712,7 +886,15 @@ pub trait Task {
     type Changes;
     type Error;
 
-    async fn run(&self, input: &Self::Input) -> Result<Self::Changes, Self::Error>;
+    /// Run the task on the given input
+    ///
+    /// The task may communicate intermediate changes via the `progress` channel before
+    /// returning. The returned changes are sent to the channel once the task terminates.
+    async fn run(
+        &self,
+        input: &Self::Input,
+        progress: &Sender<Self::Changes>,
+    ) -> Result<Self::Changes, Self::Error>;
 }
 
 impl<T> Dag<T>
@@ -745,9 +927,10 @@ where
         async fn run_task<T: Task>(
             task: T,
             value: &T::Input,
+            channel: &Sender<T::Changes>,
             interrupt: &Interrupt,
         ) -> Result<T::Changes, InnerError<T::Error>> {
-            let future = task.run(value);
+            let future = task.run(value, channel);
 
             // XXX: this assumes tasks are cancel-safe which might be a source
             // of problems in the future
@@ -804,7 +987,7 @@ where
                             guard.clone()
                         };
 
-                        match run_task(task, &value, interrupt).await {
+                        match run_task(task, &value, channel, interrupt).await {
                             Ok(changes) => {
                                 // Send task changes back to the channel, it is the
                                 // receiver responsibility to merge changes back on the shared
@@ -956,6 +1139,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn it_converts_a_dag_to_steps_and_back() {
+        let dag: Dag<i32> = seq!(1) + dag!(seq!(2, 3), seq!(4) + dag!(seq!(5), seq!(6))) + seq!(7);
+
+        let steps = serde_json::to_value(dag.to_steps()).unwrap();
+        assert_eq!(
+            steps,
+            serde_json::json!([
+                {"item": 1},
+                {"fork": [
+                    [{"item": 2}, {"item": 3}],
+                    [{"item": 4}, {"fork": [[{"item": 5}], [{"item": 6}]]}]
+                ]},
+                {"item": 7}
+            ])
+        );
+
+        let steps: Vec<Step<i32>> = serde_json::from_value(steps).unwrap();
+        assert_str_eq!(Dag::from_steps(steps).to_string(), dag.to_string());
+    }
+
     #[test]
     fn test_iterate_linear_graph() {
         let elements = vec![1, 2, 3];
@@ -996,6 +1200,36 @@ mod tests {
         assert_eq!(elems, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13])
     }
 
+    #[test]
+    fn test_dags_with_swapped_branches_are_equal() {
+        let left: Dag<i32> = seq!(1)
+            + dag!(
+                seq!(2) + dag!(seq!(3), seq!(4, 5)),
+                seq!(6),
+                dag!(seq!(7), seq!(8)) + seq!(9)
+            )
+            + seq!(10);
+        let rght: Dag<i32> = seq!(1)
+            + dag!(
+                dag!(seq!(8), seq!(7)) + seq!(9),
+                seq!(2) + dag!(seq!(4, 5), seq!(3)),
+                seq!(6)
+            )
+            + seq!(10);
+        assert!(left == rght);
+        assert_ne!(left.to_string(), rght.to_string());
+    }
+
+    #[test]
+    fn test_dags_with_different_structure_are_not_equal() {
+        let dag: Dag<i32> = seq!(1) + dag!(seq!(2, 3), seq!(4));
+        assert!(dag != seq!(1) + dag!(seq!(2), seq!(3, 4)));
+        assert!(dag != seq!(1) + dag!(seq!(2, 3), seq!(4)) + seq!(5));
+        assert!(dag != seq!(1, 2, 3, 4));
+        assert!(dag != seq!(1) + dag!(seq!(2, 3), seq!(4), seq!(5)));
+        assert!(Dag::<i32>::default() == Dag::default());
+    }
+
     #[test]
     fn test_empty_dag_string_representation() {
         let dag: Dag<char> = Dag::default();
@@ -1106,7 +1340,11 @@ mod tests {
             type Changes = ();
             type Error = ();
 
-            async fn run(&self, _input: &Self::Input) -> Result<Self::Changes, Self::Error> {
+            async fn run(
+                &self,
+                _input: &Self::Input,
+                _progress: &Sender<Self::Changes>,
+            ) -> Result<Self::Changes, Self::Error> {
                 Ok(())
             }
         }
@@ -1143,7 +1381,11 @@ mod tests {
         type Changes = &'static str;
         type Error = ();
 
-        async fn run(&self, _input: &Self::Input) -> Result<Self::Changes, Self::Error> {
+        async fn run(
+            &self,
+            _input: &Self::Input,
+            _progress: &Sender<Self::Changes>,
+        ) -> Result<Self::Changes, Self::Error> {
             tokio::time::sleep(std::time::Duration::from_millis(self.delay_ms)).await;
             Ok(self.name)
         }
@@ -1265,7 +1507,11 @@ mod tests {
         type Input = ();
         type Changes = &'static str;
         type Error = &'static str; // Simple error
-        async fn run(&self, _input: &Self::Input) -> Result<Self::Changes, Self::Error> {
+        async fn run(
+            &self,
+            _input: &Self::Input,
+            _progress: &Sender<Self::Changes>,
+        ) -> Result<Self::Changes, Self::Error> {
             if self.fail {
                 Err("task failed")
             } else {
@@ -1,17 +1,23 @@
 //! Types and utilities to generate and execute task Workflows
 
+use anyhow::Context as _;
 use async_trait::async_trait;
 use json_patch::{Patch, PatchOperation};
+use serde::{de::DeserializeOwned, Deserialize, Serialize, Serializer};
 use serde_json::Value;
 use std::collections::hash_map::DefaultHasher;
 use std::fmt::{self, Display};
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::instrument;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tracing::{instrument, warn};
 
+use crate::errors::{InternalError, SerializationError};
+use crate::path::Path;
 use crate::system::System;
-use crate::task::{Action, Error as TaskError};
+use crate::task::{Action, Context, Error as TaskError, Task as JobTask};
 
 mod aggregate_error;
 mod channel;
@@ -36,7 +42,44 @@ struct WorkUnitId<'s> {
     state: &'s Value,
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
+/// A task applied changes to the system that differ from the changes predicted during planning
+///
+/// This may happen if the effectful part of a task does not match its pure part. The worker
+/// applies the actual changes to the system. See
+/// [`Worker::follow_divergences`](`crate::worker::Worker::follow_divergences`)
+pub struct Divergence {
+    task: String,
+    path: String,
+    predicted: Value,
+    actual: Value,
+}
+
+impl Divergence {
+    /// Return the id of the task
+    pub fn task(&self) -> &str {
+        &self.task
+    }
+
+    /// Return the path the task was applied to
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Return the changes predicted during planning as a [JSON
+    /// Patch](https://datatracker.ietf.org/doc/html/rfc6902)
+    pub fn predicted(&self) -> &Value {
+        &self.predicted
+    }
+
+    /// Return the changes applied to the system as a [JSON
+    /// Patch](https://datatracker.ietf.org/doc/html/rfc6902)
+    pub fn actual(&self) -> &Value {
+        &self.actual
+    }
+}
+
+#[derive(Clone)]
 /// Utility type to encode a single work unit in a workflow
 pub(crate) struct WorkUnit {
     /// Unique id for the action. This is calculed by hashing a WorkUnitId
@@ -52,12 +95,47 @@ pub(crate) struct WorkUnit {
     /// This will be used at runtime to compare to the result of [dry_run](`crate::task::Action::dry_run`)
     /// and abort the execution if that fails
     output: Vec<PatchOperation>,
+
+    /// Maximum time the action is allowed to run for
+    ///
+    /// This is only set by the worker right before execution
+    timeout: Option<Duration>,
+
+    /// Channel to report changes that diverge from the output
+    ///
+    /// This is only set by the worker right before execution
+    divergences: Option<broadcast::Sender<Divergence>>,
+}
+
+// The execution options are not part of the identity of the work unit
+impl PartialEq for WorkUnit {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.action == other.action && self.output == other.output
+    }
 }
 
+impl Eq for WorkUnit {}
+
 impl WorkUnit {
     /// Create a new WorkUnit
     pub fn new(id: u64, action: Action, output: Vec<PatchOperation>) -> Self {
-        Self { id, action, output }
+        Self {
+            id,
+            action,
+            output,
+            timeout: None,
+            divergences: None,
+        }
+    }
+
+    /// Get the paths the action reads from, including the paths it writes to
+    pub(crate) fn reads(&self) -> Vec<Path> {
+        self.action.reads()
+    }
+
+    /// Get the paths the action writes to
+    pub(crate) fn writes(&self) -> Vec<Path> {
+        self.action.writes()
     }
 
     /// Calculate the id of a given action and state value.
@@ -87,6 +165,74 @@ impl WorkUnit {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+/// Serializable representation of a work unit
+///
+/// The action handler cannot be serialized, so only the task id and context are stored. The
+/// action is bound again to its handler when rehydrating the workflow
+pub(crate) struct SerializedWorkUnit {
+    id: u64,
+    task: String,
+    path: String,
+    args: Vec<(String, String)>,
+    target: Value,
+    output: Vec<PatchOperation>,
+}
+
+impl From<&WorkUnit> for SerializedWorkUnit {
+    fn from(unit: &WorkUnit) -> Self {
+        let context = unit.action.context();
+        Self {
+            id: unit.id,
+            task: unit.action.id().to_string(),
+            path: context.path.to_string(),
+            args: context
+                .args
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+            target: context.target.clone(),
+            output: unit.output.clone(),
+        }
+    }
+}
+
+impl SerializedWorkUnit {
+    /// Re-bind the work unit to its action using the given task lookup function
+    ///
+    /// The lookup function receives the task id and the context and returns the task
+    /// created by the matching job, if any.
+    pub fn rehydrate(
+        self,
+        new_task: impl FnOnce(&str, Context) -> Option<JobTask>,
+    ) -> Result<WorkUnit, RehydrateError> {
+        let SerializedWorkUnit {
+            id,
+            task,
+            path,
+            args,
+            target,
+            output,
+        } = self;
+
+        let mut context = Context::new().with_path(&path).with_target(target);
+        for (key, value) in args {
+            context = context.with_arg(key, value);
+        }
+
+        match new_task(&task, context) {
+            Some(JobTask::Action(action)) => Ok(WorkUnit::new(id, action, output)),
+            _ => Err(RehydrateError::ActionNotFound { id: task, path }),
+        }
+    }
+}
+
+impl Serialize for WorkUnit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedWorkUnit::from(self).serialize(serializer)
+    }
+}
+
 impl Display for WorkUnit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.action.fmt(f)
@@ -99,8 +245,19 @@ impl Task for WorkUnit {
     type Changes = Patch;
     type Error = TaskError;
 
-    #[instrument(name="run_task", skip_all, fields(id=%self.action.id(), task=%self.action, state=%system.root()), err)]
-    async fn run(&self, system: &System) -> Result<Patch, TaskError> {
+    #[instrument(
+        name = "run_task",
+        skip_all,
+        fields(
+            task.id = %self.action.id(),
+            task.path = %self.action.context().path,
+            work.id = self.id,
+            task = %self.action.describe_on(system),
+            state = %system.root()
+        ),
+        err
+    )]
+    async fn run(&self, system: &System, progress: &Sender<Patch>) -> Result<Patch, TaskError> {
         // dry-run the task to test that conditions hold
         // before executing the action should not really fail at this point
         let Patch(changes) = self.action.dry_run(system)?;
@@ -110,7 +267,77 @@ impl Task for WorkUnit {
             return Err(TaskError::ConditionFailed);
         }
 
-        self.action.run(system).await
+        // Intermediate results reported by the task are relative to the initial
+        // system, so we forward them as the difference with the last reported state
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let run = self.action.run_with_progress(
+            system,
+            Box::new(move |changes| {
+                // The receiver is only dropped after the action terminates
+                let _ = tx.send(changes);
+            }),
+        );
+        let forward = async {
+            let mut last: Option<System> = None;
+            while let Some(changes) = rx.recv().await {
+                let mut next = system.clone();
+                if let Err(e) = next.patch(changes) {
+                    warn!("failed to apply intermediate changes: {e} ... ignoring");
+                    continue;
+                }
+
+                let prev = last.as_ref().unwrap_or(system);
+                if progress
+                    .send(json_patch::diff(prev.root(), next.root()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+                last = Some(next);
+            }
+            last
+        };
+        let run = async {
+            match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, run)
+                    .await
+                    .unwrap_or(Err(TaskError::Timeout(timeout))),
+                None => run.await,
+            }
+        };
+        let (res, last) = tokio::join!(run, forward);
+
+        // The changes returned by the IO part of the task are the ones applied
+        // to the system, report if these diverge from the planned changes
+        let Patch(changes) = res?;
+        if changes != self.output {
+            warn!(
+                name: "divergence",
+                predicted = ?self.output,
+                actual = ?changes,
+                "task changes diverged from the planned changes"
+            );
+            if let Some(divergences) = &self.divergences {
+                // Sending only fails if there are no receivers
+                let _ = divergences.send(Divergence {
+                    task: self.action.id().to_string(),
+                    path: self.action.context().path.to_string(),
+                    predicted: serde_json::to_value(&self.output).unwrap_or_default(),
+                    actual: serde_json::to_value(&changes).unwrap_or_default(),
+                });
+            }
+        }
+
+        // If intermediate changes were reported, only return the remaining changes
+        if let Some(last) = last {
+            let mut next = system.clone();
+            if next.patch(Patch(changes.clone())).is_ok() {
+                return Ok(json_patch::diff(last.root(), next.root()));
+            }
+        }
+
+        Ok(Patch(changes))
     }
 }
 
@@ -133,9 +360,107 @@ impl Task for WorkUnit {
 ///
 /// Workflow implements [`Display`], using the [string representation defined for
 /// Dag](`Dag#string-representation-of-a-dag`), where each task is rendered from its provided
-/// [description](`crate::task::Job::with_description`).
+/// [description](`crate::task::Job::with_description`). Tasks without a description are
+/// rendered with their id, path and target.
 pub struct Workflow(pub(crate) Dag<WorkUnit>);
 
+/// Result of searching for a workflow to a target
+///
+/// The planner returns an empty workflow if the system is already at the target, `PlanResult`
+/// makes that case explicit.
+///
+/// ```rust
+/// use mahler::task::prelude::*;
+/// use mahler::extract::{View, Target};
+/// use mahler::worker::Worker;
+/// use mahler::workflow::PlanResult;
+///
+/// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+///    if *counter < tgt {
+///        *counter += 1;
+///    }
+///    counter
+/// }
+///
+/// # tokio_test::block_on(async {
+/// let worker = Worker::new()
+///                 .job("", update(plus_one))
+///                 .initial_state(1)
+///                 .unwrap();
+///
+/// assert!(worker.find_plan(1).await.unwrap().is_reached());
+/// match worker.find_plan(2).await.unwrap() {
+///     PlanResult::Reached => panic!("the counter is not at the target"),
+///     PlanResult::Plan(workflow) => assert_eq!(workflow.simulate_state(1).unwrap(), 2),
+/// }
+/// # })
+/// ```
+pub enum PlanResult {
+    /// The system is already at the target, no work is needed
+    Reached,
+    /// The workflow that takes the system to the target
+    Plan(Workflow),
+}
+
+impl PlanResult {
+    /// Return true if the system is already at the target
+    pub fn is_reached(&self) -> bool {
+        matches!(self, PlanResult::Reached)
+    }
+
+    /// Return the workflow to the target, which is empty if the target was already reached
+    pub fn into_workflow(self) -> Workflow {
+        match self {
+            PlanResult::Reached => Workflow::default(),
+            PlanResult::Plan(workflow) => workflow,
+        }
+    }
+}
+
+impl From<Workflow> for PlanResult {
+    fn from(workflow: Workflow) -> Self {
+        if workflow.is_empty() {
+            return PlanResult::Reached;
+        }
+        PlanResult::Plan(workflow)
+    }
+}
+
+#[derive(Debug, Error)]
+/// An error happened while simulating a workflow
+pub enum SimulationError {
+    #[error(transparent)]
+    /// The state could not be serialized or deserialized
+    Serialization(#[from] SerializationError),
+
+    #[error(transparent)]
+    /// A task in the workflow failed during simulation, e.g. because
+    /// its condition does not hold on the simulated state
+    Task(#[from] TaskError),
+
+    #[error(transparent)]
+    /// The changes of a task could not be applied to the simulated state
+    Internal(#[from] InternalError),
+}
+
+#[derive(Debug, Error)]
+#[error("loop detected: task '{0}' is applied to the same state in both workflows")]
+/// Two workflows could not be concatenated as a task of the second workflow is applied to
+/// the same state as a task of the first workflow
+pub struct LoopDetected(String);
+
+#[derive(Debug, Error)]
+/// A serialized workflow could not be bound to the jobs of the worker domain
+pub enum RehydrateError {
+    #[error(transparent)]
+    /// The serialized workflow has an invalid format
+    Serialization(#[from] SerializationError),
+
+    #[error("no action with id '{id}' found for path '{path}'")]
+    /// The task in the serialized workflow is not an action registered in the domain
+    ActionNotFound { id: String, path: String },
+}
+
 /// Runtime status of a workflow execution
 pub(crate) enum WorkflowStatus {
     /// The workflow execution terminated successfully
@@ -164,6 +489,226 @@ impl Workflow {
         self.0.is_empty()
     }
 
+    /// Append another workflow to be executed after this one
+    ///
+    /// The tasks of `other` are only executed once every task of this workflow has
+    /// terminated. Returns [`LoopDetected`] if any task in `other` is applied to the same state
+    /// as a task in this workflow. Clones of either workflow are not modified.
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    /// use mahler::extract::{View, Target};
+    /// use mahler::worker::Worker;
+    /// use mahler::{Dag, seq};
+    ///
+    /// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///    if *counter < tgt {
+    ///        *counter += 1;
+    ///    }
+    ///    counter
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker = Worker::new()
+    ///                 .job("", update(plus_one).with_description(|| "+1"))
+    ///                 .initial_state(0)
+    ///                 .unwrap();
+    /// let prep = worker.find_workflow(1).await.unwrap();
+    /// let main = worker.find_workflow(2).await.unwrap();
+    ///
+    /// // The same task cannot be applied twice to the same state
+    /// assert!(prep.clone().concat(main).is_err());
+    /// # })
+    /// ```
+    pub fn concat(self, other: Workflow) -> Result<Workflow, LoopDetected> {
+        let Workflow(dag) = self;
+        let Workflow(next) = other;
+
+        if next.is_empty() {
+            return Ok(Workflow(dag));
+        }
+
+        if let Some(unit) = next
+            .values()
+            .find(|unit| dag.any(|other| other.id == unit.id))
+        {
+            return Err(LoopDetected(unit.to_string()));
+        }
+
+        // Dag nodes are shared between clones, so the workflows are copied
+        // before joining them
+        let dag = Dag::from_steps(dag.to_steps());
+        let next = Dag::from_steps(next.to_steps());
+        Ok(Workflow(dag.concat(next)))
+    }
+
+    /// Simulate the workflow execution on the given system
+    ///
+    /// Each task in the workflow is tested on a copy of the system, applying its changes before
+    /// testing the next task, without running any effectful operations. Tasks in concurrent
+    /// branches are simulated one after the other. Returns the predicted system after the
+    /// workflow execution.
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    /// use mahler::extract::{View, Target};
+    /// use mahler::worker::Worker;
+    /// use mahler::System;
+    ///
+    /// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///    if *counter < tgt {
+    ///        *counter += 1;
+    ///    }
+    ///    counter
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker = Worker::new()
+    ///                 .job("", update(plus_one))
+    ///                 .initial_state(0)
+    ///                 .unwrap();
+    /// let workflow = worker.find_plan(2).await.unwrap().into_workflow();
+    ///
+    /// let system = System::try_from(0).unwrap();
+    /// let predicted = workflow.simulate(&system).unwrap();
+    /// assert_eq!(predicted.state::<i32>().unwrap(), 2);
+    /// # })
+    /// ```
+    pub fn simulate(&self, system: &System) -> Result<System, SimulationError> {
+        let mut system = system.clone();
+        for unit in self.0.values() {
+            let changes = unit.action.dry_run(&system)?;
+            system
+                .patch(changes)
+                .with_context(|| format!("failed to apply changes for task {}", unit.action))
+                .map_err(InternalError::from)?;
+        }
+        Ok(system)
+    }
+
+    /// Simulate the workflow execution on the given state
+    ///
+    /// This is a convenience method that converts the state to a [`System`] before calling
+    /// [`simulate`](Self::simulate), and returns the predicted state as the same type.
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    /// use mahler::extract::{View, Target};
+    /// use mahler::worker::Worker;
+    ///
+    /// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///    if *counter < tgt {
+    ///        *counter += 1;
+    ///    }
+    ///    counter
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker = Worker::new()
+    ///                 .job("", update(plus_one))
+    ///                 .initial_state(0)
+    ///                 .unwrap();
+    /// let workflow = worker.find_plan(2).await.unwrap().into_workflow();
+    ///
+    /// assert_eq!(workflow.simulate_state(0).unwrap(), 2);
+    /// # })
+    /// ```
+    pub fn simulate_state<S>(&self, state: S) -> Result<S, SimulationError>
+    where
+        S: Serialize + DeserializeOwned,
+    {
+        let system = System::try_from(state).map_err(SerializationError::from)?;
+        let system = self.simulate(&system)?;
+        let state = system.state().map_err(SerializationError::from)?;
+        Ok(state)
+    }
+
+    /// Return the predicted state of the system after each work unit
+    ///
+    /// States are calculated by applying the changes recorded for each work unit during
+    /// planning, in the order given by [`Dag::values`]
+    pub(crate) fn predicted_states(&self, system: &System) -> Result<Vec<Value>, InternalError> {
+        let mut system = system.clone();
+        let mut states = Vec::new();
+        for unit in self.0.values() {
+            system
+                .patch(Patch(unit.output))
+                .with_context(|| format!("failed to apply changes for task {}", unit.action))?;
+            states.push(system.root().clone());
+        }
+        Ok(states)
+    }
+
+    /// Return the changes of the workflow as a [JSON Merge Patch](https://datatracker.ietf.org/doc/html/rfc7386)
+    ///
+    /// The patch is calculated from the changes predicted for each task during planning, and
+    /// takes the given system to the state expected after the workflow execution when applied
+    /// with [`System::patch_merge`].
+    ///
+    /// Merge patches cannot represent every change. Arrays are always replaced as a whole, and
+    /// a value that is set to `null` by the workflow is removed from the state instead.
+    ///
+    /// ```rust
+    /// use mahler::task::prelude::*;
+    /// use mahler::extract::{View, Target};
+    /// use mahler::worker::Worker;
+    /// use mahler::System;
+    /// use serde_json::json;
+    ///
+    /// fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    ///    if *counter < tgt {
+    ///        *counter += 1;
+    ///    }
+    ///    counter
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let worker = Worker::new()
+    ///                 .job("", update(plus_one))
+    ///                 .initial_state(0)
+    ///                 .unwrap();
+    /// let workflow = worker.find_workflow(2).await.unwrap();
+    ///
+    /// let system = System::try_from(0).unwrap();
+    /// assert_eq!(workflow.as_merge_patch(&system).unwrap(), json!(2));
+    /// # })
+    /// ```
+    pub fn as_merge_patch(&self, system: &System) -> Result<Value, InternalError> {
+        // The last predicted state is the state after the workflow execution
+        let states = self.predicted_states(system)?;
+        let after = states.last().unwrap_or(system.root());
+        Ok(merge_diff(system.root(), after))
+    }
+
+    /// Set the maximum time every action in the workflow is allowed to run for
+    pub(crate) fn with_action_timeout(self, timeout: Option<Duration>) -> Self {
+        if timeout.is_none() {
+            return self;
+        }
+        self.map_units(|unit| WorkUnit { timeout, ..unit })
+    }
+
+    /// Report changes that diverge from the planned changes to the given channel
+    pub(crate) fn with_divergences(self, divergences: broadcast::Sender<Divergence>) -> Self {
+        self.map_units(|unit| WorkUnit {
+            divergences: Some(divergences.clone()),
+            ..unit
+        })
+    }
+
+    fn map_units(self, f: impl Fn(WorkUnit) -> WorkUnit) -> Self {
+        let steps = self
+            .0
+            .to_steps()
+            .into_iter()
+            .map(|step| {
+                step.try_map(&mut |unit: WorkUnit| Ok::<_, std::convert::Infallible>(f(unit)))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| match e {});
+        Workflow(Dag::from_steps(steps))
+    }
+
     #[instrument(name = "run_workflow", skip_all, err)]
     pub(crate) async fn execute(
         self,
@@ -178,8 +723,260 @@ impl Workflow {
     }
 }
 
+/// Calculate the merge patch that takes the `from` value to the `to` value
+fn merge_diff(from: &Value, to: &Value) -> Value {
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            let mut changes = serde_json::Map::new();
+            for key in from.keys().filter(|key| !to.contains_key(*key)) {
+                changes.insert(key.clone(), Value::Null);
+            }
+            for (key, value) in to.iter() {
+                match from.get(key) {
+                    Some(prev) if prev == value => {}
+                    Some(prev) => {
+                        changes.insert(key.clone(), merge_diff(prev, value));
+                    }
+                    None => {
+                        changes.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            Value::Object(changes)
+        }
+        _ => to.clone(),
+    }
+}
+
+/// Workflows can be serialized to cache the result of planning
+///
+/// The serialized workflow includes the id and context of every task, along with the
+/// changes predicted during planning. Use [`Worker::rehydrate`](`crate::worker::Worker::rehydrate`)
+/// to bind the tasks back to their handlers.
+impl Serialize for Workflow {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.to_steps().serialize(serializer)
+    }
+}
+
 impl Display for Workflow {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.0.fmt(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::{Args, Target, View};
+    use crate::seq;
+    use crate::task::prelude::*;
+    use crate::worker::Worker;
+    use pretty_assertions::assert_eq;
+
+    fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+        if *counter < tgt {
+            *counter += 1;
+        }
+        counter
+    }
+
+    #[tokio::test]
+    async fn it_concatenates_workflows() {
+        let job =
+            update(plus_one).with_description(|Target(tgt): Target<i32>| format!("+1 to {tgt}"));
+
+        let prep = Worker::new()
+            .job("", job.clone())
+            .initial_state(0)
+            .unwrap()
+            .find_plan(2)
+            .await
+            .unwrap()
+            .into_workflow();
+        let main = Worker::new()
+            .job("", job)
+            .initial_state(2)
+            .unwrap()
+            .find_plan(4)
+            .await
+            .unwrap()
+            .into_workflow();
+
+        let workflow = prep.concat(main).unwrap();
+
+        let expected: Dag<&str> = seq!("+1 to 2", "+1 to 2", "+1 to 4", "+1 to 4");
+        assert_eq!(workflow.to_string(), expected.to_string());
+        assert_eq!(workflow.simulate_state(0).unwrap(), 4);
+    }
+
+    async fn increment(from: i32, to: i32) -> Workflow {
+        Worker::new()
+            .job("", update(plus_one).with_description(|| "+1"))
+            .initial_state(from)
+            .unwrap()
+            .find_plan(to)
+            .await
+            .unwrap()
+            .into_workflow()
+    }
+
+    #[tokio::test]
+    async fn it_does_not_modify_clones_when_concatenating() {
+        let prep = increment(0, 1).await;
+        let main = increment(1, 2).await;
+
+        let workflow = prep.clone().concat(main.clone()).unwrap();
+        let expected: Dag<&str> = seq!("+1", "+1");
+        assert_eq!(workflow.to_string(), expected.to_string());
+
+        // The original workflows are not modified
+        let expected: Dag<&str> = seq!("+1");
+        assert_eq!(prep.to_string(), expected.to_string());
+        assert_eq!(main.to_string(), expected.to_string());
+        assert_eq!(prep.simulate_state(0).unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn it_concatenates_an_empty_workflow() {
+        let workflow = increment(0, 1).await.concat(Workflow::default()).unwrap();
+        assert!(!workflow.is_empty());
+
+        // Workflows can still be appended to the result
+        let workflow = workflow.concat(increment(1, 2).await).unwrap();
+        let expected: Dag<&str> = seq!("+1", "+1");
+        assert_eq!(workflow.to_string(), expected.to_string());
+
+        let workflow = Workflow::default().concat(Workflow::default()).unwrap();
+        assert!(workflow.is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_renders_task_targets_by_default() {
+        let workflow = Worker::new()
+            .job("", update(plus_one))
+            .initial_state(0)
+            .unwrap()
+            .find_plan(2)
+            .await
+            .unwrap()
+            .into_workflow();
+
+        let expected: Dag<&str> = seq!(
+            "mahler::workflow::tests::plus_one() -> 2",
+            "mahler::workflow::tests::plus_one() -> 2"
+        );
+        assert_eq!(workflow.to_string(), expected.to_string());
+    }
+
+    #[tokio::test]
+    async fn it_rejects_concatenating_workflows_with_loops() {
+        let worker = Worker::new()
+            .job("", update(plus_one).with_description(|| "+1"))
+            .initial_state(0)
+            .unwrap();
+
+        let workflow = worker.find_plan(2).await.unwrap().into_workflow();
+
+        let Err(err) = workflow.clone().concat(workflow) else {
+            panic!("expected a loop to be detected");
+        };
+        assert_eq!(
+            err.to_string(),
+            "loop detected: task '+1' is applied to the same state in both workflows"
+        );
+    }
+
+    #[tokio::test]
+    async fn it_rehydrates_a_serialized_workflow() {
+        use crate::worker::{Ready, StepOutcome};
+        use std::collections::HashMap;
+
+        let job = update(plus_one)
+            .with_description(|Args(counter): Args<String>| format!("+1 to {counter}"));
+        let target = HashMap::from([("a".to_string(), 2), ("b".to_string(), 1)]);
+
+        let worker = Worker::new()
+            .job("/{counter}", job.clone())
+            .initial_state(HashMap::from([("a".to_string(), 0), ("b".to_string(), 0)]))
+            .unwrap();
+        let workflow = worker
+            .find_plan(target.clone())
+            .await
+            .unwrap()
+            .into_workflow();
+        let serialized = serde_json::to_value(&workflow).unwrap();
+
+        // Rehydrate the workflow on a new worker with the same jobs
+        let mut worker: Worker<HashMap<String, i32>, Ready> = Worker::new()
+            .job("/{counter}", job)
+            .initial_state(HashMap::from([("a".to_string(), 0), ("b".to_string(), 0)]))
+            .unwrap();
+        let rehydrated = worker.rehydrate(serialized).unwrap();
+        assert_eq!(rehydrated.to_string(), workflow.to_string());
+
+        assert_eq!(
+            worker.run_workflow(rehydrated).await.unwrap(),
+            StepOutcome::Progressed
+        );
+        assert_eq!(worker.state().await.unwrap(), target);
+    }
+
+    #[tokio::test]
+    async fn it_fails_to_rehydrate_workflows_with_unknown_tasks() {
+        use crate::worker::Ready;
+
+        let workflow = Worker::new()
+            .job("", update(plus_one))
+            .initial_state(0)
+            .unwrap()
+            .find_plan(1)
+            .await
+            .unwrap()
+            .into_workflow();
+        let serialized = serde_json::to_value(&workflow).unwrap();
+
+        let worker: Worker<i32, Ready> = Worker::new()
+            .job("", update(plus_one).with_id("other"))
+            .initial_state(0)
+            .unwrap();
+
+        let Err(err) = worker.rehydrate(serialized) else {
+            panic!("expected rehydration to fail");
+        };
+        assert!(matches!(err, RehydrateError::ActionNotFound { .. }));
+    }
+
+    #[tokio::test]
+    async fn it_converts_a_workflow_to_a_merge_patch() {
+        use serde_json::json;
+        use std::collections::HashMap;
+
+        let initial = HashMap::from([
+            ("one".to_string(), 0),
+            ("two".to_string(), 0),
+            ("three".to_string(), 1),
+        ]);
+        let target = HashMap::from([
+            ("one".to_string(), 2),
+            ("two".to_string(), 1),
+            ("three".to_string(), 1),
+        ]);
+
+        let workflow = Worker::new()
+            .job("/{counter}", update(plus_one))
+            .initial_state(initial.clone())
+            .unwrap()
+            .find_plan(target.clone())
+            .await
+            .unwrap()
+            .into_workflow();
+
+        let mut system = System::try_from(initial).unwrap();
+        let changes = workflow.as_merge_patch(&system).unwrap();
+        assert_eq!(changes, json!({"one": 2, "two": 1}));
+
+        system.patch_merge(&changes);
+        assert_eq!(system.state::<HashMap<String, i32>>().unwrap(), target);
+    }
+}
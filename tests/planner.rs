@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use mahler::extract::{Target, View};
+use mahler::task::prelude::*;
+use mahler::worker::{StepOutcome, Worker};
+use tracing_test::traced_test;
+
+type Counters = HashMap<String, i32>;
+
+fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> View<i32> {
+    if *counter < tgt {
+        *counter += 1;
+    }
+
+    counter
+}
+
+// The condition for this task never holds
+fn never_applies(_: View<i32>) -> Option<View<i32>> {
+    None
+}
+
+#[tokio::test]
+#[traced_test]
+async fn it_traces_candidates_skipped_by_a_failed_condition() {
+    let mut worker = Worker::new()
+        .job("/{counter}", update(never_applies))
+        .job("/{counter}", update(plus_one))
+        .initial_state(Counters::from([("a".to_string(), 0)]))
+        .unwrap();
+
+    let outcome = worker
+        .run_once(Counters::from([("a".to_string(), 1)]))
+        .await
+        .unwrap();
+    assert_eq!(outcome, StepOutcome::Progressed);
+
+    assert!(logs_contain("candidate skipped: condition failed"));
+    assert!(logs_contain("planner::never_applies"));
+    assert!(logs_contain("candidate selected"));
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[tokio::test]
+async fn it_preserves_big_integers_with_arbitrary_precision() {
+    use mahler::workflow::PlanResult;
+    use serde_json::{json, Value};
+
+    fn set_value(mut value: View<Value>, Target(tgt): Target<Value>) -> View<Value> {
+        *value = tgt;
+        value
+    }
+
+    let big: Value = serde_json::from_str("123456789012345678901234567890").unwrap();
+    let initial = json!({"value": 0});
+    let target = json!({ "value": big });
+
+    let worker = Worker::new()
+        .job("/value", update(set_value))
+        .initial_state(initial.clone())
+        .unwrap();
+
+    let PlanResult::Plan(workflow) = worker.find_plan(target.clone()).await.unwrap() else {
+        panic!("expected a workflow");
+    };
+
+    let result: Value = workflow.simulate(initial).unwrap();
+    assert_eq!(result, target);
+    assert_eq!(
+        result["value"].to_string(),
+        "123456789012345678901234567890"
+    );
+}
+
+#[tokio::test]
+async fn it_reports_jobs_that_are_never_selected() {
+    let worker = Worker::new()
+        .job("/{counter}", update(never_applies).with_id("never_applies"))
+        .job("/{counter}", update(plus_one).with_id("plus_one"))
+        .initial_state(Counters::from([("a".to_string(), 0)]))
+        .unwrap();
+
+    let (res, stats) = worker
+        .find_plan_with_job_stats(Counters::from([("a".to_string(), 2)]))
+        .await;
+    assert!(res.is_ok());
+
+    let never_applies = &stats["never_applies"];
+    assert!(never_applies.matched > 0);
+    assert_eq!(never_applies.selected, 0);
+    assert!(never_applies.pruned() > 0);
+    assert_eq!(never_applies.pruned_condition, never_applies.matched);
+
+    assert_eq!(stats["plus_one"].selected, 2);
+}
+
+#[tokio::test]
+async fn it_assigns_the_expanded_wildcard_path_to_the_handler_view() {
+    use mahler::extract::Args;
+    use mahler::workflow::PlanResult;
+    use mahler::{seq, Dag};
+    use serde_json::{json, Value};
+
+    // The wildcard route also matches directories, so the handler only
+    // applies when the view points to a file
+    fn write_file(
+        mut file: View<Value>,
+        Args(path): Args<String>,
+        Target(tgt): Target<Value>,
+    ) -> Option<View<Value>> {
+        if !file.is_string() || !tgt.is_string() {
+            return None;
+        }
+
+        // The path argument captures the full remainder of the route
+        assert_eq!(path, "docs/reports/2024/q1.txt");
+        *file = tgt;
+        Some(file)
+    }
+
+    let initial = json!({
+        "files": {
+            "docs": {"reports": {"2024": {"q1.txt": "draft"}}},
+            "notes.txt": "hello"
+        }
+    });
+    let target = json!({
+        "files": {
+            "docs": {"reports": {"2024": {"q1.txt": "final"}}},
+            "notes.txt": "hello"
+        }
+    });
+
+    let worker = Worker::new()
+        .job("/files/{*path}", update(write_file).with_id("write_file"))
+        .initial_state(initial.clone())
+        .unwrap();
+
+    let PlanResult::Plan(workflow) = worker.find_plan(target.clone()).await.unwrap() else {
+        panic!("expected a workflow");
+    };
+
+    let expected: Dag<&str> = seq!(r#"write_file(/files/docs/reports/2024/q1.txt) -> "final""#);
+    assert_eq!(workflow.to_string(), expected.to_string());
+
+    let result: Value = workflow.simulate_state(initial).unwrap();
+    assert_eq!(result, target);
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use mahler::extract::{Target, View};
+use mahler::task::prelude::*;
+use mahler::task::Effect;
+use mahler::worker::{StepOutcome, Worker};
+use serde_json::json;
+use tokio_stream::StreamExt;
+use tracing::info;
+use tracing_test::traced_test;
+
+type Counters = HashMap<String, i32>;
+
+fn plus_one(mut counter: View<i32>, Target(tgt): Target<i32>) -> IO<i32> {
+    if *counter < tgt {
+        *counter += 1;
+    }
+
+    with_io(counter, |counter| async move {
+        info!("updating counter");
+        Ok(counter)
+    })
+}
+
+#[tokio::test]
+#[traced_test]
+async fn it_nests_handler_logs_under_the_task_span() {
+    let mut worker = Worker::new()
+        .job("/{counter}", update(plus_one))
+        .initial_state(Counters::from([("a".to_string(), 0)]))
+        .unwrap();
+
+    let outcome = worker
+        .run_once(Counters::from([("a".to_string(), 1)]))
+        .await
+        .unwrap();
+    assert_eq!(outcome, StepOutcome::Progressed);
+
+    assert!(logs_contain("updating counter"));
+    logs_assert(|lines: &[&str]| {
+        let line = lines
+            .iter()
+            .find(|line| line.contains("updating counter"))
+            .ok_or("handler log not found")?;
+
+        for field in ["task.id=worker::plus_one", "task.path=/a", "work.id="] {
+            if !line.contains(field) {
+                return Err(format!("field {field} not found in: {line}"));
+            }
+        }
+        Ok(())
+    });
+}
+
+#[tokio::test]
+#[traced_test]
+async fn it_reports_when_task_changes_diverge_from_the_plan() {
+    // The counter is predicted to increase by one but
+    // the IO operation jumps straight to the target
+    fn jump_to_target(mut counter: View<i32>, Target(tgt): Target<i32>) -> Effect<View<i32>> {
+        if *counter < tgt {
+            *counter += 1;
+        }
+
+        Effect::of(counter).with_io(move |mut counter| async move {
+            *counter = tgt;
+            Ok(counter)
+        })
+    }
+
+    let mut worker = Worker::new()
+        .job("/{counter}", update(jump_to_target))
+        .initial_state(Counters::from([("a".to_string(), 0)]))
+        .unwrap();
+    let mut divergences = worker.follow_divergences();
+
+    let outcome = worker
+        .run_once(Counters::from([("a".to_string(), 3)]))
+        .await
+        .unwrap();
+    assert_eq!(outcome, StepOutcome::Progressed);
+
+    // The system reflects the actual changes
+    assert_eq!(
+        worker.state().await.unwrap(),
+        Counters::from([("a".to_string(), 3)])
+    );
+
+    let divergence = divergences.next().await.unwrap();
+    assert_eq!(divergence.path(), "/a");
+    assert_eq!(
+        divergence.predicted(),
+        &json!([{"op": "replace", "path": "/a", "value": 1}])
+    );
+    assert_eq!(
+        divergence.actual(),
+        &json!([{"op": "replace", "path": "/a", "value": 3}])
+    );
+
+    logs_assert(|lines: &[&str]| {
+        let line = lines
+            .iter()
+            .find(|line| line.contains("task changes diverged from the planned changes"))
+            .ok_or("divergence warning not found")?;
+
+        if !line.contains("WARN") {
+            return Err(format!("divergence not reported as a warning: {line}"));
+        }
+        Ok(())
+    });
+}